@@ -1,6 +1,27 @@
+pub mod blocking;
+pub mod client;
 pub mod command;
 pub mod database;
+pub mod geohash;
+pub mod hash;
+pub mod hyperloglog;
+pub mod intset;
+pub mod object;
 pub mod query;
+pub mod scan;
 pub mod serializer;
+pub mod set;
+pub mod settings;
+pub mod skiplist;
+pub mod sorted_set;
+pub mod stream;
 
+pub mod geohash_spec;
+pub mod hash_spec;
+pub mod hyperloglog_spec;
+pub mod intset_spec;
+pub mod object_spec;
+pub mod scan_spec;
 pub mod serializer_spec;
+pub mod skiplist_spec;
+pub mod stream_spec;
@@ -0,0 +1,258 @@
+use once_cell::sync::Lazy;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Condvar, Mutex, Once},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{client::Client, database::Database, serializer};
+
+/*
+ * blocking commands (BLPOP & co.) work the same way as in redis:
+ *
+ * 1. the command is tried right away. if it can be served, it replies as
+ *    usual and nothing here is involved
+ * 2. otherwise, while still holding the database lock, the client is added to
+ *    the queue of every key it waits on, along with a `ServeFn` that knows how
+ *    to run the command against one of those keys
+ * 3. once the command returns, the connection is parked here and its thread
+ *    ends. a blocked client holds no thread and no lock
+ * 4. when a write creates one of those keys, the database marks it as ready.
+ *    before the writer releases the database lock, the waiting clients are
+ *    served in FIFO order, and each of them resumes on a new thread starting
+ *    with its reply
+ * 5. a single timer thread replies to the clients whose timeout is over, and
+ *    drops the ones that disconnected while waiting
+ *
+ * lock order is always DB -> REGISTRY. the registry is never held while
+ * trying to take the database lock
+ */
+
+/*
+ * runs the blocked command against the key that became ready. `None` means
+ * there was nothing to serve and the client should keep waiting
+ */
+pub type ServeFn = Box<dyn FnMut(&mut Database, &str) -> Option<Vec<u8>> + Send>;
+
+// how often the timer checks whether the parked clients are still connected
+static CONNECTION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+struct BlockedClient {
+    keys: Vec<String>,
+    serve: ServeFn,
+    deadline: Option<Instant>,
+    // `None` until the connection thread parks the client
+    conn: Option<Client>,
+}
+
+struct Registry {
+    clients: HashMap<u64, BlockedClient>,
+    keys: HashMap<String, VecDeque<u64>>,
+    // replies to clients that were served before they could be parked
+    early: HashMap<u64, Vec<u8>>,
+}
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| {
+    Mutex::new(Registry {
+        clients: HashMap::new(),
+        keys: HashMap::new(),
+        early: HashMap::new(),
+    })
+});
+
+// wakes the timer up when a client blocks, its deadline may be the next one
+static TIMER: Condvar = Condvar::new();
+static TIMER_STARTED: Once = Once::new();
+
+impl Registry {
+    fn remove(&mut self, client_id: u64) -> Option<BlockedClient> {
+        let client = self.clients.remove(&client_id)?;
+
+        for key in &client.keys {
+            if let Some(queue) = self.keys.get_mut(key) {
+                queue.retain(|id| *id != client_id);
+
+                if queue.is_empty() {
+                    self.keys.remove(key);
+                }
+            }
+        }
+
+        Some(client)
+    }
+
+    /*
+     * unblocks the client with `reply`. without a reply the connection is
+     * dropped, which closes it
+     */
+    fn unblock(&mut self, client_id: u64, reply: Option<Vec<u8>>) {
+        let client = match self.remove(client_id) {
+            Some(client) => client,
+            None => return,
+        };
+
+        match (client.conn, reply) {
+            (Some(conn), Some(reply)) => {
+                thread::spawn(move || conn.resume(reply));
+            }
+            (None, Some(reply)) => {
+                self.early.insert(client_id, reply);
+            }
+            (_, None) => {}
+        }
+    }
+
+    fn serve(&mut self, db: &mut Database, key: &str) {
+        let waiting: Vec<u64> = match self.keys.get(key) {
+            Some(queue) => queue.iter().copied().collect(),
+            None => return,
+        };
+
+        /*
+         * in FIFO order, but every client gets its turn: one that can not be
+         * served (XREAD on a key that turned out to be a list, ...) must not
+         * hold back the ones behind it
+         */
+        for client_id in waiting {
+            // served through another key in the meantime
+            let client = match self.clients.get_mut(&client_id) {
+                Some(client) => client,
+                None => continue,
+            };
+
+            // nothing may be popped for a client that is gone
+            if client
+                .conn
+                .as_ref()
+                .is_some_and(|conn| !conn.is_connected())
+            {
+                self.unblock(client_id, None);
+                continue;
+            }
+
+            if let Some(reply) = (client.serve)(db, key) {
+                self.unblock(client_id, Some(reply));
+            }
+        }
+    }
+
+    /*
+     * times out the clients whose deadline is over, and drops the parked ones
+     * that disconnected. returns the next deadline
+     */
+    fn expire(&mut self, now: Instant) -> Option<Instant> {
+        let mut done = vec![];
+        let mut next: Option<Instant> = None;
+
+        for (client_id, client) in &self.clients {
+            match client.deadline {
+                Some(deadline) if deadline <= now => {
+                    done.push((*client_id, Some(serializer::null_arr())))
+                }
+                _ if client
+                    .conn
+                    .as_ref()
+                    .is_some_and(|conn| !conn.is_connected()) =>
+                {
+                    done.push((*client_id, None))
+                }
+                Some(deadline) => next = Some(next.map_or(deadline, |next| next.min(deadline))),
+                None => {}
+            }
+        }
+
+        for (client_id, reply) in done {
+            self.unblock(client_id, reply);
+        }
+
+        next
+    }
+}
+
+fn run_timer() {
+    let mut registry = REGISTRY.lock().unwrap();
+
+    loop {
+        let now = Instant::now();
+
+        let wait = match registry.expire(now) {
+            Some(deadline) => (deadline - now).min(CONNECTION_CHECK_INTERVAL),
+            None => CONNECTION_CHECK_INTERVAL,
+        };
+
+        registry = TIMER.wait_timeout(registry, wait).unwrap().0;
+    }
+}
+
+/*
+ * registers `client` as waiting on `keys` for `timeout` (`None` = forever).
+ * taking `&mut Database` makes sure the caller holds the database lock, so no
+ * write can slip in between the failed attempt and the registration
+ */
+pub fn block(
+    _db: &mut Database,
+    client: &Client,
+    keys: &[String],
+    timeout: Option<Duration>,
+    serve: ServeFn,
+) {
+    TIMER_STARTED.call_once(|| {
+        thread::spawn(run_timer);
+    });
+
+    let mut registry = REGISTRY.lock().unwrap();
+
+    for key in keys {
+        let queue = registry.keys.entry(key.to_string()).or_default();
+
+        // BLPOP k k ... waits on `k` only once
+        if !queue.contains(&client.id) {
+            queue.push_back(client.id);
+        }
+    }
+
+    registry.clients.insert(
+        client.id,
+        BlockedClient {
+            keys: keys.to_vec(),
+            serve,
+            // a timeout too far away to be represented never expires
+            deadline: timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+            conn: None,
+        },
+    );
+
+    client.block();
+    TIMER.notify_one();
+}
+
+/*
+ * hands the connection of a blocked client over, once its command returned.
+ * when it was already served in the meantime, it is given back along with
+ * the reply instead
+ */
+pub fn park(client: Client) -> Option<(Client, Vec<u8>)> {
+    let mut registry = REGISTRY.lock().unwrap();
+
+    if let Some(reply) = registry.early.remove(&client.id) {
+        return Some((client, reply));
+    }
+
+    if let Some(blocked) = registry.clients.get_mut(&client.id) {
+        blocked.conn = Some(client);
+    }
+
+    None
+}
+
+// serves the clients waiting on the keys that were marked as ready
+pub fn handle_ready_keys(db: &mut Database) {
+    while let Some(key) = db.next_ready_key() {
+        let mut registry = REGISTRY.lock().unwrap();
+
+        if registry.keys.contains_key(&key) {
+            registry.serve(db, &key);
+        }
+    }
+}
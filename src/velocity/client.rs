@@ -0,0 +1,141 @@
+use std::{
+    cell::Cell,
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{blocking, query::Query};
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /*
+     * CLIENT NO-TOUCH. it is read deep down in the database, where there is
+     * no `Client` at hand, but always on the thread serving the connection
+     */
+    static NO_TOUCH: Cell<bool> = const { Cell::new(false) };
+}
+
+// whether the client being served asked not to alter the access time of keys
+pub fn no_touch() -> bool {
+    NO_TOUCH.with(|flag| flag.get())
+}
+
+/*
+ * per connection state. a connection is served by a thread of its own, which
+ * owns the `Client` until the connection is closed or a command blocks. a
+ * blocked client is handed over to `blocking`, and gets a new thread once it
+ * is served
+ */
+#[derive(Debug)]
+pub struct Client {
+    pub id: u64,
+    stream: TcpStream,
+    // kept here too, to survive the move to another thread
+    no_touch: Cell<bool>,
+    blocked: Cell<bool>,
+}
+
+impl Client {
+    pub fn new(stream: TcpStream) -> Client {
+        Client {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            stream,
+            no_touch: Cell::new(false),
+            blocked: Cell::new(false),
+        }
+    }
+
+    // reads and answers commands until the connection is closed or blocks
+    pub fn run(mut self) {
+        let mut buffer = vec![0; 1024 * 100]; // 100kb buffer
+
+        NO_TOUCH.with(|flag| flag.set(self.no_touch.get()));
+
+        loop {
+            let response = match (&self.stream).read(&mut buffer) {
+                Ok(size) if size > 0 => {
+                    let query = Query::new(&buffer[..size]);
+                    query.create_response(&self)
+                }
+
+                Ok(_) => {
+                    println!("Client closed the connection");
+                    break;
+                }
+
+                Err(e) => {
+                    println!("Error reading from stream: {}", e);
+                    break;
+                }
+            };
+
+            let response = match self.blocked.get() {
+                false => response,
+                true => match blocking::park(self) {
+                    // served before it was even parked
+                    Some((client, reply)) => {
+                        self = client;
+                        self.blocked.set(false);
+                        reply
+                    }
+                    None => return,
+                },
+            };
+
+            if (&self.stream).write_all(&response).is_err() {
+                println!("Failed to write to stream");
+                break;
+            }
+        }
+    }
+
+    // sends the reply to the command the client was blocked on, then carries on
+    pub fn resume(self, reply: Vec<u8>) {
+        self.blocked.set(false);
+
+        if (&self.stream).write_all(&reply).is_err() {
+            println!("Failed to write to stream");
+            return;
+        }
+
+        self.run();
+    }
+
+    /*
+     * the command being run could not be served right away. its (empty)
+     * response is not sent, and the connection is parked once it returns
+     */
+    pub fn block(&self) {
+        self.blocked.set(true);
+    }
+
+    /*
+     * a blocked client is not reading from its socket, so this is the only way
+     * to notice it went away while waiting
+     */
+    pub fn is_connected(&self) -> bool {
+        let mut buffer = [0; 1];
+
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+
+        let connected = match self.stream.peek(&mut buffer) {
+            Ok(0) => false,
+            Ok(_) => true,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => true,
+            Err(_) => false,
+        };
+
+        let _ = self.stream.set_nonblocking(false);
+
+        connected
+    }
+
+    pub fn set_no_touch(&self, on: bool) {
+        self.no_touch.set(on);
+        NO_TOUCH.with(|flag| flag.set(on));
+    }
+}
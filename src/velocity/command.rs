@@ -1,6 +1,36 @@
 use glob::Pattern;
+use std::time::Duration;
+
+mod geo;
+mod hash;
+mod hyperloglog;
+mod keyspace;
+mod list;
+mod set;
+mod sort;
+mod sorted_set;
+mod stream;
+
+mod blocking_spec;
+mod geo_spec;
+mod hash_spec;
+mod hyperloglog_spec;
+mod keyspace_spec;
+mod list_spec;
+mod set_spec;
+mod sort_spec;
+mod sorted_set_spec;
+mod spec_helper;
+mod stream_spec;
+
+use geo::GeoSearchCmd;
+use hash::FieldTtl;
+use list::ListEnd;
+use set::SetOp;
+use sorted_set::{PopEnd, RangeBy, ZSetOp};
 
 use super::{
+    client::Client,
     database::{DataTypes, DatabaseOps},
     query::Query,
     serializer,
@@ -40,9 +70,147 @@ pub enum Command {
     Set,
     Keys,
     Delete,
+    Unlink,
+    Type,
+    Rename,
+    RenameNx,
+    Copy,
+    Touch,
+    RandomKey,
+    Object,
+    Client,
+    Sort,
+    SortRo,
     Exists,
     Incr,
     Decr,
+    LPush,
+    RPush,
+    LPushX,
+    RPushX,
+    LPop,
+    RPop,
+    LRange,
+    LLen,
+    LIndex,
+    LSet,
+    LInsert,
+    LRem,
+    LTrim,
+    LPos,
+    LMove,
+    RPopLPush,
+    LMPop,
+    BLPop,
+    BRPop,
+    BLMove,
+    BLMPop,
+    HSet,
+    HGet,
+    HMGet,
+    HDel,
+    HExists,
+    HLen,
+    HKeys,
+    HVals,
+    HGetAll,
+    HSetNx,
+    HStrLen,
+    HIncrBy,
+    HIncrByFloat,
+    HScan,
+    HRandField,
+    HExpire,
+    HPExpire,
+    HExpireAt,
+    HPExpireAt,
+    HTtl,
+    HPTtl,
+    HExpireTime,
+    HPExpireTime,
+    HPersist,
+    HGetEx,
+    HSetEx,
+    SAdd,
+    SRem,
+    SIsMember,
+    SMIsMember,
+    SMembers,
+    SCard,
+    SMove,
+    SInter,
+    SUnion,
+    SDiff,
+    SInterStore,
+    SUnionStore,
+    SDiffStore,
+    SInterCard,
+    SPop,
+    SRandMember,
+    SScan,
+    ZAdd,
+    ZIncrBy,
+    ZScore,
+    ZMScore,
+    ZRem,
+    ZCard,
+    ZCount,
+    ZRank,
+    ZRevRank,
+    ZPopMin,
+    ZPopMax,
+    ZRange,
+    ZRevRange,
+    ZRangeByScore,
+    ZRevRangeByScore,
+    ZRangeByLex,
+    ZRevRangeByLex,
+    ZRangeStore,
+    ZLexCount,
+    ZRemRangeByRank,
+    ZRemRangeByScore,
+    ZRemRangeByLex,
+    ZUnion,
+    ZInter,
+    ZDiff,
+    ZUnionStore,
+    ZInterStore,
+    ZDiffStore,
+    ZInterCard,
+    ZMPop,
+    BZPopMin,
+    BZPopMax,
+    BZMPop,
+    ZRandMember,
+    ZScan,
+    XAdd,
+    XRange,
+    XRevRange,
+    XLen,
+    XDel,
+    XTrim,
+    XSetId,
+    XRead,
+    XGroup,
+    XReadGroup,
+    XAck,
+    XPending,
+    XClaim,
+    XAutoClaim,
+    XInfo,
+    PFAdd,
+    PFCount,
+    PFMerge,
+    GeoAdd,
+    GeoDist,
+    GeoPos,
+    GeoHash,
+    GeoSearch,
+    GeoSearchStore,
+    GeoRadius,
+    GeoRadiusByMember,
+    GeoRadiusRo,
+    GeoRadiusByMemberRo,
     Unknown,
 }
 
@@ -55,38 +223,573 @@ impl Command {
             "set" => Command::Set,
             "keys" => Command::Keys,
             "del" => Command::Delete,
+            "unlink" => Command::Unlink,
+            "type" => Command::Type,
+            "rename" => Command::Rename,
+            "renamenx" => Command::RenameNx,
+            "copy" => Command::Copy,
+            "touch" => Command::Touch,
+            "randomkey" => Command::RandomKey,
+            "object" => Command::Object,
+            "client" => Command::Client,
+            "sort" => Command::Sort,
+            "sort_ro" => Command::SortRo,
             "exists" => Command::Exists,
             "incr" => Command::Incr,
             "decr" => Command::Decr,
+            "lpush" => Command::LPush,
+            "rpush" => Command::RPush,
+            "lpushx" => Command::LPushX,
+            "rpushx" => Command::RPushX,
+            "lpop" => Command::LPop,
+            "rpop" => Command::RPop,
+            "lrange" => Command::LRange,
+            "llen" => Command::LLen,
+            "lindex" => Command::LIndex,
+            "lset" => Command::LSet,
+            "linsert" => Command::LInsert,
+            "lrem" => Command::LRem,
+            "ltrim" => Command::LTrim,
+            "lpos" => Command::LPos,
+            "lmove" => Command::LMove,
+            "rpoplpush" => Command::RPopLPush,
+            "lmpop" => Command::LMPop,
+            "blpop" => Command::BLPop,
+            "brpop" => Command::BRPop,
+            "blmove" => Command::BLMove,
+            "blmpop" => Command::BLMPop,
+            "hset" => Command::HSet,
+            "hget" => Command::HGet,
+            "hmget" => Command::HMGet,
+            "hdel" => Command::HDel,
+            "hexists" => Command::HExists,
+            "hlen" => Command::HLen,
+            "hkeys" => Command::HKeys,
+            "hvals" => Command::HVals,
+            "hgetall" => Command::HGetAll,
+            "hsetnx" => Command::HSetNx,
+            "hstrlen" => Command::HStrLen,
+            "hincrby" => Command::HIncrBy,
+            "hincrbyfloat" => Command::HIncrByFloat,
+            "hscan" => Command::HScan,
+            "hrandfield" => Command::HRandField,
+            "hexpire" => Command::HExpire,
+            "hpexpire" => Command::HPExpire,
+            "hexpireat" => Command::HExpireAt,
+            "hpexpireat" => Command::HPExpireAt,
+            "httl" => Command::HTtl,
+            "hpttl" => Command::HPTtl,
+            "hexpiretime" => Command::HExpireTime,
+            "hpexpiretime" => Command::HPExpireTime,
+            "hpersist" => Command::HPersist,
+            "hgetex" => Command::HGetEx,
+            "hsetex" => Command::HSetEx,
+            "sadd" => Command::SAdd,
+            "srem" => Command::SRem,
+            "sismember" => Command::SIsMember,
+            "smismember" => Command::SMIsMember,
+            "smembers" => Command::SMembers,
+            "scard" => Command::SCard,
+            "smove" => Command::SMove,
+            "sinter" => Command::SInter,
+            "sunion" => Command::SUnion,
+            "sdiff" => Command::SDiff,
+            "sinterstore" => Command::SInterStore,
+            "sunionstore" => Command::SUnionStore,
+            "sdiffstore" => Command::SDiffStore,
+            "sintercard" => Command::SInterCard,
+            "spop" => Command::SPop,
+            "srandmember" => Command::SRandMember,
+            "sscan" => Command::SScan,
+            "zadd" => Command::ZAdd,
+            "zincrby" => Command::ZIncrBy,
+            "zscore" => Command::ZScore,
+            "zmscore" => Command::ZMScore,
+            "zrem" => Command::ZRem,
+            "zcard" => Command::ZCard,
+            "zcount" => Command::ZCount,
+            "zrank" => Command::ZRank,
+            "zrevrank" => Command::ZRevRank,
+            "zpopmin" => Command::ZPopMin,
+            "zpopmax" => Command::ZPopMax,
+            "zrange" => Command::ZRange,
+            "zrevrange" => Command::ZRevRange,
+            "zrangebyscore" => Command::ZRangeByScore,
+            "zrevrangebyscore" => Command::ZRevRangeByScore,
+            "zrangebylex" => Command::ZRangeByLex,
+            "zrevrangebylex" => Command::ZRevRangeByLex,
+            "zrangestore" => Command::ZRangeStore,
+            "zlexcount" => Command::ZLexCount,
+            "zremrangebyrank" => Command::ZRemRangeByRank,
+            "zremrangebyscore" => Command::ZRemRangeByScore,
+            "zremrangebylex" => Command::ZRemRangeByLex,
+            "zunion" => Command::ZUnion,
+            "zinter" => Command::ZInter,
+            "zdiff" => Command::ZDiff,
+            "zunionstore" => Command::ZUnionStore,
+            "zinterstore" => Command::ZInterStore,
+            "zdiffstore" => Command::ZDiffStore,
+            "zintercard" => Command::ZInterCard,
+            "zmpop" => Command::ZMPop,
+            "bzpopmin" => Command::BZPopMin,
+            "bzpopmax" => Command::BZPopMax,
+            "bzmpop" => Command::BZMPop,
+            "zrandmember" => Command::ZRandMember,
+            "zscan" => Command::ZScan,
+            "xadd" => Command::XAdd,
+            "xrange" => Command::XRange,
+            "xrevrange" => Command::XRevRange,
+            "xlen" => Command::XLen,
+            "xdel" => Command::XDel,
+            "xtrim" => Command::XTrim,
+            "xsetid" => Command::XSetId,
+            "xread" => Command::XRead,
+            "xgroup" => Command::XGroup,
+            "xreadgroup" => Command::XReadGroup,
+            "xack" => Command::XAck,
+            "xpending" => Command::XPending,
+            "xclaim" => Command::XClaim,
+            "xautoclaim" => Command::XAutoClaim,
+            "xinfo" => Command::XInfo,
+            "pfadd" => Command::PFAdd,
+            "pfcount" => Command::PFCount,
+            "pfmerge" => Command::PFMerge,
+            "geoadd" => Command::GeoAdd,
+            "geodist" => Command::GeoDist,
+            "geopos" => Command::GeoPos,
+            "geohash" => Command::GeoHash,
+            "geosearch" => Command::GeoSearch,
+            "geosearchstore" => Command::GeoSearchStore,
+            "georadius" => Command::GeoRadius,
+            "georadiusbymember" => Command::GeoRadiusByMember,
+            "georadius_ro" => Command::GeoRadiusRo,
+            "georadiusbymember_ro" => Command::GeoRadiusByMemberRo,
             _ => Command::Unknown,
         }
     }
 
-    pub fn create_response(&self, query: &Query) -> Vec<u8> {
+    pub fn create_response(&self, query: &Query, client: &Client) -> Vec<u8> {
         match self {
             Command::Ping => serializer::str("PONG "),
             Command::Get => handle_get(query),
             Command::Echo => handle_echo(query),
             Command::Set => handle_set(query),
             Command::Keys => handle_keys(query),
-            Command::Delete => handle_delete(query),
+            Command::Delete => keyspace::handle_del(query, "del"),
+            Command::Unlink => keyspace::handle_del(query, "unlink"),
+            Command::Type => keyspace::handle_type(query),
+            Command::Rename => keyspace::handle_rename(query, "rename", false),
+            Command::RenameNx => keyspace::handle_rename(query, "renamenx", true),
+            Command::Copy => keyspace::handle_copy(query),
+            Command::Touch => keyspace::handle_touch(query),
+            Command::RandomKey => keyspace::handle_randomkey(query),
+            Command::Object => keyspace::handle_object(query),
+            Command::Client => handle_client(query, client),
+            Command::Sort => sort::handle_sort(query, false),
+            Command::SortRo => sort::handle_sort(query, true),
             Command::Exists => handle_exists(query),
             Command::Incr => handle_incr_decr(query, IncrDecrOpts::Incr),
             Command::Decr => handle_incr_decr(query, IncrDecrOpts::Decr),
+            Command::LPush => list::handle_push(query, ListEnd::Left, false),
+            Command::RPush => list::handle_push(query, ListEnd::Right, false),
+            Command::LPushX => list::handle_push(query, ListEnd::Left, true),
+            Command::RPushX => list::handle_push(query, ListEnd::Right, true),
+            Command::LPop => list::handle_pop(query, ListEnd::Left),
+            Command::RPop => list::handle_pop(query, ListEnd::Right),
+            Command::LRange => list::handle_lrange(query),
+            Command::LLen => list::handle_llen(query),
+            Command::LIndex => list::handle_lindex(query),
+            Command::LSet => list::handle_lset(query),
+            Command::LInsert => list::handle_linsert(query),
+            Command::LRem => list::handle_lrem(query),
+            Command::LTrim => list::handle_ltrim(query),
+            Command::LPos => list::handle_lpos(query),
+            Command::LMove => list::handle_lmove(query),
+            Command::RPopLPush => list::handle_rpoplpush(query),
+            Command::LMPop => list::handle_lmpop(query),
+            Command::BLPop => list::handle_bpop(query, client, ListEnd::Left),
+            Command::BRPop => list::handle_bpop(query, client, ListEnd::Right),
+            Command::BLMove => list::handle_blmove(query, client),
+            Command::BLMPop => list::handle_blmpop(query, client),
+            Command::HSet => hash::handle_hset(query),
+            Command::HGet => hash::handle_hget(query),
+            Command::HMGet => hash::handle_hmget(query),
+            Command::HDel => hash::handle_hdel(query),
+            Command::HExists => hash::handle_hexists(query),
+            Command::HLen => hash::handle_hlen(query),
+            Command::HKeys => hash::handle_hkeys(query),
+            Command::HVals => hash::handle_hvals(query),
+            Command::HGetAll => hash::handle_hgetall(query),
+            Command::HSetNx => hash::handle_hsetnx(query),
+            Command::HStrLen => hash::handle_hstrlen(query),
+            Command::HIncrBy => hash::handle_hincrby(query),
+            Command::HIncrByFloat => hash::handle_hincrbyfloat(query),
+            Command::HScan => hash::handle_hscan(query),
+            Command::HRandField => hash::handle_hrandfield(query),
+            Command::HExpire => hash::handle_hexpire(query, "hexpire", "EX"),
+            Command::HPExpire => hash::handle_hexpire(query, "hpexpire", "PX"),
+            Command::HExpireAt => hash::handle_hexpire(query, "hexpireat", "EXAT"),
+            Command::HPExpireAt => hash::handle_hexpire(query, "hpexpireat", "PXAT"),
+            Command::HTtl => hash::handle_httl(query, "httl", FieldTtl::Ttl),
+            Command::HPTtl => hash::handle_httl(query, "hpttl", FieldTtl::PTtl),
+            Command::HExpireTime => hash::handle_httl(query, "hexpiretime", FieldTtl::ExpireTime),
+            Command::HPExpireTime => {
+                hash::handle_httl(query, "hpexpiretime", FieldTtl::PExpireTime)
+            }
+            Command::HPersist => hash::handle_hpersist(query),
+            Command::HGetEx => hash::handle_hgetex(query),
+            Command::HSetEx => hash::handle_hsetex(query),
+            Command::SAdd => set::handle_sadd(query),
+            Command::SRem => set::handle_srem(query),
+            Command::SIsMember => set::handle_sismember(query),
+            Command::SMIsMember => set::handle_smismember(query),
+            Command::SMembers => set::handle_smembers(query),
+            Command::SCard => set::handle_scard(query),
+            Command::SMove => set::handle_smove(query),
+            Command::SInter => set::handle_set_op(query, "sinter", SetOp::Inter),
+            Command::SUnion => set::handle_set_op(query, "sunion", SetOp::Union),
+            Command::SDiff => set::handle_set_op(query, "sdiff", SetOp::Diff),
+            Command::SInterStore => set::handle_set_op_store(query, "sinterstore", SetOp::Inter),
+            Command::SUnionStore => set::handle_set_op_store(query, "sunionstore", SetOp::Union),
+            Command::SDiffStore => set::handle_set_op_store(query, "sdiffstore", SetOp::Diff),
+            Command::SInterCard => set::handle_sintercard(query),
+            Command::SPop => set::handle_spop(query),
+            Command::SRandMember => set::handle_srandmember(query),
+            Command::SScan => set::handle_sscan(query),
+            Command::ZAdd => sorted_set::handle_zadd(query),
+            Command::ZIncrBy => sorted_set::handle_zincrby(query),
+            Command::ZScore => sorted_set::handle_zscore(query),
+            Command::ZMScore => sorted_set::handle_zmscore(query),
+            Command::ZRem => sorted_set::handle_zrem(query),
+            Command::ZCard => sorted_set::handle_zcard(query),
+            Command::ZCount => sorted_set::handle_zcount(query),
+            Command::ZRank => sorted_set::handle_zrank(query, "zrank", false),
+            Command::ZRevRank => sorted_set::handle_zrank(query, "zrevrank", true),
+            Command::ZPopMin => sorted_set::handle_zpop(query, "zpopmin", PopEnd::Min),
+            Command::ZPopMax => sorted_set::handle_zpop(query, "zpopmax", PopEnd::Max),
+            Command::ZRange => sorted_set::handle_zrange(query, "zrange", None, None),
+            Command::ZRevRange => {
+                sorted_set::handle_zrange(query, "zrevrange", Some(RangeBy::Rank), Some(true))
+            }
+            Command::ZRangeByScore => {
+                sorted_set::handle_zrange(query, "zrangebyscore", Some(RangeBy::Score), Some(false))
+            }
+            Command::ZRevRangeByScore => sorted_set::handle_zrange(
+                query,
+                "zrevrangebyscore",
+                Some(RangeBy::Score),
+                Some(true),
+            ),
+            Command::ZRangeByLex => {
+                sorted_set::handle_zrange(query, "zrangebylex", Some(RangeBy::Lex), Some(false))
+            }
+            Command::ZRevRangeByLex => {
+                sorted_set::handle_zrange(query, "zrevrangebylex", Some(RangeBy::Lex), Some(true))
+            }
+            Command::ZRangeStore => sorted_set::handle_zrangestore(query),
+            Command::ZLexCount => sorted_set::handle_zlexcount(query),
+            Command::ZRemRangeByRank => {
+                sorted_set::handle_zremrange(query, "zremrangebyrank", RangeBy::Rank)
+            }
+            Command::ZRemRangeByScore => {
+                sorted_set::handle_zremrange(query, "zremrangebyscore", RangeBy::Score)
+            }
+            Command::ZRemRangeByLex => {
+                sorted_set::handle_zremrange(query, "zremrangebylex", RangeBy::Lex)
+            }
+            Command::ZUnion => sorted_set::handle_zset_op(query, "zunion", ZSetOp::Union),
+            Command::ZInter => sorted_set::handle_zset_op(query, "zinter", ZSetOp::Inter),
+            Command::ZDiff => sorted_set::handle_zset_op(query, "zdiff", ZSetOp::Diff),
+            Command::ZUnionStore => {
+                sorted_set::handle_zset_op_store(query, "zunionstore", ZSetOp::Union)
+            }
+            Command::ZInterStore => {
+                sorted_set::handle_zset_op_store(query, "zinterstore", ZSetOp::Inter)
+            }
+            Command::ZDiffStore => {
+                sorted_set::handle_zset_op_store(query, "zdiffstore", ZSetOp::Diff)
+            }
+            Command::ZInterCard => sorted_set::handle_zintercard(query),
+            Command::ZMPop => sorted_set::handle_zmpop(query),
+            Command::BZPopMin => sorted_set::handle_bzpop(query, client, "bzpopmin", PopEnd::Min),
+            Command::BZPopMax => sorted_set::handle_bzpop(query, client, "bzpopmax", PopEnd::Max),
+            Command::BZMPop => sorted_set::handle_bzmpop(query, client),
+            Command::ZRandMember => sorted_set::handle_zrandmember(query),
+            Command::ZScan => sorted_set::handle_zscan(query),
+            Command::XAdd => stream::handle_xadd(query),
+            Command::XRange => stream::handle_xrange(query, "xrange", false),
+            Command::XRevRange => stream::handle_xrange(query, "xrevrange", true),
+            Command::XLen => stream::handle_xlen(query),
+            Command::XDel => stream::handle_xdel(query),
+            Command::XTrim => stream::handle_xtrim(query),
+            Command::XSetId => stream::handle_xsetid(query),
+            Command::XRead => stream::handle_xread(query, client, "xread"),
+            Command::XGroup => stream::handle_xgroup(query),
+            Command::XReadGroup => stream::handle_xreadgroup(query, client),
+            Command::XAck => stream::handle_xack(query),
+            Command::XPending => stream::handle_xpending(query),
+            Command::XClaim => stream::handle_xclaim(query),
+            Command::XAutoClaim => stream::handle_xautoclaim(query),
+            Command::XInfo => stream::handle_xinfo(query),
+            Command::PFAdd => hyperloglog::handle_pfadd(query),
+            Command::PFCount => hyperloglog::handle_pfcount(query),
+            Command::PFMerge => hyperloglog::handle_pfmerge(query),
+            Command::GeoAdd => geo::handle_geoadd(query),
+            Command::GeoDist => geo::handle_geodist(query),
+            Command::GeoPos => geo::handle_geopos(query),
+            Command::GeoHash => geo::handle_geohash(query),
+            Command::GeoSearch => geo::handle_geosearch(query, GeoSearchCmd::Search),
+            Command::GeoSearchStore => geo::handle_geosearch(query, GeoSearchCmd::SearchStore),
+            Command::GeoRadius => geo::handle_geosearch(query, GeoSearchCmd::Radius),
+            Command::GeoRadiusByMember => {
+                geo::handle_geosearch(query, GeoSearchCmd::RadiusByMember)
+            }
+            Command::GeoRadiusRo => geo::handle_geosearch(query, GeoSearchCmd::RadiusRo),
+            Command::GeoRadiusByMemberRo => {
+                geo::handle_geosearch(query, GeoSearchCmd::RadiusByMemberRo)
+            }
             Command::Unknown => serializer::err("Err Unknown command"),
         }
     }
 }
 
+fn wrong_args(command: &str) -> Vec<u8> {
+    let msg = format!("ERR wrong number of arguments for '{}' command", command);
+    serializer::err(&msg)
+}
+
+fn wrong_type() -> Vec<u8> {
+    serializer::err("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+fn not_an_integer() -> Vec<u8> {
+    serializer::err("ERR value is not an integer or out of range")
+}
+
+fn syntax_error() -> Vec<u8> {
+    serializer::err("ERR syntax error")
+}
+
+/*
+ * the count of HRANDFIELD & co. as redis, a negative count asking for values
+ * too is kept within half the range, so that the size of the reply fits
+ */
+fn parse_rand_count(value: &str, with_values: bool) -> Result<i64, Vec<u8>> {
+    match value.parse::<i64>() {
+        Ok(i64::MIN) => Err(serializer::err("ERR value is out of range")),
+        Ok(count) if with_values && count < -(i64::MAX / 2) => {
+            Err(serializer::err("ERR value is out of range"))
+        }
+        Ok(count) => Ok(count),
+        Err(_) => Err(not_an_integer()),
+    }
+}
+
+// like `str::parse`, but NaN is never a valid value in redis
+fn parse_float(value: &str) -> Option<f64> {
+    match value.parse::<f64>() {
+        Ok(value) if !value.is_nan() => Some(value),
+        _ => None,
+    }
+}
+
+/*
+ * formats a float the way redis replies with it: the shortest representation
+ * that parses back to the same value, `inf`/`-inf` for infinities and an
+ * exponent for very large or very small values
+ */
+fn format_float(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+
+    let abs = value.abs();
+
+    if abs == 0.0 || (1e-5..1e17).contains(&abs) {
+        return value.to_string();
+    }
+
+    let formatted = format!("{:e}", value);
+
+    match formatted.split_once('e') {
+        Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+            format!("{}e+{}", mantissa, exponent)
+        }
+        _ => formatted,
+    }
+}
+
+#[derive(Debug)]
+struct ScanOpts {
+    cursor: u64,
+    pattern: Option<Pattern>,
+    count: usize,
+    novalues: bool,
+}
+
+impl ScanOpts {
+    fn matches(&self, name: &str) -> bool {
+        match &self.pattern {
+            Some(pattern) => pattern.matches(name),
+            None => true,
+        }
+    }
+}
+
+// parses `cursor [MATCH pattern] [COUNT count]`, plus `NOVALUES` for HSCAN
+fn parse_scan_args(args: &[String], allow_novalues: bool) -> Result<ScanOpts, Vec<u8>> {
+    let cursor = match args[0].parse::<u64>() {
+        Ok(cursor) => cursor,
+        Err(_) => return Err(serializer::err("ERR invalid cursor")),
+    };
+
+    let mut opts = ScanOpts {
+        cursor,
+        pattern: None,
+        count: 10,
+        novalues: false,
+    };
+
+    let mut args = args[1..].iter();
+
+    while let Some(arg) = args.next() {
+        match arg.to_uppercase().as_str() {
+            "MATCH" => {
+                let pattern = match args.next() {
+                    Some(pattern) => pattern,
+                    None => return Err(syntax_error()),
+                };
+
+                // anything glob can not parse is matched literally
+                let pattern = Pattern::new(pattern)
+                    .unwrap_or_else(|_| Pattern::new(&Pattern::escape(pattern)).unwrap());
+
+                opts.pattern = Some(pattern);
+            }
+
+            "COUNT" => {
+                opts.count = match args.next().map(|count| count.parse::<i64>()) {
+                    Some(Ok(count)) if count >= 1 => count as usize,
+                    Some(Ok(_)) | None => return Err(syntax_error()),
+                    Some(Err(_)) => return Err(not_an_integer()),
+                };
+            }
+
+            "NOVALUES" if allow_novalues => opts.novalues = true,
+
+            _ => return Err(syntax_error()),
+        }
+    }
+
+    Ok(opts)
+}
+
+fn scan_reply(cursor: u64, items: &[String]) -> Vec<u8> {
+    serializer::arr(&[
+        serializer::bulk_str(&cursor.to_string()),
+        serializer::str_arr(items),
+    ])
+}
+
+/*
+ * clamps `start` and `stop` the way LRANGE does. returns `None` when the
+ * range does not contain a single element
+ */
+fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let mut start = if start < 0 { len + start } else { start };
+    let mut stop = if stop < 0 { len + stop } else { stop };
+
+    if start < 0 {
+        start = 0;
+    }
+
+    if stop >= len {
+        stop = len - 1;
+    }
+
+    if start > stop || start >= len {
+        return None;
+    }
+
+    Some((start as usize, stop as usize))
+}
+
+/*
+ * the outcome of the first, non-blocking, attempt of a blocking command:
+ * either a reply, or the client got blocked and its reply is sent once it is
+ * served (see blocking.rs). the command then replies with nothing
+ */
+enum Attempt {
+    Reply(Vec<u8>),
+    Blocked,
+}
+
+/*
+ * blocking commands take their timeout in seconds, with decimals allowed.
+ * `None` means 0, which is "wait forever"
+ */
+fn parse_timeout(value: &str) -> Result<Option<Duration>, Vec<u8>> {
+    let timeout = match value.parse::<f64>() {
+        Ok(timeout) if timeout.is_finite() => timeout,
+        _ => {
+            return Err(serializer::err(
+                "ERR timeout is not a float or out of range",
+            ))
+        }
+    };
+
+    if timeout < 0.0 {
+        return Err(serializer::err("ERR timeout is negative"));
+    }
+
+    if timeout == 0.0 {
+        return Ok(None);
+    }
+
+    match Duration::try_from_secs_f64(timeout) {
+        Ok(timeout) => Ok(Some(timeout)),
+        Err(_) => Err(serializer::err("ERR timeout is out of range")),
+    }
+}
+
+// CLIENT NO-TOUCH ON|OFF, the only subcommand so far
+fn handle_client(query: &Query, client: &Client) -> Vec<u8> {
+    let subcommand = query.command_str.to_lowercase();
+    let args = &query.args;
+
+    match subcommand.as_str() {
+        "" => wrong_args("client"),
+        "no-touch" if args.len() != 1 => wrong_args("client|no-touch"),
+        "no-touch" => match args[0].to_lowercase().as_str() {
+            "on" => {
+                client.set_no_touch(true);
+                serializer::str("OK")
+            }
+            "off" => {
+                client.set_no_touch(false);
+                serializer::str("OK")
+            }
+            _ => syntax_error(),
+        },
+        _ => {
+            let msg = format!(
+                "ERR unknown subcommand '{}'. Try CLIENT HELP.",
+                query.command_str
+            );
+            serializer::err(&msg)
+        }
+    }
+}
+
 fn handle_echo(query: &Query) -> Vec<u8> {
     let value = &query.command_str;
     let value = &value[..];
-    let response = serializer::bulk_str(&value);
 
-    response
+    serializer::bulk_str(value)
 }
 
-fn parse_set_args(args: &Vec<String>) -> Result<SetCommandOpts, SetCommandParseErr> {
+fn parse_set_args(args: &[String]) -> Result<SetCommandOpts, SetCommandParseErr> {
     let mut args = args.iter();
     let mut set_command = SetCommandOpts::new();
 
@@ -220,17 +923,15 @@ fn parse_set_args(args: &Vec<String>) -> Result<SetCommandOpts, SetCommandParseE
         }
     }
 
-    return Ok(set_command);
+    Ok(set_command)
 }
 
 fn handle_set(query: &Query) -> Vec<u8> {
     let mut db = DatabaseOps;
     let key = query.command_str.to_string();
 
-    let data = &query.args;
-
-    let default_value = "".to_string();
-    let data = data.first().unwrap_or(&default_value);
+    // the value is kept as sent, it may not even be valid UTF-8
+    let data = query.raw_args.get(1).cloned().unwrap_or_default();
 
     // in `args` property, anything after the first element are the modifiers
     // for the command. For example:
@@ -252,19 +953,15 @@ fn handle_set(query: &Query) -> Vec<u8> {
 
     match parsed {
         Ok(parsed) => {
-            if parsed.ex.is_some() {
-                let ex = parsed.ex.unwrap();
+            if let Some(ex) = parsed.ex {
                 let unix_time = chrono::Utc::now().timestamp() + ex;
                 db.expire(key.clone(), unix_time + ex);
-            } else if parsed.px.is_some() {
-                let px = parsed.px.unwrap();
-                let unix_time = chrono::Utc::now().timestamp_millis() + (px as i64 / 1000);
+            } else if let Some(px) = parsed.px {
+                let unix_time = chrono::Utc::now().timestamp_millis() + (px / 1000);
                 db.expire(key.clone(), unix_time);
-            } else if parsed.exat.is_some() {
-                let exat = parsed.exat.unwrap();
+            } else if let Some(exat) = parsed.exat {
                 db.expire(key.clone(), exat);
-            } else if parsed.pxat.is_some() {
-                let pxat = parsed.pxat.unwrap();
+            } else if let Some(pxat) = parsed.pxat {
                 db.expire(key.clone(), pxat / 1000);
             }
 
@@ -272,29 +969,29 @@ fn handle_set(query: &Query) -> Vec<u8> {
                 let existing_data = db.get(key.clone());
 
                 if existing_data.is_none() {
-                    db.set(key.clone(), DataTypes::String(data.to_string()));
-                    return serializer::str("OK");
+                    db.set(key.clone(), DataTypes::String(data.clone()));
+                    serializer::str("OK")
                 } else {
-                    return serializer::nil();
+                    serializer::nil()
                 }
             } else if parsed.xx.is_some() {
                 let existing_data = db.get(key.clone());
 
                 if existing_data.is_some() {
-                    db.set(key.clone(), DataTypes::String(data.to_string()));
-                    return serializer::str("OK");
+                    db.set(key.clone(), DataTypes::String(data.clone()));
+                    serializer::str("OK")
                 } else {
-                    return serializer::nil();
+                    serializer::nil()
                 }
             } else {
-                db.set(key.clone(), DataTypes::String(data.to_string()));
+                db.set(key.clone(), DataTypes::String(data.clone()));
                 serializer::str("OK")
             }
         }
 
         Err(msg) => {
             let err_msg = format!("ERR syntax error. {}", msg.0);
-            return serializer::err(&err_msg);
+            serializer::err(&err_msg)
         }
     }
 }
@@ -305,14 +1002,13 @@ fn handle_get(query: &Query) -> Vec<u8> {
 
     let data = db.get(key);
 
-    let response = match data {
+    match data {
         Some(data) => match data {
-            DataTypes::String(data) => serializer::bulk_str(&data),
+            DataTypes::String(data) => serializer::bulk_bytes(&data),
+            _ => wrong_type(),
         },
         None => serializer::nil(),
-    };
-
-    response
+    }
 }
 
 fn handle_keys(query: &Query) -> Vec<u8> {
@@ -324,7 +1020,7 @@ fn handle_keys(query: &Query) -> Vec<u8> {
         return serializer::err("ERR wrong number of arguments for 'keys' command");
     }
 
-    if keys.len() == 0 {
+    if keys.is_empty() {
         return serializer::nil();
     }
 
@@ -341,29 +1037,17 @@ fn handle_keys(query: &Query) -> Vec<u8> {
     serializer::str_arr(&keys)
 }
 
-fn handle_delete(query: &Query) -> Vec<u8> {
-    let db = DatabaseOps;
-    let key = &query.command_str;
-    let result = db.del(key.to_string());
-
-    match result {
-        Some(_) => serializer::int(1),
-        None => serializer::int(0),
-    }
-}
-
 fn handle_exists(query: &Query) -> Vec<u8> {
-    let db = DatabaseOps;
+    let mut db = DatabaseOps;
     let mut count = 0;
     let first_key = &query.command_str; // fist key is the command itself
     let mut other_keys = query.args.as_slice().to_vec();
 
     other_keys.push(first_key.to_string());
 
+    // unlike GET, checking for a key does not count as an access
     for key in other_keys {
-        let result = db.get(key);
-
-        if result.is_some() {
+        if db.transaction(|db| db.contains_key(&key)) {
             count += 1;
         }
     }
@@ -387,10 +1071,10 @@ fn handle_incr_decr(query: &Query, ops: IncrDecrOpts) -> Vec<u8> {
     match data {
         Some(data) => match data {
             DataTypes::String(data) => {
-                let data = data.parse::<i64>();
+                let data = String::from_utf8_lossy(&data).parse::<i64>();
 
                 if data.is_err() {
-                    return serializer::err("ERR value is not an integer or out of range");
+                    return not_an_integer();
                 }
 
                 let data = data.unwrap();
@@ -400,9 +1084,13 @@ fn handle_incr_decr(query: &Query, ops: IncrDecrOpts) -> Vec<u8> {
                     IncrDecrOpts::Decr => data - 1,
                 };
 
-                db.set(key.to_string(), DataTypes::String(result.to_string()));
-                return serializer::int(result);
+                db.set(
+                    key.to_string(),
+                    DataTypes::String(result.to_string().into_bytes()),
+                );
+                serializer::int(result)
             }
+            _ => wrong_type(),
         },
         None => {
             let data_to_store = match ops {
@@ -412,10 +1100,10 @@ fn handle_incr_decr(query: &Query, ops: IncrDecrOpts) -> Vec<u8> {
 
             db.set(
                 key.to_string(),
-                DataTypes::String(data_to_store.to_string()),
+                DataTypes::String(data_to_store.to_string().into_bytes()),
             );
 
-            return serializer::int(data_to_store);
+            serializer::int(data_to_store)
         }
-    };
+    }
 }
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::super::spec_helper::*;
+
+    const WAIT: Duration = Duration::from_secs(2);
+    // long enough for a command sent before to be blocked
+    const SETTLE: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_served_by_push() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BLPOP", "blpop:push", "0"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        run(&["RPUSH", "blpop:push", "x"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "*2\r\n$10\r\nblpop:push\r\n$1\r\nx\r\n"
+        );
+
+        // the connection goes on once served
+        conn.send(&["LLEN", "blpop:push"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), ":0\r\n");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BLPOP", "blpop:timeout", "0.1"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "*-1\r\n");
+
+        conn.send(&["BRPOP", "blpop:timeout", "-1"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "-ERR timeout is negative\r\n");
+    }
+
+    #[test]
+    fn test_fifo() {
+        let mut first = Connection::open();
+        let mut second = Connection::open();
+
+        first.send(&["BLPOP", "blpop:fifo", "0"]);
+        assert_eq!(first.reply(SETTLE), None);
+        second.send(&["BLPOP", "blpop:fifo", "0"]);
+        assert_eq!(second.reply(SETTLE), None);
+
+        run(&["RPUSH", "blpop:fifo", "1", "2"]);
+        assert_eq!(
+            first.reply(WAIT).unwrap(),
+            "*2\r\n$10\r\nblpop:fifo\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            second.reply(WAIT).unwrap(),
+            "*2\r\n$10\r\nblpop:fifo\r\n$1\r\n2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BLPOP", "blpop:gone", "0"]);
+        assert_eq!(conn.reply(SETTLE), None);
+        drop(conn);
+        thread::sleep(SETTLE);
+
+        // nothing is popped for a client that went away
+        run(&["RPUSH", "blpop:gone", "x"]);
+        assert_eq!(run(&["LLEN", "blpop:gone"]), ":1\r\n");
+    }
+
+    #[test]
+    fn test_blmove() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BLMOVE", "blmove:src", "blmove:dst", "LEFT", "RIGHT", "0"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        run(&["RPUSH", "blmove:src", "x"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "$1\r\nx\r\n");
+        assert_eq!(
+            run(&["LRANGE", "blmove:dst", "0", "-1"]),
+            "*1\r\n$1\r\nx\r\n"
+        );
+        assert_eq!(run(&["EXISTS", "blmove:src"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_unservable_waiter_skipped() {
+        let mut xread = Connection::open();
+        let mut blpop = Connection::open();
+
+        xread.send(&["XREAD", "BLOCK", "300", "STREAMS", "blocked:mixed", "$"]);
+        assert_eq!(xread.reply(SETTLE), None);
+        blpop.send(&["BLPOP", "blocked:mixed", "0"]);
+        assert_eq!(blpop.reply(SETTLE), None);
+
+        // the key is a list: XREAD keeps waiting, BLPOP behind it is served
+        run(&["RPUSH", "blocked:mixed", "x"]);
+        assert_eq!(
+            blpop.reply(WAIT).unwrap(),
+            "*2\r\n$13\r\nblocked:mixed\r\n$1\r\nx\r\n"
+        );
+        assert_eq!(xread.reply(WAIT).unwrap(), "*-1\r\n");
+    }
+}
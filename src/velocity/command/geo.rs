@@ -0,0 +1,655 @@
+use super::{
+    not_an_integer, parse_float,
+    sorted_set::{zadd_one, ZAddOpts, ZAddOutcome},
+    syntax_error, wrong_args, wrong_type,
+};
+use crate::velocity::{
+    database::{DataTypes, DatabaseOps},
+    geohash::{self, Search, Shape},
+    query::Query,
+    serializer,
+    sorted_set::{ScoreBound, ScoreRange, SortedSet},
+};
+
+fn not_a_float() -> Vec<u8> {
+    serializer::err("ERR value is not a valid float")
+}
+
+// meters per unit
+fn parse_unit(unit: &str) -> Result<f64, Vec<u8>> {
+    match unit.to_lowercase().as_str() {
+        "m" => Ok(1.0),
+        "km" => Ok(1000.0),
+        "ft" => Ok(0.3048),
+        "mi" => Ok(1609.34),
+        _ => Err(serializer::err(
+            "ERR unsupported unit provided. please use M, KM, FT, MI",
+        )),
+    }
+}
+
+fn parse_lonlat(longitude: &str, latitude: &str) -> Result<(f64, f64), Vec<u8>> {
+    let (longitude, latitude) = match (parse_float(longitude), parse_float(latitude)) {
+        (Some(longitude), Some(latitude)) => (longitude, latitude),
+        _ => return Err(not_a_float()),
+    };
+
+    if !geohash::is_valid(longitude, latitude) {
+        let msg = format!(
+            "ERR invalid longitude,latitude pair {:.6},{:.6}",
+            longitude, latitude
+        );
+        return Err(serializer::err(&msg));
+    }
+
+    Ok((longitude, latitude))
+}
+
+/*
+ * coordinates are replied with 17 decimals, without the trailing zeroes,
+ * like redis does
+ */
+fn format_coord(value: f64) -> String {
+    let formatted = format!("{:.17}", value);
+    let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+
+    match formatted {
+        "-0" => "0".to_string(),
+        _ => formatted.to_string(),
+    }
+}
+
+fn coord_reply(longitude: f64, latitude: f64) -> Vec<u8> {
+    serializer::str_arr(&[format_coord(longitude), format_coord(latitude)])
+}
+
+fn format_distance(meters: f64, unit: f64) -> String {
+    format!("{:.4}", meters / unit)
+}
+
+/* ---- GEOADD ---- */
+
+pub fn handle_geoadd(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 3 {
+        return wrong_args("geoadd");
+    }
+
+    let mut opts = ZAddOpts::default();
+    let mut flags = 0;
+
+    for arg in query.args.iter() {
+        match arg.to_uppercase().as_str() {
+            "NX" => opts.nx = true,
+            "XX" => opts.xx = true,
+            "CH" => opts.ch = true,
+            _ => break,
+        }
+
+        flags += 1;
+    }
+
+    let triples = &query.args[flags..];
+
+    if triples.is_empty() || !triples.len().is_multiple_of(3) || (opts.nx && opts.xx) {
+        return syntax_error();
+    }
+
+    // every position is checked before anything is added
+    let mut scored = Vec::with_capacity(triples.len() / 3);
+
+    for triple in triples.chunks(3) {
+        match parse_lonlat(&triple[0], &triple[1]) {
+            Ok((longitude, latitude)) => {
+                let score = geohash::score(longitude, latitude).unwrap();
+                scored.push((score, &triple[2]));
+            }
+            Err(err) => return err,
+        }
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_or_create_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let mut changed = 0;
+
+        for (score, member) in scored {
+            match zadd_one(zset, &opts, member, score) {
+                Ok(ZAddOutcome::Added(_)) => changed += 1,
+                Ok(ZAddOutcome::Updated(_)) if opts.ch => changed += 1,
+                _ => {}
+            }
+        }
+
+        // XX may have left a brand new key empty
+        db.remove_if_empty(key);
+
+        serializer::int(changed)
+    })
+}
+
+/* ---- GEODIST, GEOPOS, GEOHASH ---- */
+
+pub fn handle_geodist(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 2 {
+        return wrong_args("geodist");
+    }
+
+    let unit = match &query.args[2..] {
+        [] => 1.0,
+        [unit] => match parse_unit(unit) {
+            Ok(unit) => unit,
+            Err(err) => return err,
+        },
+        _ => return syntax_error(),
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let (first, second) = match (zset.score(&query.args[0]), zset.score(&query.args[1])) {
+            (Some(first), Some(second)) => (geohash::position(first), geohash::position(second)),
+            _ => return serializer::nil(),
+        };
+
+        let meters = geohash::distance(first.0, first.1, second.0, second.1);
+        serializer::bulk_str(&format_distance(meters, unit))
+    })
+}
+
+pub fn handle_geopos(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() {
+        return wrong_args("geopos");
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let replies: Vec<Vec<u8>> = query
+            .args
+            .iter()
+            .map(|member| match zset.and_then(|zset| zset.score(member)) {
+                Some(score) => {
+                    let (longitude, latitude) = geohash::position(score);
+                    coord_reply(longitude, latitude)
+                }
+                None => serializer::null_arr(),
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+pub fn handle_geohash(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() {
+        return wrong_args("geohash");
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let replies: Vec<Vec<u8>> = query
+            .args
+            .iter()
+            .map(|member| match zset.and_then(|zset| zset.score(member)) {
+                Some(score) => serializer::bulk_str(&geohash::to_string(score)),
+                None => serializer::nil(),
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+/* ---- GEOSEARCH and GEORADIUS ---- */
+
+// the commands sharing the search code, which differ in their arguments
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoSearchCmd {
+    Search,
+    SearchStore,
+    Radius,
+    RadiusByMember,
+    RadiusRo,
+    RadiusByMemberRo,
+}
+
+impl GeoSearchCmd {
+    fn name(&self) -> &'static str {
+        match self {
+            GeoSearchCmd::Search => "geosearch",
+            GeoSearchCmd::SearchStore => "geosearchstore",
+            GeoSearchCmd::Radius => "georadius",
+            GeoSearchCmd::RadiusByMember => "georadiusbymember",
+            GeoSearchCmd::RadiusRo => "georadius_ro",
+            GeoSearchCmd::RadiusByMemberRo => "georadiusbymember_ro",
+        }
+    }
+
+    fn is_geosearch(&self) -> bool {
+        matches!(self, GeoSearchCmd::Search | GeoSearchCmd::SearchStore)
+    }
+
+    // the legacy forms take STORE and STOREDIST with a key
+    fn takes_store_key(&self) -> bool {
+        matches!(self, GeoSearchCmd::Radius | GeoSearchCmd::RadiusByMember)
+    }
+
+    // arguments after the searched key that are not options
+    fn base_args(&self) -> usize {
+        match self {
+            GeoSearchCmd::Search | GeoSearchCmd::SearchStore => 0,
+            GeoSearchCmd::Radius | GeoSearchCmd::RadiusRo => 4,
+            GeoSearchCmd::RadiusByMember | GeoSearchCmd::RadiusByMemberRo => 3,
+        }
+    }
+
+    // fewest arguments after the first key the command can be called with
+    fn min_args(&self) -> usize {
+        match self {
+            GeoSearchCmd::Search => 5,
+            GeoSearchCmd::SearchStore => 6,
+            _ => self.base_args(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Center {
+    Member(String),
+    LonLat(f64, f64),
+}
+
+#[derive(Debug, PartialEq)]
+enum Sort {
+    None,
+    Asc,
+    Desc,
+}
+
+#[derive(Debug)]
+struct SearchOpts {
+    center: Option<Center>,
+    shape: Option<Shape>, // in meters
+    unit: f64,
+    sort: Sort,
+    count: Option<usize>,
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+    store: Option<String>,
+    store_dist: bool,
+}
+
+// a member within the searched shape
+struct Found {
+    member: String,
+    score: f64,
+    distance: f64, // in meters
+}
+
+// "radius unit"
+fn parse_radius(args: &[String]) -> Result<(Shape, f64), Vec<u8>> {
+    let radius = match parse_float(&args[0]) {
+        Some(radius) if radius < 0.0 => {
+            return Err(serializer::err("ERR radius cannot be negative"))
+        }
+        Some(radius) => radius,
+        None => return Err(serializer::err("ERR need numeric radius")),
+    };
+
+    let unit = parse_unit(&args[1])?;
+    Ok((Shape::Radius(radius * unit), unit))
+}
+
+// "width height unit"
+fn parse_box(args: &[String]) -> Result<(Shape, f64), Vec<u8>> {
+    let width = parse_float(&args[0]).ok_or_else(|| serializer::err("ERR need numeric width"))?;
+    let height = parse_float(&args[1]).ok_or_else(|| serializer::err("ERR need numeric height"))?;
+
+    if width < 0.0 || height < 0.0 {
+        return Err(serializer::err("ERR height or width cannot be negative"));
+    }
+
+    let unit = parse_unit(&args[2])?;
+
+    let shape = Shape::Box {
+        width: width * unit,
+        height: height * unit,
+    };
+
+    Ok((shape, unit))
+}
+
+fn parse_search_opts(args: &[String], cmd: GeoSearchCmd) -> Result<SearchOpts, Vec<u8>> {
+    let mut opts = SearchOpts {
+        center: None,
+        shape: None,
+        unit: 1.0,
+        sort: Sort::None,
+        count: None,
+        any: false,
+        with_coord: false,
+        with_dist: false,
+        with_hash: false,
+        store: None,
+        store_dist: false,
+    };
+
+    // the legacy forms start with the center and the radius
+    let base = cmd.base_args();
+
+    match cmd {
+        GeoSearchCmd::Radius | GeoSearchCmd::RadiusRo => {
+            let (longitude, latitude) = parse_lonlat(&args[0], &args[1])?;
+            let (shape, unit) = parse_radius(&args[2..4])?;
+
+            opts.center = Some(Center::LonLat(longitude, latitude));
+            (opts.shape, opts.unit) = (Some(shape), unit);
+        }
+        GeoSearchCmd::RadiusByMember | GeoSearchCmd::RadiusByMemberRo => {
+            let (shape, unit) = parse_radius(&args[1..3])?;
+
+            opts.center = Some(Center::Member(args[0].to_string()));
+            (opts.shape, opts.unit) = (Some(shape), unit);
+        }
+        _ => {}
+    }
+
+    let args = &args[base..];
+    let mut i = 0;
+
+    while i < args.len() {
+        let remaining = args.len() - i - 1;
+        let search = cmd.is_geosearch();
+
+        match args[i].to_lowercase().as_str() {
+            "withdist" => opts.with_dist = true,
+            "withhash" => opts.with_hash = true,
+            "withcoord" => opts.with_coord = true,
+            "any" => opts.any = true,
+            "asc" => opts.sort = Sort::Asc,
+            "desc" => opts.sort = Sort::Desc,
+            "count" if remaining >= 1 => {
+                opts.count = match args[i + 1].parse::<i64>() {
+                    Ok(count) if count > 0 => Some(count as usize),
+                    Ok(_) => return Err(serializer::err("ERR COUNT must be > 0")),
+                    Err(_) => return Err(not_an_integer()),
+                };
+                i += 1;
+            }
+            option @ ("store" | "storedist") if cmd.takes_store_key() && remaining >= 1 => {
+                opts.store = Some(args[i + 1].to_string());
+                opts.store_dist = option == "storedist";
+                i += 1;
+            }
+            "storedist" if cmd == GeoSearchCmd::SearchStore => opts.store_dist = true,
+            "frommember" if search && remaining >= 1 && opts.center.is_none() => {
+                opts.center = Some(Center::Member(args[i + 1].to_string()));
+                i += 1;
+            }
+            "fromlonlat" if search && remaining >= 2 && opts.center.is_none() => {
+                let (longitude, latitude) = parse_lonlat(&args[i + 1], &args[i + 2])?;
+                opts.center = Some(Center::LonLat(longitude, latitude));
+                i += 2;
+            }
+            "byradius" if search && remaining >= 2 && opts.shape.is_none() => {
+                let (shape, unit) = parse_radius(&args[i + 1..i + 3])?;
+                (opts.shape, opts.unit) = (Some(shape), unit);
+                i += 2;
+            }
+            "bybox" if search && remaining >= 3 && opts.shape.is_none() => {
+                let (shape, unit) = parse_box(&args[i + 1..i + 4])?;
+                (opts.shape, opts.unit) = (Some(shape), unit);
+                i += 3;
+            }
+            _ => return Err(syntax_error()),
+        }
+
+        i += 1;
+    }
+
+    let storing = opts.store.is_some() || cmd == GeoSearchCmd::SearchStore;
+
+    if storing && (opts.with_dist || opts.with_hash || opts.with_coord) {
+        let msg = match cmd {
+            GeoSearchCmd::SearchStore => "ERR GEOSEARCHSTORE",
+            _ => "ERR STORE option in GEORADIUS",
+        };
+        let msg = format!(
+            "{} is not compatible with WITHDIST, WITHHASH and WITHCOORD options",
+            msg
+        );
+        return Err(serializer::err(&msg));
+    }
+
+    if opts.center.is_none() {
+        let msg = format!(
+            "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+            cmd.name()
+        );
+        return Err(serializer::err(&msg));
+    }
+
+    if opts.shape.is_none() {
+        let msg = format!(
+            "ERR exactly one of BYRADIUS and BYBOX can be specified for {}",
+            cmd.name()
+        );
+        return Err(serializer::err(&msg));
+    }
+
+    if opts.any && opts.count.is_none() {
+        return Err(serializer::err(
+            "ERR the ANY argument requires COUNT argument",
+        ));
+    }
+
+    // the closest members are wanted, unless any will do
+    if opts.count.is_some() && opts.sort == Sort::None && !opts.any {
+        opts.sort = Sort::Asc;
+    }
+
+    Ok(opts)
+}
+
+/*
+ * walks the cells covering the searched shape, keeping the members that are
+ * actually within it. with ANY, the search stops as soon as `count` members
+ * are found
+ */
+fn search(zset: &SortedSet, search: &Search, limit: Option<usize>) -> Vec<Found> {
+    let mut found = vec![];
+
+    for area in search.areas() {
+        let (min, max) = area.score_range();
+
+        let range = ScoreRange {
+            min: ScoreBound {
+                value: min,
+                exclusive: false,
+            },
+            max: ScoreBound {
+                value: max,
+                exclusive: true,
+            },
+        };
+
+        for (member, score) in zset.range(&range, false, 0, None) {
+            if limit.is_some_and(|limit| found.len() >= limit) {
+                return found;
+            }
+
+            let (longitude, latitude) = geohash::position(score);
+
+            if let Some(distance) = search.distance_to(longitude, latitude) {
+                found.push(Found {
+                    member,
+                    score,
+                    distance,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+fn found_reply(found: &Found, opts: &SearchOpts) -> Vec<u8> {
+    if !(opts.with_dist || opts.with_hash || opts.with_coord) {
+        return serializer::bulk_str(&found.member);
+    }
+
+    let mut items = vec![serializer::bulk_str(&found.member)];
+
+    if opts.with_dist {
+        items.push(serializer::bulk_str(&format_distance(
+            found.distance,
+            opts.unit,
+        )));
+    }
+
+    if opts.with_hash {
+        items.push(serializer::int(found.score as i64));
+    }
+
+    if opts.with_coord {
+        let (longitude, latitude) = geohash::position(found.score);
+        items.push(coord_reply(longitude, latitude));
+    }
+
+    serializer::arr(&items)
+}
+
+/*
+ * GEOSEARCH key, GEOSEARCHSTORE dest key, GEORADIUS key lon lat radius unit
+ * and GEORADIUSBYMEMBER key member radius unit, all followed by options
+ */
+pub fn handle_geosearch(query: &Query, cmd: GeoSearchCmd) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.len() < cmd.min_args() {
+        return wrong_args(cmd.name());
+    }
+
+    let (key, args, dest) = match cmd {
+        GeoSearchCmd::SearchStore => (&query.args[0], &query.args[1..], Some(&query.command_str)),
+        _ => (&query.command_str, &query.args[..], None),
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let opts = match parse_search_opts(args, cmd) {
+            Ok(opts) => opts,
+            Err(err) => return err,
+        };
+
+        let (longitude, latitude) = match &opts.center {
+            Some(Center::LonLat(longitude, latitude)) => (*longitude, *latitude),
+            Some(Center::Member(member)) => match zset.and_then(|zset| zset.score(member)) {
+                Some(score) => geohash::position(score),
+                None => return serializer::err("ERR could not decode requested zset member"),
+            },
+            None => unreachable!(),
+        };
+
+        let dest = dest.or(opts.store.as_ref());
+
+        let zset = match zset {
+            Some(zset) => zset,
+            None => {
+                return match dest {
+                    Some(dest) => {
+                        db.remove(dest);
+                        serializer::int(0)
+                    }
+                    None => serializer::arr(&[]),
+                };
+            }
+        };
+
+        let area = Search {
+            longitude,
+            latitude,
+            shape: opts.shape.unwrap(),
+        };
+
+        let limit = opts.count.filter(|_| opts.any);
+        let mut found = search(zset, &area, limit);
+
+        match opts.sort {
+            Sort::Asc => found.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+            Sort::Desc => found.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+            Sort::None => {}
+        }
+
+        if let Some(count) = opts.count {
+            found.truncate(count);
+        }
+
+        let dest = match dest {
+            Some(dest) => dest,
+            None => {
+                let replies: Vec<Vec<u8>> = found
+                    .iter()
+                    .map(|found| found_reply(found, &opts))
+                    .collect();
+                return serializer::arr(&replies);
+            }
+        };
+
+        // the destination is overwritten whatever its type (and TTL), or
+        // deleted if the result is empty
+        db.remove(dest);
+
+        if found.is_empty() {
+            return serializer::int(0);
+        }
+
+        let mut stored = SortedSet::new();
+
+        for found in found {
+            let score = match opts.store_dist {
+                true => found.distance / opts.unit,
+                false => found.score,
+            };
+
+            stored.insert(found.member, score);
+        }
+
+        let len = stored.len();
+        db.insert(dest.to_string(), DataTypes::SortedSet(stored));
+
+        serializer::int(len as i64)
+    })
+}
@@ -0,0 +1,295 @@
+#[cfg(test)]
+mod tests {
+    use super::super::spec_helper::*;
+
+    fn add_places(key: &str) {
+        run(&[
+            "GEOADD",
+            key,
+            "13.361389",
+            "38.115556",
+            "Palermo",
+            "15.087269",
+            "37.502669",
+            "Catania",
+        ]);
+    }
+
+    // a member at the center, one east, one north and one off in a corner
+    fn add_grid(key: &str) {
+        run(&[
+            "GEOADD", key, "15", "37", "center", "16", "37", "east", "15", "37.5", "north", "16.1",
+            "37.4", "corner",
+        ]);
+    }
+
+    #[test]
+    fn test_geosearch_bybox() {
+        add_grid("geobox");
+
+        // north is under 100 km away but out of the box, the corner is the other way round
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYBOX",
+                "200",
+                "100",
+                "km",
+                "ASC"
+            ]),
+            "*3\r\n$6\r\ncenter\r\n$4\r\neast\r\n$6\r\ncorner\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYRADIUS",
+                "100",
+                "km",
+                "ASC"
+            ]),
+            "*3\r\n$6\r\ncenter\r\n$5\r\nnorth\r\n$4\r\neast\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMMEMBER",
+                "center",
+                "BYBOX",
+                "200",
+                "100",
+                "km",
+                "WITHDIST",
+                "COUNT",
+                "1",
+                "DESC"
+            ]),
+            "*1\r\n*2\r\n$6\r\ncorner\r\n$8\r\n107.1289\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMMEMBER",
+                "center",
+                "BYBOX",
+                "0.2",
+                "0.1",
+                "mi"
+            ]),
+            "*1\r\n$6\r\ncenter\r\n"
+        );
+
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYBOX",
+                "-1",
+                "1",
+                "km"
+            ]),
+            "-ERR height or width cannot be negative\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYBOX",
+                "200",
+                "km"
+            ]),
+            "-ERR syntax error\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geobox",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYBOX",
+                "200",
+                "100",
+                "km",
+                "BYRADIUS",
+                "1",
+                "km"
+            ]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_geosearch_count() {
+        add_grid("geocount");
+
+        // COUNT alone gives the closest members
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geocount",
+                "FROMMEMBER",
+                "center",
+                "BYRADIUS",
+                "200",
+                "km",
+                "COUNT",
+                "2"
+            ]),
+            "*2\r\n$6\r\ncenter\r\n$5\r\nnorth\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geocount",
+                "FROMMEMBER",
+                "center",
+                "BYRADIUS",
+                "200",
+                "km",
+                "COUNT",
+                "2",
+                "DESC"
+            ]),
+            "*2\r\n$6\r\ncorner\r\n$4\r\neast\r\n"
+        );
+
+        // with ANY, whichever members are found first will do
+        let any = run(&[
+            "GEOSEARCH",
+            "geocount",
+            "FROMMEMBER",
+            "center",
+            "BYRADIUS",
+            "200",
+            "km",
+            "COUNT",
+            "2",
+            "ANY",
+        ]);
+        assert!(any.starts_with("*2\r\n"));
+        assert_eq!(any.matches("\r\n$").count(), 2);
+
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geocount",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYRADIUS",
+                "200",
+                "km",
+                "ANY"
+            ]),
+            "-ERR the ANY argument requires COUNT argument\r\n"
+        );
+        for count in ["0", "-1"] {
+            assert_eq!(
+                run(&[
+                    "GEOSEARCH",
+                    "geocount",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "COUNT",
+                    count
+                ]),
+                "-ERR COUNT must be > 0\r\n"
+            );
+        }
+        assert_eq!(
+            run(&[
+                "GEOSEARCH",
+                "geocount",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYRADIUS",
+                "200",
+                "km",
+                "COUNT",
+                "x"
+            ]),
+            "-ERR value is not an integer or out of range\r\n"
+        );
+    }
+
+    #[test]
+    fn test_geosearchstore() {
+        add_places("geostore:src");
+
+        assert_eq!(
+            run(&[
+                "GEOSEARCHSTORE",
+                "geostore:dst",
+                "geostore:src",
+                "FROMLONLAT",
+                "15",
+                "37",
+                "BYRADIUS",
+                "200",
+                "km",
+                "ASC"
+            ]),
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "geostore:dst", "0", "-1"]),
+            "*2\r\n$7\r\nPalermo\r\n$7\r\nCatania\r\n"
+        );
+    }
+
+    #[test]
+    fn test_store_replaces_destination() {
+        add_places("georeplace:src");
+
+        let empty = [
+            "GEOSEARCHSTORE",
+            "georeplace:dst",
+            "georeplace:src",
+            "FROMLONLAT",
+            "0",
+            "0",
+            "BYRADIUS",
+            "1",
+            "km",
+        ];
+
+        for store in ["STORE", "STOREDIST"] {
+            assert_store_replaces(
+                "georeplace:dst",
+                &[
+                    "GEORADIUS",
+                    "georeplace:src",
+                    "15",
+                    "37",
+                    "200",
+                    "km",
+                    store,
+                    "georeplace:dst",
+                ],
+                &empty,
+                "zset",
+            );
+        }
+    }
+}
@@ -0,0 +1,834 @@
+use rand::{seq::index, Rng};
+
+use super::{
+    format_float, not_an_integer, parse_float, parse_rand_count, parse_scan_args, scan_reply,
+    syntax_error, wrong_args, wrong_type,
+};
+use crate::velocity::{database::DatabaseOps, query::Query, serializer};
+
+pub fn handle_hset(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() || !query.args.len().is_multiple_of(2) {
+        return wrong_args("hset");
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_or_create_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        let mut added = 0;
+
+        for pair in query.args.chunks(2) {
+            if hash
+                .insert(pair[0].to_string(), pair[1].to_string())
+                .is_none()
+            {
+                added += 1;
+            }
+        }
+
+        serializer::int(added)
+    })
+}
+
+pub fn handle_hsetnx(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("hsetnx");
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_or_create_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        if hash.contains_key(&query.args[0]) {
+            return serializer::int(0);
+        }
+
+        hash.insert(query.args[0].to_string(), query.args[1].to_string());
+        serializer::int(1)
+    })
+}
+
+pub fn handle_hget(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("hget");
+    }
+
+    db.transaction(|db| match db.get_hash(key) {
+        Ok(Some(hash)) => match hash.get(&query.args[0]) {
+            Some(value) => serializer::bulk_str(value),
+            None => serializer::nil(),
+        },
+        Ok(None) => serializer::nil(),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_hmget(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("hmget");
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        let values: Vec<Vec<u8>> = query
+            .args
+            .iter()
+            .map(|field| match hash.and_then(|hash| hash.get(field)) {
+                Some(value) => serializer::bulk_str(value),
+                None => serializer::nil(),
+            })
+            .collect();
+
+        serializer::arr(&values)
+    })
+}
+
+pub fn handle_hdel(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("hdel");
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_hash_mut(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut removed = 0;
+
+        for field in query.args.iter() {
+            if hash.remove(field).is_some() {
+                removed += 1;
+            }
+        }
+
+        db.remove_if_empty(key);
+        serializer::int(removed)
+    })
+}
+
+pub fn handle_hexists(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("hexists");
+    }
+
+    db.transaction(|db| match db.get_hash(key) {
+        Ok(Some(hash)) if hash.contains_key(&query.args[0]) => serializer::int(1),
+        Ok(_) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_hlen(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("hlen");
+    }
+
+    db.transaction(|db| match db.get_hash(key) {
+        Ok(Some(hash)) => serializer::int(hash.len() as i64),
+        Ok(None) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_hstrlen(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("hstrlen");
+    }
+
+    db.transaction(|db| match db.get_hash(key) {
+        Ok(Some(hash)) => match hash.get(&query.args[0]) {
+            Some(value) => serializer::int(value.len() as i64),
+            None => serializer::int(0),
+        },
+        Ok(None) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+#[derive(Debug)]
+enum HashDump {
+    Keys,
+    Values,
+    All,
+}
+
+fn dump_hash(query: &Query, command: &str, dump: HashDump) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args(command);
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_hash(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::str_arr(&[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut items: Vec<String> = vec![];
+
+        for (field, value) in hash.iter() {
+            match dump {
+                HashDump::Keys => items.push(field.to_string()),
+                HashDump::Values => items.push(value.to_string()),
+                HashDump::All => {
+                    items.push(field.to_string());
+                    items.push(value.to_string());
+                }
+            }
+        }
+
+        serializer::str_arr(&items)
+    })
+}
+
+pub fn handle_hkeys(query: &Query) -> Vec<u8> {
+    dump_hash(query, "hkeys", HashDump::Keys)
+}
+
+pub fn handle_hvals(query: &Query) -> Vec<u8> {
+    dump_hash(query, "hvals", HashDump::Values)
+}
+
+pub fn handle_hgetall(query: &Query) -> Vec<u8> {
+    dump_hash(query, "hgetall", HashDump::All)
+}
+
+pub fn handle_hincrby(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("hincrby");
+    }
+
+    let increment = match query.args[1].parse::<i64>() {
+        Ok(increment) => increment,
+        Err(_) => return not_an_integer(),
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_or_create_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        let current = match hash.get(&query.args[0]) {
+            Some(value) => match value.parse::<i64>() {
+                Ok(value) => value,
+                Err(_) => return serializer::err("ERR hash value is not an integer"),
+            },
+            None => 0,
+        };
+
+        let result = match current.checked_add(increment) {
+            Some(result) => result,
+            None => return serializer::err("ERR increment or decrement would overflow"),
+        };
+
+        hash.update(query.args[0].to_string(), result.to_string());
+        serializer::int(result)
+    })
+}
+
+pub fn handle_hincrbyfloat(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("hincrbyfloat");
+    }
+
+    let increment = match parse_float(&query.args[1]) {
+        Some(increment) => increment,
+        None => return serializer::err("ERR value is not a valid float"),
+    };
+
+    if !increment.is_finite() {
+        return serializer::err("ERR increment would produce NaN or Infinity");
+    }
+
+    db.transaction(|db| {
+        let hash = match db.get_or_create_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        let current = match hash.get(&query.args[0]) {
+            Some(value) => match parse_float(value) {
+                Some(value) => value,
+                None => return serializer::err("ERR hash value is not a float"),
+            },
+            None => 0.0,
+        };
+
+        let result = current + increment;
+
+        if !result.is_finite() {
+            return serializer::err("ERR increment would produce NaN or Infinity");
+        }
+
+        let result = format_float(result);
+
+        hash.update(query.args[0].to_string(), result.to_string());
+        serializer::bulk_str(&result)
+    })
+}
+
+pub fn handle_hscan(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("hscan");
+    }
+
+    let opts = match parse_scan_args(&query.args, true) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return scan_reply(0, &[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let (cursor, found) = hash.scan(opts.cursor, opts.count);
+        let mut items: Vec<String> = vec![];
+
+        for (field, value) in found {
+            if !opts.matches(field) {
+                continue;
+            }
+
+            items.push(field.to_string());
+
+            if !opts.novalues {
+                items.push(value.to_string());
+            }
+        }
+
+        scan_reply(cursor, &items)
+    })
+}
+
+/*
+ * HRANDFIELD key [count [WITHVALUES]]
+ *
+ * a positive count returns distinct fields, a negative one may return the
+ * same field several times. either way every field is equally likely to be
+ * picked
+ */
+pub fn handle_hrandfield(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 2 {
+        return wrong_args("hrandfield");
+    }
+
+    let with_values = match query.args.get(1) {
+        Some(arg) if arg.to_uppercase() == "WITHVALUES" => true,
+        Some(_) => return syntax_error(),
+        None => false,
+    };
+
+    let count = match query.args.first() {
+        Some(count) => match parse_rand_count(count, with_values) {
+            Ok(count) => Some(count),
+            Err(err) => return err,
+        },
+        None => None,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) if count.is_some() => return serializer::str_arr(&[]),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut rng = rand::thread_rng();
+
+        let count = match count {
+            Some(count) => count,
+            None => {
+                let (field, _) = hash.iter().nth(rng.gen_range(0..hash.len())).unwrap();
+                return serializer::bulk_str(field);
+            }
+        };
+
+        let entries: Vec<(&String, &String)> = hash.iter().collect();
+        let mut items: Vec<String> = vec![];
+
+        let mut push = |position: usize| {
+            let (field, value) = entries[position];
+            items.push(field.to_string());
+
+            if with_values {
+                items.push(value.to_string());
+            }
+        };
+
+        // with repeats, the reply grows with what is actually picked
+        if count >= 0 {
+            let amount = (count as usize).min(entries.len());
+            index::sample(&mut rng, entries.len(), amount)
+                .into_iter()
+                .for_each(&mut push);
+        } else {
+            for _ in 0..count.unsigned_abs() {
+                push(rng.gen_range(0..entries.len()));
+            }
+        }
+
+        serializer::str_arr(&items)
+    })
+}
+
+/* ------------------------------------------------------------------- */
+/* ------------------- Per Field Expiration -------------------------- */
+/* ------------------------------------------------------------------- */
+
+// parses `FIELDS numfields field [field ...]`, each field taking `width` args
+fn parse_fields(args: &[String], width: usize) -> Result<&[String], Vec<u8>> {
+    match args.first() {
+        Some(arg) if arg.to_uppercase() == "FIELDS" => {}
+        _ => {
+            let msg = "ERR Mandatory argument FIELDS is missing or not at the right position";
+            return Err(serializer::err(msg));
+        }
+    }
+
+    let numfields = match args.get(1).map(|numfields| numfields.parse::<i64>()) {
+        Some(Ok(numfields)) if numfields > 0 => numfields as usize,
+        _ => {
+            let msg = "ERR Parameter `numFields` should be greater than 0";
+            return Err(serializer::err(msg));
+        }
+    };
+
+    let fields = &args[2..];
+
+    if fields.len() != numfields * width {
+        let msg = "ERR The `numfields` parameter must match the number of arguments";
+        return Err(serializer::err(msg));
+    }
+
+    Ok(fields)
+}
+
+/*
+ * converts the value of an EX/PX/EXAT/PXAT option into a unix time in
+ * milliseconds. `None` means the value is not valid
+ */
+fn parse_expire_at(option: &str, value: &str) -> Option<i64> {
+    let value = value.parse::<i64>().ok().filter(|value| *value >= 0)?;
+    let now = chrono::Utc::now().timestamp_millis();
+
+    match option {
+        "EX" => value.checked_mul(1000)?.checked_add(now),
+        "PX" => value.checked_add(now),
+        "EXAT" => value.checked_mul(1000),
+        "PXAT" => Some(value),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExpireCondition {
+    Always,
+    Nx, // only when the field has no TTL
+    Xx, // only when the field has a TTL
+    Gt, // only when the new TTL is greater than the current one
+    Lt, // only when the new TTL is less than the current one
+}
+
+impl ExpireCondition {
+    // a field without a TTL is treated as one that never expires
+    fn allows(&self, current: Option<i64>, new: i64) -> bool {
+        match (self, current) {
+            (ExpireCondition::Always, _) => true,
+            (ExpireCondition::Nx, current) => current.is_none(),
+            (ExpireCondition::Xx, current) => current.is_some(),
+            (ExpireCondition::Gt, Some(current)) => new > current,
+            (ExpireCondition::Gt, None) => false,
+            (ExpireCondition::Lt, Some(current)) => new < current,
+            (ExpireCondition::Lt, None) => true,
+        }
+    }
+}
+
+// HEXPIRE, HPEXPIRE, HEXPIREAT & HPEXPIREAT
+pub fn handle_hexpire(query: &Query, command: &str, option: &str) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 4 {
+        return wrong_args(command);
+    }
+
+    let at = match parse_expire_at(option, &query.args[0]) {
+        Some(at) => at,
+        None => {
+            let msg = format!("ERR invalid expire time in '{}' command", command);
+            return serializer::err(&msg);
+        }
+    };
+
+    let (condition, rest) = match query.args[1].to_uppercase().as_str() {
+        "NX" => (ExpireCondition::Nx, &query.args[2..]),
+        "XX" => (ExpireCondition::Xx, &query.args[2..]),
+        "GT" => (ExpireCondition::Gt, &query.args[2..]),
+        "LT" => (ExpireCondition::Lt, &query.args[2..]),
+        _ => (ExpireCondition::Always, &query.args[1..]),
+    };
+
+    let fields = match parse_fields(rest, 1) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash_mut(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::arr(&vec![serializer::int(-2); fields.len()]),
+            Err(_) => return wrong_type(),
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut replies: Vec<Vec<u8>> = vec![];
+
+        for field in fields {
+            if !hash.contains_key(field) {
+                replies.push(serializer::int(-2));
+            } else if !condition.allows(hash.expires_at(field), at) {
+                replies.push(serializer::int(0));
+            } else if at <= now {
+                // an expiration in the past deletes the field right away
+                hash.remove(field);
+                replies.push(serializer::int(2));
+            } else {
+                hash.set_expires_at(field, at);
+                replies.push(serializer::int(1));
+            }
+        }
+
+        if hash.has_expires() {
+            db.track_hash_expires(key);
+        }
+
+        db.remove_if_empty(key);
+        serializer::arr(&replies)
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FieldTtl {
+    Ttl,
+    PTtl,
+    ExpireTime,
+    PExpireTime,
+}
+
+// HTTL, HPTTL, HEXPIRETIME & HPEXPIRETIME
+pub fn handle_httl(query: &Query, command: &str, kind: FieldTtl) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 3 {
+        return wrong_args(command);
+    }
+
+    let fields = match parse_fields(&query.args, 1) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::arr(&vec![serializer::int(-2); fields.len()]),
+            Err(_) => return wrong_type(),
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let replies: Vec<Vec<u8>> = fields
+            .iter()
+            .map(|field| {
+                if !hash.contains_key(field) {
+                    return serializer::int(-2);
+                }
+
+                let at = match hash.expires_at(field) {
+                    Some(at) => at,
+                    None => return serializer::int(-1),
+                };
+
+                let value = match kind {
+                    FieldTtl::Ttl => (at - now + 999) / 1000,
+                    FieldTtl::PTtl => at - now,
+                    FieldTtl::ExpireTime => at / 1000,
+                    FieldTtl::PExpireTime => at,
+                };
+
+                serializer::int(value)
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+pub fn handle_hpersist(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 3 {
+        return wrong_args("hpersist");
+    }
+
+    let fields = match parse_fields(&query.args, 1) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash_mut(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::arr(&vec![serializer::int(-2); fields.len()]),
+            Err(_) => return wrong_type(),
+        };
+
+        let replies: Vec<Vec<u8>> = fields
+            .iter()
+            .map(|field| {
+                if !hash.contains_key(field) {
+                    serializer::int(-2)
+                } else if hash.persist(field) {
+                    serializer::int(1)
+                } else {
+                    serializer::int(-1)
+                }
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+// HGETEX key [EX seconds | PX ms | EXAT unix | PXAT unix-ms | PERSIST] FIELDS ...
+pub fn handle_hgetex(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 3 {
+        return wrong_args("hgetex");
+    }
+
+    let option = query.args[0].to_uppercase();
+
+    // `Some(None)` stands for PERSIST
+    let (expire, rest): (Option<Option<i64>>, &[String]) = match option.as_str() {
+        "EX" | "PX" | "EXAT" | "PXAT" => match parse_expire_at(&option, &query.args[1]) {
+            Some(at) => (Some(Some(at)), &query.args[2..]),
+            None => return serializer::err("ERR invalid expire time in 'hgetex' command"),
+        },
+        "PERSIST" => (Some(None), &query.args[1..]),
+        _ => (None, &query.args[..]),
+    };
+
+    let fields = match parse_fields(rest, 1) {
+        Ok(fields) => fields,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_hash_mut(key) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return serializer::arr(&vec![serializer::nil(); fields.len()]),
+            Err(_) => return wrong_type(),
+        };
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut replies: Vec<Vec<u8>> = vec![];
+
+        for field in fields {
+            let value = match hash.get(field) {
+                Some(value) => value.to_string(),
+                None => {
+                    replies.push(serializer::nil());
+                    continue;
+                }
+            };
+
+            match expire {
+                Some(Some(at)) if at <= now => {
+                    hash.remove(field);
+                }
+                Some(Some(at)) => hash.set_expires_at(field, at),
+                Some(None) => {
+                    hash.persist(field);
+                }
+                None => {}
+            }
+
+            replies.push(serializer::bulk_str(&value));
+        }
+
+        if hash.has_expires() {
+            db.track_hash_expires(key);
+        }
+
+        db.remove_if_empty(key);
+        serializer::arr(&replies)
+    })
+}
+
+#[derive(Debug)]
+struct HSetExOpts {
+    // `Some(true)` for FNX, `Some(false)` for FXX
+    only_new: Option<bool>,
+    expire: Option<i64>,
+    keep_ttl: bool,
+}
+
+// HSETEX key [FNX | FXX] [EX | PX | EXAT | PXAT | KEEPTTL] FIELDS ...
+pub fn handle_hsetex(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 4 {
+        return wrong_args("hsetex");
+    }
+
+    let mut opts = HSetExOpts {
+        only_new: None,
+        expire: None,
+        keep_ttl: false,
+    };
+
+    let mut position = 0;
+
+    while position < query.args.len() {
+        let option = query.args[position].to_uppercase();
+        let has_expire = opts.expire.is_some() || opts.keep_ttl;
+
+        match option.as_str() {
+            "FNX" | "FXX" if opts.only_new.is_none() => {
+                opts.only_new = Some(option == "FNX");
+            }
+
+            "EX" | "PX" | "EXAT" | "PXAT" if !has_expire => {
+                let value = query.args.get(position + 1).map(|value| value.as_str());
+
+                opts.expire = match value.and_then(|value| parse_expire_at(&option, value)) {
+                    Some(at) => Some(at),
+                    None => return serializer::err("ERR invalid expire time in 'hsetex' command"),
+                };
+
+                position += 1;
+            }
+
+            "KEEPTTL" if !has_expire => opts.keep_ttl = true,
+
+            "FIELDS" => break,
+
+            _ => return syntax_error(),
+        }
+
+        position += 1;
+    }
+
+    let pairs = match parse_fields(&query.args[position..], 2) {
+        Ok(pairs) => pairs,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let hash = match db.get_or_create_hash(key) {
+            Ok(hash) => hash,
+            Err(_) => return wrong_type(),
+        };
+
+        let allowed = match opts.only_new {
+            Some(true) => pairs.chunks(2).all(|pair| !hash.contains_key(&pair[0])),
+            Some(false) => pairs.chunks(2).all(|pair| hash.contains_key(&pair[0])),
+            None => true,
+        };
+
+        if !allowed {
+            db.remove_if_empty(key);
+            return serializer::int(0);
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        for pair in pairs.chunks(2) {
+            let (field, value) = (pair[0].to_string(), pair[1].to_string());
+
+            match opts.expire {
+                Some(at) if at <= now => {
+                    hash.remove(&field);
+                }
+                Some(at) => {
+                    hash.insert(field.to_string(), value);
+                    hash.set_expires_at(&field, at);
+                }
+                None if opts.keep_ttl => {
+                    hash.update(field, value);
+                }
+                None => {
+                    hash.insert(field, value);
+                }
+            }
+        }
+
+        if hash.has_expires() {
+            db.track_hash_expires(key);
+        }
+
+        db.remove_if_empty(key);
+        serializer::int(1)
+    })
+}
@@ -0,0 +1,300 @@
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::super::spec_helper::*;
+
+    const WRONG_TYPE: &str =
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+    #[test]
+    fn test_hset_hget() {
+        assert_eq!(run(&["HSET", "hset", "a", "1", "b", "2"]), ":2\r\n");
+        // only new fields are counted
+        assert_eq!(run(&["HSET", "hset", "a", "3", "c", "4"]), ":1\r\n");
+
+        assert_eq!(run(&["HGET", "hset", "a"]), "$1\r\n3\r\n");
+        assert_eq!(run(&["HGET", "hset", "z"]), "$-1\r\n");
+        assert_eq!(run(&["HGET", "hset:missing", "a"]), "$-1\r\n");
+        assert_eq!(
+            run(&["HMGET", "hset", "b", "z", "c"]),
+            "*3\r\n$1\r\n2\r\n$-1\r\n$1\r\n4\r\n"
+        );
+        assert_eq!(run(&["HLEN", "hset"]), ":3\r\n");
+        assert_eq!(run(&["HLEN", "hset:missing"]), ":0\r\n");
+        assert_eq!(run(&["HSTRLEN", "hset", "a"]), ":1\r\n");
+        assert_eq!(run(&["HEXISTS", "hset", "a"]), ":1\r\n");
+        assert_eq!(run(&["HEXISTS", "hset", "z"]), ":0\r\n");
+
+        assert_eq!(
+            run(&["HSET", "hset", "a"]),
+            "-ERR wrong number of arguments for 'hset' command\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hsetnx() {
+        assert_eq!(run(&["HSETNX", "hsetnx", "a", "1"]), ":1\r\n");
+        assert_eq!(run(&["HSETNX", "hsetnx", "a", "2"]), ":0\r\n");
+        assert_eq!(run(&["HGET", "hsetnx", "a"]), "$1\r\n1\r\n");
+    }
+
+    #[test]
+    fn test_hgetall() {
+        run(&["HSET", "hgetall", "a", "1"]);
+
+        assert_eq!(run(&["HGETALL", "hgetall"]), "*2\r\n$1\r\na\r\n$1\r\n1\r\n");
+        assert_eq!(run(&["HKEYS", "hgetall"]), "*1\r\n$1\r\na\r\n");
+        assert_eq!(run(&["HVALS", "hgetall"]), "*1\r\n$1\r\n1\r\n");
+        assert_eq!(run(&["HGETALL", "hgetall:missing"]), "*0\r\n");
+    }
+
+    #[test]
+    fn test_hdel() {
+        run(&["HSET", "hdel", "a", "1", "b", "2"]);
+
+        assert_eq!(run(&["HDEL", "hdel", "a", "z"]), ":1\r\n");
+        assert_eq!(run(&["HDEL", "hdel", "b"]), ":1\r\n");
+
+        // the key is gone with its last field
+        assert_eq!(run(&["EXISTS", "hdel"]), ":0\r\n");
+        assert_eq!(run(&["HDEL", "hdel", "a"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_hincrby() {
+        assert_eq!(run(&["HINCRBY", "hincrby", "n", "5"]), ":5\r\n");
+        assert_eq!(run(&["HINCRBY", "hincrby", "n", "-7"]), ":-2\r\n");
+
+        run(&["HSET", "hincrby", "s", "x", "max", "9223372036854775807"]);
+        assert_eq!(
+            run(&["HINCRBY", "hincrby", "s", "1"]),
+            "-ERR hash value is not an integer\r\n"
+        );
+        assert_eq!(
+            run(&["HINCRBY", "hincrby", "max", "1"]),
+            "-ERR increment or decrement would overflow\r\n"
+        );
+        assert_eq!(
+            run(&["HINCRBY", "hincrby", "n", "x"]),
+            "-ERR value is not an integer or out of range\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hincrbyfloat() {
+        assert_eq!(
+            run(&["HINCRBYFLOAT", "hincrbyfloat", "f", "10.5"]),
+            "$4\r\n10.5\r\n"
+        );
+        assert_eq!(
+            run(&["HINCRBYFLOAT", "hincrbyfloat", "f", "0.1"]),
+            "$4\r\n10.6\r\n"
+        );
+        assert_eq!(
+            run(&["HINCRBYFLOAT", "hincrbyfloat", "f", "-10.6"]),
+            "$1\r\n0\r\n"
+        );
+
+        run(&["HSET", "hincrbyfloat", "s", "x"]);
+        assert_eq!(
+            run(&["HINCRBYFLOAT", "hincrbyfloat", "s", "1"]),
+            "-ERR hash value is not a float\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hrandfield() {
+        run(&["HSET", "hrandfield", "a", "1", "b", "2"]);
+
+        assert_eq!(
+            run(&["HRANDFIELD", "hrandfield", "5"]).lines().count(),
+            1 + 2 * 2
+        );
+        assert_eq!(
+            run(&["HRANDFIELD", "hrandfield", "-5"]).lines().count(),
+            1 + 5 * 2
+        );
+        assert_eq!(
+            run(&["HRANDFIELD", "hrandfield", "-3", "WITHVALUES"])
+                .lines()
+                .count(),
+            1 + 3 * 4
+        );
+        assert_eq!(run(&["HRANDFIELD", "hrandfield:missing", "-3"]), "*0\r\n");
+
+        // any positive count is fine, it is capped by the size of the key
+        assert_eq!(
+            run(&["HRANDFIELD", "hrandfield", "9223372036854775807"])
+                .lines()
+                .count(),
+            1 + 2 * 2
+        );
+
+        // out of range counts are refused before anything is allocated
+        assert_eq!(
+            run(&["HRANDFIELD", "hrandfield", "-9223372036854775808"]),
+            "-ERR value is out of range\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "HRANDFIELD",
+                "hrandfield",
+                "-4611686018427387904",
+                "WITHVALUES"
+            ]),
+            "-ERR value is out of range\r\n"
+        );
+        assert_eq!(run(&["HLEN", "hrandfield"]), ":2\r\n");
+    }
+
+    #[test]
+    fn test_hscan() {
+        for i in 0..100 {
+            run(&["HSET", "hscan", &format!("f{}", i), "v"]);
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = "0".to_string();
+
+        loop {
+            let reply = run(&["HSCAN", "hscan", &cursor, "COUNT", "10", "NOVALUES"]);
+            let lines: Vec<&str> = reply.split("\r\n").collect();
+            let fields: Vec<&str> = lines[4..lines.len() - 1]
+                .iter()
+                .skip(1)
+                .step_by(2)
+                .cloned()
+                .collect();
+
+            assert!(fields.len() <= 11);
+
+            for field in fields {
+                assert!(seen.insert(field.to_string()));
+            }
+
+            cursor = lines[2].to_string();
+
+            if cursor == "0" {
+                break;
+            }
+        }
+
+        assert_eq!(seen.len(), 100);
+        assert_eq!(
+            run(&["HSCAN", "hscan", "0", "MATCH", "f7", "COUNT", "1000"]),
+            "*2\r\n$1\r\n0\r\n*2\r\n$2\r\nf7\r\n$1\r\nv\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hexpire_conditions() {
+        run(&["HSET", "hexpire", "a", "1", "b", "2"]);
+
+        // NX only sets a TTL on fields without one, XX only on fields with one
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "100", "XX", "FIELDS", "1", "a"]),
+            "*1\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "100", "NX", "FIELDS", "2", "a", "z"]),
+            "*2\r\n:1\r\n:-2\r\n"
+        );
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "200", "NX", "FIELDS", "1", "a"]),
+            "*1\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&["HPEXPIRE", "hexpire", "200000", "XX", "FIELDS", "1", "a"]),
+            "*1\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(&["HTTL", "hexpire", "FIELDS", "2", "a", "b"]),
+            "*2\r\n:200\r\n:-1\r\n"
+        );
+
+        // a field without a TTL never expires: it is greater than any TTL
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "300", "GT", "FIELDS", "2", "a", "b"]),
+            "*2\r\n:1\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "250", "GT", "FIELDS", "1", "a"]),
+            "*1\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&["HPEXPIRE", "hexpire", "250000", "LT", "FIELDS", "2", "a", "b"]),
+            "*2\r\n:1\r\n:1\r\n"
+        );
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire", "400", "LT", "FIELDS", "1", "a"]),
+            "*1\r\n:0\r\n"
+        );
+        assert_eq!(
+            run(&["HTTL", "hexpire", "FIELDS", "2", "a", "b"]),
+            "*2\r\n:250\r\n:250\r\n"
+        );
+
+        assert_eq!(
+            run(&["HEXPIRE", "hexpire:missing", "100", "FIELDS", "1", "a"]),
+            "*1\r\n:-2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hpersist() {
+        run(&["HSET", "hpersist", "a", "1", "b", "2"]);
+        run(&["HEXPIRE", "hpersist", "100", "FIELDS", "1", "a"]);
+
+        assert_eq!(
+            run(&["HPERSIST", "hpersist", "FIELDS", "3", "a", "b", "z"]),
+            "*3\r\n:1\r\n:-1\r\n:-2\r\n"
+        );
+        assert_eq!(
+            run(&["HTTL", "hpersist", "FIELDS", "1", "a"]),
+            "*1\r\n:-1\r\n"
+        );
+        assert_eq!(
+            run(&["HPERSIST", "hpersist:missing", "FIELDS", "1", "a"]),
+            "*1\r\n:-2\r\n"
+        );
+
+        // a persisted field is left alone once its old TTL is over
+        run(&["HPEXPIRE", "hpersist", "20", "FIELDS", "1", "b"]);
+        run(&["HPERSIST", "hpersist", "FIELDS", "1", "b"]);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(run(&["HLEN", "hpersist"]), ":2\r\n");
+    }
+
+    #[test]
+    fn test_expired_fields_deleted() {
+        run(&["HSET", "hexpired", "a", "1", "b", "2", "c", "3"]);
+        run(&["HPEXPIRE", "hexpired", "20", "FIELDS", "1", "a"]);
+        run(&["HPEXPIRE", "hexpired", "40", "FIELDS", "1", "b"]);
+        run(&["HPEXPIRE", "hexpired", "100000", "FIELDS", "1", "c"]);
+
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(run(&["HLEN", "hexpired"]), ":2\r\n");
+        assert_eq!(run(&["HEXISTS", "hexpired", "a"]), ":0\r\n");
+
+        // the hash is deleted along with its last field
+        run(&["HPERSIST", "hexpired", "FIELDS", "1", "c"]);
+        run(&["HDEL", "hexpired", "c"]);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(run(&["HLEN", "hexpired"]), ":0\r\n");
+        assert_eq!(run(&["EXISTS", "hexpired"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        run(&["SET", "hash:string", "x"]);
+
+        assert_eq!(run(&["HSET", "hash:string", "a", "1"]), WRONG_TYPE);
+        assert_eq!(run(&["HGET", "hash:string", "a"]), WRONG_TYPE);
+        assert_eq!(run(&["HLEN", "hash:string"]), WRONG_TYPE);
+        assert_eq!(run(&["HGETALL", "hash:string"]), WRONG_TYPE);
+
+        run(&["HSET", "hash:hash", "a", "1"]);
+        assert_eq!(run(&["GET", "hash:hash"]), WRONG_TYPE);
+    }
+}
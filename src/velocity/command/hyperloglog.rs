@@ -0,0 +1,122 @@
+use std::iter;
+
+use super::{wrong_args, wrong_type};
+use crate::velocity::{
+    database::{DataTypes, Database, DatabaseOps},
+    hyperloglog::{HllErr, HyperLogLog},
+    query::Query,
+    serializer,
+};
+
+fn invalid_hll(err: HllErr) -> Vec<u8> {
+    match err {
+        HllErr::NotHll => serializer::err("WRONGTYPE Key is not a valid HyperLogLog string value."),
+        HllErr::Corrupted => serializer::err("INVALIDOBJ Corrupted HLL object detected"),
+    }
+}
+
+// the HLL stored at `key`, or the error reply if it holds something else
+fn get_hll(db: &Database, key: &str) -> Result<Option<HyperLogLog>, Vec<u8>> {
+    match db.get_string(key) {
+        Ok(Some(data)) => HyperLogLog::decode(data).map(Some).map_err(invalid_hll),
+        Ok(None) => Ok(None),
+        Err(_) => Err(wrong_type()),
+    }
+}
+
+pub fn handle_pfadd(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() {
+        return wrong_args("pfadd");
+    }
+
+    db.transaction(|db| {
+        let (mut hll, mut changed) = match get_hll(db, key) {
+            Ok(Some(hll)) => (hll, false),
+            Ok(None) => (HyperLogLog::new(), true),
+            Err(reply) => return reply,
+        };
+
+        // elements are hashed as sent, not through their lossy strings
+        for element in &query.raw_args[1..] {
+            changed |= hll.add(element);
+        }
+
+        if changed {
+            db.update(key, DataTypes::String(hll.encode()));
+        }
+
+        serializer::int(changed as i64)
+    })
+}
+
+/*
+ * a single key is counted on its own, and its cached cardinality refreshed
+ * along the way. several keys are counted as their union, which is not kept
+ */
+pub fn handle_pfcount(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() {
+        return wrong_args("pfcount");
+    }
+
+    db.transaction(|db| {
+        if query.args.is_empty() {
+            let mut hll = match get_hll(db, key) {
+                Ok(Some(hll)) => hll,
+                Ok(None) => return serializer::int(0),
+                Err(reply) => return reply,
+            };
+
+            let count = hll.count();
+            let data = hll.encode();
+
+            if !matches!(db.get_string(key), Ok(Some(old)) if *old == data) {
+                db.update(key, DataTypes::String(data));
+            }
+
+            return serializer::int(count as i64);
+        }
+
+        let mut union = HyperLogLog::new();
+
+        for key in iter::once(key).chain(&query.args) {
+            match get_hll(db, key) {
+                Ok(Some(hll)) => union.merge(&hll),
+                Ok(None) => {}
+                Err(reply) => return reply,
+            }
+        }
+
+        serializer::int(union.count() as i64)
+    })
+}
+
+pub fn handle_pfmerge(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let dest = &query.command_str;
+
+    if dest.is_empty() {
+        return wrong_args("pfmerge");
+    }
+
+    db.transaction(|db| {
+        let mut merged = HyperLogLog::new();
+
+        // the destination takes part in the union when it already exists
+        for key in iter::once(dest).chain(&query.args) {
+            match get_hll(db, key) {
+                Ok(Some(hll)) => merged.merge(&hll),
+                Ok(None) => {}
+                Err(reply) => return reply,
+            }
+        }
+
+        db.update(dest, DataTypes::String(merged.encode()));
+        serializer::str("OK")
+    })
+}
@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    use super::super::spec_helper::*;
+
+    #[test]
+    fn test_updates_keep_ttl() {
+        run(&["PFADD", "pf:ttl", "a"]);
+        set_ttl("pf:ttl");
+
+        // a HyperLogLog is changed in place, unlike what STORE overwrites
+        assert_eq!(run(&["PFADD", "pf:ttl", "b", "c"]), ":1\r\n");
+        assert_eq!(run(&["PFCOUNT", "pf:ttl"]), ":3\r\n");
+        assert!(has_ttl("pf:ttl"));
+
+        run(&["PFADD", "pf:other", "d"]);
+        assert_eq!(run(&["PFMERGE", "pf:ttl", "pf:other"]), "+OK\r\n");
+        assert_eq!(run(&["PFCOUNT", "pf:ttl"]), ":4\r\n");
+        assert!(has_ttl("pf:ttl"));
+    }
+}
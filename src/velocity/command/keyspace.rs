@@ -0,0 +1,190 @@
+use std::iter;
+
+use super::{not_an_integer, syntax_error, wrong_args};
+use crate::velocity::{database::DatabaseOps, object, query::Query, serializer};
+
+// every key of the query: `command_str` followed by `args`
+fn all_keys(query: &Query) -> impl Iterator<Item = &String> {
+    iter::once(&query.command_str).chain(&query.args)
+}
+
+pub fn handle_type(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("type");
+    }
+
+    db.transaction(|db| match db.get_value(key) {
+        Some(value) => serializer::str(value.type_name()),
+        None => serializer::str("none"),
+    })
+}
+
+// DEL and UNLINK, which only differ in redis by freeing memory lazily
+pub fn handle_del(query: &Query, command: &str) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() {
+        return wrong_args(command);
+    }
+
+    db.transaction(|db| {
+        let removed = all_keys(query)
+            .filter(|key| db.remove(key).is_some())
+            .count();
+
+        serializer::int(removed as i64)
+    })
+}
+
+// RENAME and RENAMENX, which does nothing if the new name is taken
+pub fn handle_rename(query: &Query, command: &str, nx: bool) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args(command);
+    }
+
+    let new_key = &query.args[0];
+
+    db.transaction(|db| {
+        if !db.contains_key(key) {
+            return serializer::err("ERR no such key");
+        }
+
+        let renamed = match nx {
+            true => serializer::int(1),
+            false => serializer::str("OK"),
+        };
+
+        if key == new_key {
+            return match nx {
+                true => serializer::int(0),
+                false => renamed,
+            };
+        }
+
+        if nx && db.contains_key(new_key) {
+            return serializer::int(0);
+        }
+
+        db.rename(key, new_key);
+        renamed
+    })
+}
+
+// COPY source destination [DB destination-db] [REPLACE]
+pub fn handle_copy(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("copy");
+    }
+
+    let dst = &query.args[0];
+    let mut replace = false;
+    let mut options = query.args[1..].iter();
+
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "REPLACE" => replace = true,
+            "DB" => {
+                let index = match options.next() {
+                    Some(index) => index,
+                    None => return syntax_error(),
+                };
+
+                // there is a single database, the one every key lives in
+                match index.parse::<i64>() {
+                    Ok(0) => {}
+                    Ok(_) => return serializer::err("ERR DB index is out of range"),
+                    Err(_) => return not_an_integer(),
+                }
+            }
+            _ => return syntax_error(),
+        }
+    }
+
+    if key == dst {
+        return serializer::err("ERR source and destination objects are the same");
+    }
+
+    db.transaction(|db| {
+        if !db.contains_key(key) || (!replace && db.contains_key(dst)) {
+            return serializer::int(0);
+        }
+
+        db.copy(key, dst);
+        serializer::int(1)
+    })
+}
+
+pub fn handle_touch(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() {
+        return wrong_args("touch");
+    }
+
+    db.transaction(|db| {
+        let touched = all_keys(query).filter(|key| db.touch(key)).count();
+        serializer::int(touched as i64)
+    })
+}
+
+pub fn handle_randomkey(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if !query.raw_args.is_empty() {
+        return wrong_args("randomkey");
+    }
+
+    db.transaction(|db| match db.random_key() {
+        Some(key) => serializer::bulk_str(&key),
+        None => serializer::nil(),
+    })
+}
+
+// OBJECT ENCODING|IDLETIME|FREQ|REFCOUNT, which do not count as an access
+pub fn handle_object(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let subcommand = query.command_str.to_lowercase();
+    let args = &query.args;
+
+    if subcommand.is_empty() {
+        return wrong_args("object");
+    }
+
+    if !["encoding", "idletime", "freq", "refcount"].contains(&subcommand.as_str()) {
+        let msg = format!(
+            "ERR unknown subcommand '{}'. Try OBJECT HELP.",
+            query.command_str
+        );
+        return serializer::err(&msg);
+    }
+
+    if args.len() != 1 {
+        return wrong_args(&format!("object|{}", subcommand));
+    }
+
+    let key = &args[0];
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+
+    db.transaction(|db| {
+        let (value, meta) = match (db.get_value(key), db.get_metadata(key)) {
+            (Some(value), Some(meta)) => (value, meta),
+            _ => return serializer::nil(),
+        };
+
+        match subcommand.as_str() {
+            "encoding" => serializer::bulk_str(&meta.encoding().to_string()),
+            "idletime" => serializer::int((meta.idle_time(now) / 1000) as i64),
+            "freq" => serializer::int(meta.frequency(now) as i64),
+            _ => serializer::int(object::refcount(value)),
+        }
+    })
+}
@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::spec_helper::*;
+
+    const WAIT: Duration = Duration::from_secs(2);
+
+    fn encoding(key: &str) -> String {
+        run(&["OBJECT", "ENCODING", key])
+    }
+
+    #[test]
+    fn test_type() {
+        run(&["SET", "type:string", "x"]);
+        run(&["RPUSH", "type:list", "x"]);
+        run(&["SADD", "type:set", "x"]);
+        run(&["ZADD", "type:zset", "1", "x"]);
+        run(&["HSET", "type:hash", "f", "x"]);
+
+        assert_eq!(run(&["TYPE", "type:string"]), "+string\r\n");
+        assert_eq!(run(&["TYPE", "type:list"]), "+list\r\n");
+        assert_eq!(run(&["TYPE", "type:set"]), "+set\r\n");
+        assert_eq!(run(&["TYPE", "type:zset"]), "+zset\r\n");
+        assert_eq!(run(&["TYPE", "type:hash"]), "+hash\r\n");
+        assert_eq!(run(&["TYPE", "type:missing"]), "+none\r\n");
+    }
+
+    #[test]
+    fn test_del_unlink() {
+        run(&["SET", "del:a", "x"]);
+        run(&["SET", "del:b", "x"]);
+        run(&["SET", "del:c", "x"]);
+
+        assert_eq!(run(&["DEL", "del:a", "del:b", "del:missing"]), ":2\r\n");
+        assert_eq!(run(&["UNLINK", "del:c", "del:c"]), ":1\r\n");
+        assert_eq!(run(&["EXISTS", "del:a"]), ":0\r\n");
+        assert_eq!(run(&["EXISTS", "del:c"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_rename() {
+        run(&["SET", "rename:a", "1"]);
+        set_ttl("rename:a");
+
+        // the TTL goes along with the value
+        assert_eq!(run(&["RENAME", "rename:a", "rename:b"]), "+OK\r\n");
+        assert_eq!(run(&["EXISTS", "rename:a"]), ":0\r\n");
+        assert_eq!(run(&["GET", "rename:b"]), "$1\r\n1\r\n");
+        assert!(has_ttl("rename:b"));
+
+        // and the one of the key it replaces is dropped
+        run(&["SET", "rename:c", "2"]);
+        assert_eq!(run(&["RENAME", "rename:c", "rename:b"]), "+OK\r\n");
+        assert_eq!(run(&["GET", "rename:b"]), "$1\r\n2\r\n");
+        assert!(!has_ttl("rename:b"));
+
+        assert_eq!(run(&["RENAME", "rename:b", "rename:b"]), "+OK\r\n");
+        assert_eq!(
+            run(&["RENAME", "rename:missing", "rename:b"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    #[test]
+    fn test_renamenx() {
+        run(&["SET", "renamenx:a", "1"]);
+        run(&["SET", "renamenx:b", "2"]);
+
+        assert_eq!(run(&["RENAMENX", "renamenx:a", "renamenx:b"]), ":0\r\n");
+        assert_eq!(run(&["RENAMENX", "renamenx:a", "renamenx:c"]), ":1\r\n");
+        assert_eq!(run(&["GET", "renamenx:c"]), "$1\r\n1\r\n");
+        assert_eq!(
+            run(&["RENAMENX", "renamenx:missing", "renamenx:d"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    #[test]
+    fn test_copy() {
+        run(&["RPUSH", "copy:src", "a", "b"]);
+        set_ttl("copy:src");
+        run(&["SET", "copy:taken", "x"]);
+
+        assert_eq!(run(&["COPY", "copy:src", "copy:dst"]), ":1\r\n");
+        assert_eq!(
+            run(&["LRANGE", "copy:dst", "0", "-1"]),
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n"
+        );
+        assert!(has_ttl("copy:dst"));
+
+        // a copy is not shared with its source
+        run(&["RPUSH", "copy:dst", "c"]);
+        assert_eq!(run(&["LLEN", "copy:src"]), ":2\r\n");
+
+        assert_eq!(run(&["COPY", "copy:src", "copy:taken"]), ":0\r\n");
+        assert_eq!(run(&["TYPE", "copy:taken"]), "+string\r\n");
+        assert_eq!(
+            run(&["COPY", "copy:src", "copy:taken", "DB", "0", "REPLACE"]),
+            ":1\r\n"
+        );
+        assert_eq!(run(&["TYPE", "copy:taken"]), "+list\r\n");
+
+        assert_eq!(run(&["COPY", "copy:missing", "copy:new"]), ":0\r\n");
+        assert_eq!(
+            run(&["COPY", "copy:src", "copy:src"]),
+            "-ERR source and destination objects are the same\r\n"
+        );
+        assert_eq!(
+            run(&["COPY", "copy:src", "copy:new", "DB", "1"]),
+            "-ERR DB index is out of range\r\n"
+        );
+    }
+
+    #[test]
+    fn test_touch() {
+        run(&["SET", "touch:a", "x"]);
+
+        assert_eq!(
+            run(&["TOUCH", "touch:a", "touch:missing", "touch:a"]),
+            ":2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_randomkey() {
+        run(&["SET", "randomkey", "x"]);
+
+        // other specs share the database, there is at least this key
+        assert!(run(&["RANDOMKEY"]).starts_with('$'));
+        assert_eq!(
+            run(&["RANDOMKEY", "x"]),
+            "-ERR wrong number of arguments for 'randomkey' command\r\n"
+        );
+    }
+
+    #[test]
+    fn test_string_encodings() {
+        run(&["SET", "encoding:string", "123"]);
+        assert_eq!(encoding("encoding:string"), "$3\r\nint\r\n");
+
+        run(&["SET", "encoding:string", "007"]);
+        assert_eq!(encoding("encoding:string"), "$6\r\nembstr\r\n");
+
+        run(&["SET", "encoding:string", &"a".repeat(45)]);
+        assert_eq!(encoding("encoding:string"), "$3\r\nraw\r\n");
+
+        // every write encodes the string anew
+        run(&["SET", "encoding:string", "9"]);
+        run(&["INCR", "encoding:string"]);
+        assert_eq!(encoding("encoding:string"), "$3\r\nint\r\n");
+    }
+
+    #[test]
+    fn test_list_encodings() {
+        let item = "a".repeat(3000);
+        run(&["RPUSH", "encoding:list", &item, &item, &item]);
+        assert_eq!(encoding("encoding:list"), "$9\r\nquicklist\r\n");
+
+        // back to a listpack only once under half the limit
+        run(&["LPOP", "encoding:list"]);
+        assert_eq!(encoding("encoding:list"), "$9\r\nquicklist\r\n");
+        run(&["LPOP", "encoding:list"]);
+        assert_eq!(encoding("encoding:list"), "$8\r\nlistpack\r\n");
+    }
+
+    #[test]
+    fn test_hash_encodings() {
+        run(&["HSET", "encoding:hash", "a", "1"]);
+        assert_eq!(encoding("encoding:hash"), "$8\r\nlistpack\r\n");
+
+        // a field TTL makes it a listpackex for good
+        run(&["HEXPIRE", "encoding:hash", "100", "FIELDS", "1", "a"]);
+        assert_eq!(encoding("encoding:hash"), "$10\r\nlistpackex\r\n");
+        run(&["HPERSIST", "encoding:hash", "FIELDS", "1", "a"]);
+        assert_eq!(encoding("encoding:hash"), "$10\r\nlistpackex\r\n");
+
+        let big = "b".repeat(65);
+        run(&["HSET", "encoding:hash", "big", &big]);
+        assert_eq!(encoding("encoding:hash"), "$9\r\nhashtable\r\n");
+        run(&["HDEL", "encoding:hash", "big"]);
+        assert_eq!(encoding("encoding:hash"), "$9\r\nhashtable\r\n");
+    }
+
+    #[test]
+    fn test_zset_encodings() {
+        run(&["ZADD", "encoding:zset", "1", "a"]);
+        assert_eq!(encoding("encoding:zset"), "$8\r\nlistpack\r\n");
+
+        let big = "b".repeat(65);
+        run(&["ZADD", "encoding:zset", "2", &big]);
+        assert_eq!(encoding("encoding:zset"), "$8\r\nskiplist\r\n");
+        run(&["ZREM", "encoding:zset", &big]);
+        assert_eq!(encoding("encoding:zset"), "$8\r\nskiplist\r\n");
+    }
+
+    #[test]
+    fn test_object() {
+        run(&["SET", "object:shared", "100"]);
+        run(&["RPUSH", "object:list", "x"]);
+
+        assert_eq!(
+            run(&["OBJECT", "REFCOUNT", "object:shared"]),
+            ":2147483647\r\n"
+        );
+        assert_eq!(run(&["OBJECT", "REFCOUNT", "object:list"]), ":1\r\n");
+        assert_eq!(
+            run(&["OBJECT", "IDLETIME", "object:list"]).chars().next(),
+            Some(':')
+        );
+        assert_eq!(encoding("object:missing"), "$-1\r\n");
+
+        run(&["XADD", "object:stream", "1-0", "f", "v"]);
+        assert_eq!(encoding("object:stream"), "$6\r\nstream\r\n");
+
+        assert_eq!(
+            run(&["OBJECT", "LENGTH", "object:list"]),
+            "-ERR unknown subcommand 'LENGTH'. Try OBJECT HELP.\r\n"
+        );
+        assert_eq!(
+            run(&["OBJECT", "ENCODING"]),
+            "-ERR wrong number of arguments for 'object|encoding' command\r\n"
+        );
+    }
+
+    #[test]
+    fn test_client_no_touch() {
+        run(&["SET", "notouch", "x"]);
+        assert_eq!(run(&["OBJECT", "FREQ", "notouch"]), ":5\r\n");
+
+        let mut conn = Connection::open();
+        conn.send(&["CLIENT", "NO-TOUCH", "ON"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "+OK\r\n");
+
+        // reading does not count as an access, TOUCH still does
+        conn.send(&["GET", "notouch"]);
+        conn.reply(WAIT).unwrap();
+        assert_eq!(run(&["OBJECT", "FREQ", "notouch"]), ":5\r\n");
+        conn.send(&["TOUCH", "notouch"]);
+        conn.reply(WAIT).unwrap();
+        assert_eq!(run(&["OBJECT", "FREQ", "notouch"]), ":6\r\n");
+
+        conn.send(&["CLIENT", "NO-TOUCH", "maybe"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "-ERR syntax error\r\n");
+        assert_eq!(
+            run(&["CLIENT", "NO-TOUCH"]),
+            "-ERR wrong number of arguments for 'client|no-touch' command\r\n"
+        );
+    }
+}
@@ -0,0 +1,843 @@
+use std::collections::VecDeque;
+
+use super::{
+    normalize_range, not_an_integer, parse_timeout, syntax_error, wrong_args, wrong_type, Attempt,
+};
+use crate::velocity::{
+    blocking,
+    client::Client,
+    database::{Database, DatabaseOps, WrongTypeErr},
+    query::Query,
+    serializer,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+impl ListEnd {
+    fn parse(value: &str) -> Option<ListEnd> {
+        match value.to_uppercase().as_str() {
+            "LEFT" => Some(ListEnd::Left),
+            "RIGHT" => Some(ListEnd::Right),
+            _ => None,
+        }
+    }
+}
+
+fn push(list: &mut VecDeque<Vec<u8>>, end: ListEnd, value: Vec<u8>) {
+    match end {
+        ListEnd::Left => list.push_front(value),
+        ListEnd::Right => list.push_back(value),
+    }
+}
+
+fn pop(list: &mut VecDeque<Vec<u8>>, end: ListEnd) -> Option<Vec<u8>> {
+    match end {
+        ListEnd::Left => list.pop_front(),
+        ListEnd::Right => list.pop_back(),
+    }
+}
+
+/*
+ * pops an element from `src` and pushes it to `dst`, as a single step. `src`
+ * and `dst` may be the same key, in which case the list is rotated
+ *
+ * the type of `dst` is checked before anything is popped, so that an element
+ * is never lost because it could not be pushed
+ */
+fn move_element(
+    db: &mut Database,
+    src: &str,
+    dst: &str,
+    from: ListEnd,
+    to: ListEnd,
+) -> Result<Option<Vec<u8>>, WrongTypeErr> {
+    db.get_list(dst)?;
+
+    let value = match db.get_list_mut(src)? {
+        Some(list) => pop(list, from),
+        None => None,
+    };
+
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    /*
+     * pushing first keeps a one element list rotated onto itself (along with
+     * its TTL) from being deleted and created anew in between
+     */
+    push(db.get_or_create_list(dst)?, to, value.clone());
+    db.remove_if_empty(src);
+
+    Ok(Some(value))
+}
+
+// the key some elements were popped from, with those elements
+type Popped = (String, Vec<Vec<u8>>);
+
+/*
+ * pops up to `count` elements from the first non-empty list among `keys`,
+ * returning the key they were popped from
+ */
+fn pop_first_non_empty(
+    db: &mut Database,
+    keys: &[String],
+    end: ListEnd,
+    count: usize,
+) -> Result<Option<Popped>, WrongTypeErr> {
+    for key in keys {
+        let list = match db.get_list_mut(key)? {
+            Some(list) => list,
+            None => continue,
+        };
+
+        let mut popped: Vec<Vec<u8>> = vec![];
+
+        while popped.len() < count {
+            match pop(list, end) {
+                Some(value) => popped.push(value),
+                None => break,
+            }
+        }
+
+        db.remove_if_empty(key);
+        return Ok(Some((key.to_string(), popped)));
+    }
+
+    Ok(None)
+}
+
+/*
+ * converts a redis style index (where -1 is the last element) into a vector
+ * position. `None` means the index points outside of the list
+ */
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let len = len as i64;
+    let index = if index < 0 { len + index } else { index };
+
+    if index < 0 || index >= len {
+        return None;
+    }
+
+    Some(index as usize)
+}
+
+// LPUSH, RPUSH, LPUSHX & RPUSHX
+pub fn handle_push(query: &Query, end: ListEnd, only_if_exists: bool) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        let command = match (end, only_if_exists) {
+            (ListEnd::Left, false) => "lpush",
+            (ListEnd::Right, false) => "rpush",
+            (ListEnd::Left, true) => "lpushx",
+            (ListEnd::Right, true) => "rpushx",
+        };
+
+        return wrong_args(command);
+    }
+
+    db.transaction(|db| {
+        if only_if_exists {
+            match db.get_list(key) {
+                Ok(Some(_)) => {}
+                Ok(None) => return serializer::int(0),
+                Err(_) => return wrong_type(),
+            }
+        }
+
+        let list = match db.get_or_create_list(key) {
+            Ok(list) => list,
+            Err(_) => return wrong_type(),
+        };
+
+        // the values are pushed as sent, they may hold any bytes
+        for value in query.raw_args[1..].iter() {
+            push(list, end, value.to_vec());
+        }
+
+        serializer::int(list.len() as i64)
+    })
+}
+
+// LPOP & RPOP
+pub fn handle_pop(query: &Query, end: ListEnd) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 1 {
+        return match end {
+            ListEnd::Left => wrong_args("lpop"),
+            ListEnd::Right => wrong_args("rpop"),
+        };
+    }
+
+    // without a count, a single bulk string is returned instead of an array
+    let count = match query.args.first() {
+        Some(count) => match count.parse::<i64>() {
+            Ok(count) if count >= 0 => Some(count as usize),
+            Ok(_) => return serializer::err("ERR value is out of range, must be positive"),
+            Err(_) => return not_an_integer(),
+        },
+        None => None,
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list_mut(key) {
+            Ok(Some(list)) => list,
+            Ok(None) if count.is_some() => return serializer::null_arr(),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let response = match count {
+            Some(count) => {
+                let mut popped: Vec<Vec<u8>> = vec![];
+
+                while popped.len() < count {
+                    match pop(list, end) {
+                        Some(value) => popped.push(value),
+                        None => break,
+                    }
+                }
+
+                serializer::bytes_arr(&popped)
+            }
+
+            None => match pop(list, end) {
+                Some(value) => serializer::bulk_bytes(&value),
+                None => serializer::nil(),
+            },
+        };
+
+        db.remove_if_empty(key);
+        response
+    })
+}
+
+pub fn handle_lrange(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("lrange");
+    }
+
+    let start = query.args[0].parse::<i64>();
+    let stop = query.args[1].parse::<i64>();
+
+    let (start, stop) = match (start, stop) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        _ => return not_an_integer(),
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::str_arr(&[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let values: Vec<Vec<u8>> = match normalize_range(start, stop, list.len()) {
+            Some((start, stop)) => list.range(start..=stop).cloned().collect(),
+            None => vec![],
+        };
+
+        serializer::bytes_arr(&values)
+    })
+}
+
+pub fn handle_llen(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("llen");
+    }
+
+    db.transaction(|db| match db.get_list(key) {
+        Ok(Some(list)) => serializer::int(list.len() as i64),
+        Ok(None) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_lindex(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("lindex");
+    }
+
+    let index = match query.args[0].parse::<i64>() {
+        Ok(index) => index,
+        Err(_) => return not_an_integer(),
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        match normalize_index(index, list.len()) {
+            Some(index) => serializer::bulk_bytes(&list[index]),
+            None => serializer::nil(),
+        }
+    })
+}
+
+pub fn handle_lset(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("lset");
+    }
+
+    let index = match query.args[0].parse::<i64>() {
+        Ok(index) => index,
+        Err(_) => return not_an_integer(),
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list_mut(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::err("ERR no such key"),
+            Err(_) => return wrong_type(),
+        };
+
+        match normalize_index(index, list.len()) {
+            Some(index) => {
+                list[index] = query.raw_args[2].to_vec();
+                serializer::str("OK")
+            }
+            None => serializer::err("ERR index out of range"),
+        }
+    })
+}
+
+pub fn handle_linsert(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 3 {
+        return wrong_args("linsert");
+    }
+
+    let after = match query.args[0].to_uppercase().as_str() {
+        "BEFORE" => false,
+        "AFTER" => true,
+        _ => return syntax_error(),
+    };
+
+    let pivot = &query.raw_args[2];
+    let value = &query.raw_args[3];
+
+    db.transaction(|db| {
+        let list = match db.get_list_mut(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        match list.iter().position(|item| item == pivot) {
+            Some(index) => {
+                let index = if after { index + 1 } else { index };
+                list.insert(index, value.to_vec());
+                serializer::int(list.len() as i64)
+            }
+            None => serializer::int(-1),
+        }
+    })
+}
+
+pub fn handle_lrem(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("lrem");
+    }
+
+    let count = match query.args[0].parse::<i64>() {
+        Ok(count) => count,
+        Err(_) => return not_an_integer(),
+    };
+
+    let value = &query.raw_args[2];
+
+    db.transaction(|db| {
+        let list = match db.get_list_mut(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        /*
+         * a positive count removes from head to tail, a negative count from
+         * tail to head, and zero removes every occurrence
+         */
+        let limit = if count == 0 {
+            usize::MAX
+        } else {
+            count.unsigned_abs() as usize
+        };
+
+        let matches = list.iter().filter(|item| *item == value).count();
+        let removed = matches.min(limit);
+
+        // when removing from the tail, the first few matches have to be kept
+        let mut to_keep = if count < 0 { matches - removed } else { 0 };
+        let mut to_remove = removed;
+
+        list.retain(|item| {
+            if item != value || to_remove == 0 {
+                return true;
+            }
+
+            if to_keep > 0 {
+                to_keep -= 1;
+                return true;
+            }
+
+            to_remove -= 1;
+            false
+        });
+
+        db.remove_if_empty(key);
+        serializer::int(removed as i64)
+    })
+}
+
+pub fn handle_ltrim(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("ltrim");
+    }
+
+    let start = query.args[0].parse::<i64>();
+    let stop = query.args[1].parse::<i64>();
+
+    let (start, stop) = match (start, stop) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        _ => return not_an_integer(),
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list_mut(key) {
+            Ok(Some(list)) => list,
+            Ok(None) => return serializer::str("OK"),
+            Err(_) => return wrong_type(),
+        };
+
+        match normalize_range(start, stop, list.len()) {
+            Some((start, stop)) => {
+                list.truncate(stop + 1);
+                list.drain(..start);
+            }
+            None => list.clear(),
+        }
+
+        db.remove_if_empty(key);
+        serializer::str("OK")
+    })
+}
+
+#[derive(Debug)]
+struct LPosOpts {
+    rank: i64,
+    count: Option<usize>,
+    maxlen: usize,
+}
+
+fn parse_lpos_args(args: &[String]) -> Result<LPosOpts, Vec<u8>> {
+    let mut opts = LPosOpts {
+        rank: 1,
+        count: None,
+        maxlen: 0,
+    };
+
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let value = match args.next() {
+            Some(value) => value.parse::<i64>().map_err(|_| not_an_integer())?,
+            None => return Err(syntax_error()),
+        };
+
+        match arg.to_uppercase().as_str() {
+            "RANK" => {
+                if value == 0 || value == i64::MIN {
+                    let msg = "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list";
+                    return Err(serializer::err(msg));
+                }
+
+                opts.rank = value;
+            }
+
+            "COUNT" => {
+                if value < 0 {
+                    return Err(serializer::err("ERR COUNT can't be negative"));
+                }
+
+                opts.count = Some(value as usize);
+            }
+
+            "MAXLEN" => {
+                if value < 0 {
+                    return Err(serializer::err("ERR MAXLEN can't be negative"));
+                }
+
+                opts.maxlen = value as usize;
+            }
+
+            _ => return Err(syntax_error()),
+        }
+    }
+
+    Ok(opts)
+}
+
+pub fn handle_lpos(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("lpos");
+    }
+
+    let value = &query.raw_args[1];
+    let opts = match parse_lpos_args(&query.args[1..]) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let list = match db.get_list(key) {
+            Ok(Some(list)) => list,
+            Ok(None) if opts.count.is_some() => return serializer::str_arr(&[]),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        // COUNT 0 means "all the matches"
+        let wanted = match opts.count {
+            Some(0) => usize::MAX,
+            Some(count) => count,
+            None => 1,
+        };
+
+        // MAXLEN 0 means "scan the whole list"
+        let maxlen = if opts.maxlen == 0 {
+            list.len()
+        } else {
+            opts.maxlen.min(list.len())
+        };
+
+        let indexes: Box<dyn Iterator<Item = usize>> = if opts.rank > 0 {
+            Box::new(0..maxlen)
+        } else {
+            Box::new((list.len() - maxlen..list.len()).rev())
+        };
+
+        let mut skip = opts.rank.unsigned_abs() - 1;
+        let mut matches: Vec<i64> = vec![];
+
+        for index in indexes {
+            if &list[index] != value {
+                continue;
+            }
+
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+
+            matches.push(index as i64);
+
+            if matches.len() >= wanted {
+                break;
+            }
+        }
+
+        match opts.count {
+            Some(_) => {
+                let matches: Vec<Vec<u8>> = matches.into_iter().map(serializer::int).collect();
+                serializer::arr(&matches)
+            }
+            None => match matches.first() {
+                Some(index) => serializer::int(*index),
+                None => serializer::nil(),
+            },
+        }
+    })
+}
+
+pub fn handle_lmove(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let src = &query.command_str;
+
+    if src.is_empty() || query.args.len() != 3 {
+        return wrong_args("lmove");
+    }
+
+    let dst = &query.args[0];
+    let from = ListEnd::parse(&query.args[1]);
+    let to = ListEnd::parse(&query.args[2]);
+
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return syntax_error(),
+    };
+
+    db.transaction(|db| match move_element(db, src, dst, from, to) {
+        Ok(Some(value)) => serializer::bulk_bytes(&value),
+        Ok(None) => serializer::nil(),
+        Err(_) => wrong_type(),
+    })
+}
+
+// RPOPLPUSH is LMOVE with the directions fixed to RIGHT LEFT
+pub fn handle_rpoplpush(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let src = &query.command_str;
+
+    if src.is_empty() || query.args.len() != 1 {
+        return wrong_args("rpoplpush");
+    }
+
+    let dst = &query.args[0];
+
+    db.transaction(
+        |db| match move_element(db, src, dst, ListEnd::Right, ListEnd::Left) {
+            Ok(Some(value)) => serializer::bulk_bytes(&value),
+            Ok(None) => serializer::nil(),
+            Err(_) => wrong_type(),
+        },
+    )
+}
+
+#[derive(Debug)]
+struct MPopArgs {
+    keys: Vec<String>,
+    end: ListEnd,
+    count: usize,
+}
+
+// parses `numkeys key [key ...] LEFT|RIGHT [COUNT count]`
+fn parse_mpop_args(args: &[String]) -> Result<MPopArgs, Vec<u8>> {
+    let numkeys = match args[0].parse::<i64>() {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return Err(serializer::err("ERR numkeys should be greater than 0")),
+        Err(_) => return Err(not_an_integer()),
+    };
+
+    if args.len() < numkeys + 2 {
+        return Err(syntax_error());
+    }
+
+    let keys = args[1..=numkeys].to_vec();
+    let end = match ListEnd::parse(&args[numkeys + 1]) {
+        Some(end) => end,
+        None => return Err(syntax_error()),
+    };
+
+    let count = match &args[numkeys + 2..] {
+        [] => 1,
+        [modifier, count] if modifier.to_uppercase() == "COUNT" => match count.parse::<i64>() {
+            Ok(count) if count > 0 => count as usize,
+            Ok(_) => return Err(serializer::err("ERR count should be greater than 0")),
+            Err(_) => return Err(not_an_integer()),
+        },
+        _ => return Err(syntax_error()),
+    };
+
+    Ok(MPopArgs { keys, end, count })
+}
+
+pub fn handle_lmpop(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.len() < 2 {
+        return wrong_args("lmpop");
+    }
+
+    let mut args = vec![query.command_str.to_string()];
+    args.extend(query.args.iter().cloned());
+
+    let args = match parse_mpop_args(&args) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    db.transaction(
+        |db| match pop_first_non_empty(db, &args.keys, args.end, args.count) {
+            Ok(Some((key, values))) => mpop_reply(key, values),
+            Ok(None) => serializer::null_arr(),
+            Err(_) => wrong_type(),
+        },
+    )
+}
+
+fn pop_reply(key: String, values: Vec<Vec<u8>>) -> Vec<u8> {
+    serializer::arr(&[
+        serializer::bulk_str(&key),
+        serializer::bulk_bytes(&values[0]),
+    ])
+}
+
+fn mpop_reply(key: String, values: Vec<Vec<u8>>) -> Vec<u8> {
+    serializer::arr(&[serializer::bulk_str(&key), serializer::bytes_arr(&values)])
+}
+
+// BLPOP & BRPOP
+pub fn handle_bpop(query: &Query, client: &Client, end: ListEnd) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.is_empty() {
+        return match end {
+            ListEnd::Left => wrong_args("blpop"),
+            ListEnd::Right => wrong_args("brpop"),
+        };
+    }
+
+    let mut keys = vec![query.command_str.to_string()];
+    keys.extend(query.args.iter().cloned());
+
+    let timeout = match parse_timeout(&keys.pop().unwrap()) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+
+    let attempt = db.transaction(|db| match pop_first_non_empty(db, &keys, end, 1) {
+        Ok(Some((key, values))) => Attempt::Reply(pop_reply(key, values)),
+        Err(_) => Attempt::Reply(wrong_type()),
+        Ok(None) => {
+            let serve = move |db: &mut Database, key: &str| match pop_first_non_empty(
+                db,
+                &[key.to_string()],
+                end,
+                1,
+            ) {
+                Ok(Some((key, values))) => Some(pop_reply(key, values)),
+                // the key may have been created with another type
+                Ok(None) | Err(_) => None,
+            };
+
+            blocking::block(db, client, &keys, timeout, Box::new(serve));
+            Attempt::Blocked
+        }
+    });
+
+    match attempt {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
+
+pub fn handle_blmove(query: &Query, client: &Client) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let src = query.command_str.to_string();
+
+    if src.is_empty() || query.args.len() != 4 {
+        return wrong_args("blmove");
+    }
+
+    let dst = query.args[0].to_string();
+    let from = ListEnd::parse(&query.args[1]);
+    let to = ListEnd::parse(&query.args[2]);
+
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return syntax_error(),
+    };
+
+    let timeout = match parse_timeout(&query.args[3]) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+
+    let attempt = db.transaction(|db| match move_element(db, &src, &dst, from, to) {
+        Ok(Some(value)) => Attempt::Reply(serializer::bulk_bytes(&value)),
+        Err(_) => Attempt::Reply(wrong_type()),
+        Ok(None) => {
+            let keys = [src.to_string()];
+            let serve = move |db: &mut Database, _: &str| {
+                // `src` may have been created with another type: keep waiting
+                if db.get_list(&src).is_err() {
+                    return None;
+                }
+
+                match move_element(db, &src, &dst, from, to) {
+                    Ok(Some(value)) => Some(serializer::bulk_bytes(&value)),
+                    Ok(None) => None,
+                    Err(_) => Some(wrong_type()),
+                }
+            };
+
+            blocking::block(db, client, &keys, timeout, Box::new(serve));
+            Attempt::Blocked
+        }
+    });
+
+    match attempt {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
+
+pub fn handle_blmpop(query: &Query, client: &Client) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.len() < 3 {
+        return wrong_args("blmpop");
+    }
+
+    let timeout = match parse_timeout(&query.command_str) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+
+    let args = match parse_mpop_args(&query.args) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    let MPopArgs { keys, end, count } = args;
+
+    let attempt = db.transaction(|db| match pop_first_non_empty(db, &keys, end, count) {
+        Ok(Some((key, values))) => Attempt::Reply(mpop_reply(key, values)),
+        Err(_) => Attempt::Reply(wrong_type()),
+        Ok(None) => {
+            let serve = move |db: &mut Database, key: &str| match pop_first_non_empty(
+                db,
+                &[key.to_string()],
+                end,
+                count,
+            ) {
+                Ok(Some((key, values))) => Some(mpop_reply(key, values)),
+                // the key may have been created with another type
+                Ok(None) | Err(_) => None,
+            };
+
+            blocking::block(db, client, &keys, timeout, Box::new(serve));
+            Attempt::Blocked
+        }
+    });
+
+    match attempt {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
@@ -0,0 +1,310 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::spec_helper::*;
+
+    const WAIT: Duration = Duration::from_secs(2);
+    // long enough for a command sent before to be blocked
+    const SETTLE: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_push_pop() {
+        assert_eq!(run(&["LPUSHX", "push", "a"]), ":0\r\n");
+        assert_eq!(run(&["EXISTS", "push"]), ":0\r\n");
+
+        // LPUSH inserts its values one after the other, so they end up reversed
+        assert_eq!(run(&["LPUSH", "push", "b", "a"]), ":2\r\n");
+        assert_eq!(run(&["RPUSH", "push", "c", "d"]), ":4\r\n");
+        assert_eq!(run(&["RPUSHX", "push", "e"]), ":5\r\n");
+        assert_eq!(
+            run(&["LRANGE", "push", "0", "-1"]),
+            "*5\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\nd\r\n$1\r\ne\r\n"
+        );
+
+        assert_eq!(run(&["LPOP", "push"]), "$1\r\na\r\n");
+        assert_eq!(run(&["RPOP", "push", "2"]), "*2\r\n$1\r\ne\r\n$1\r\nd\r\n");
+        assert_eq!(run(&["LPOP", "push", "0"]), "*0\r\n");
+        assert_eq!(
+            run(&["LPOP", "push", "-1"]),
+            "-ERR value is out of range, must be positive\r\n"
+        );
+
+        // popping more than there is empties the list, and deletes it
+        assert_eq!(run(&["LPOP", "push", "10"]), "*2\r\n$1\r\nb\r\n$1\r\nc\r\n");
+        assert_eq!(run(&["EXISTS", "push"]), ":0\r\n");
+        assert_eq!(run(&["LPOP", "push"]), "$-1\r\n");
+        assert_eq!(run(&["LPOP", "push", "1"]), "*-1\r\n");
+    }
+
+    #[test]
+    fn test_lrange_lindex() {
+        run(&["RPUSH", "lrange", "a", "b", "c"]);
+
+        assert_eq!(
+            run(&["LRANGE", "lrange", "-2", "100"]),
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+        assert_eq!(run(&["LRANGE", "lrange", "-100", "0"]), "*1\r\n$1\r\na\r\n");
+        assert_eq!(run(&["LRANGE", "lrange", "2", "1"]), "*0\r\n");
+        assert_eq!(run(&["LRANGE", "lrange", "5", "10"]), "*0\r\n");
+        assert_eq!(run(&["LRANGE", "lrange:missing", "0", "-1"]), "*0\r\n");
+
+        assert_eq!(run(&["LINDEX", "lrange", "-1"]), "$1\r\nc\r\n");
+        assert_eq!(run(&["LINDEX", "lrange", "3"]), "$-1\r\n");
+        assert_eq!(run(&["LINDEX", "lrange", "-4"]), "$-1\r\n");
+        assert_eq!(run(&["LLEN", "lrange"]), ":3\r\n");
+        assert_eq!(run(&["LLEN", "lrange:missing"]), ":0\r\n");
+
+        assert_eq!(
+            run(&["LINDEX", "lrange", "x"]),
+            "-ERR value is not an integer or out of range\r\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_values() {
+        let value: &[u8] = b"\xff\x00\r\n";
+
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"RPUSH", b"binary", value, b"x"])),
+            b":2\r\n"
+        );
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LINDEX", b"binary", b"0"])),
+            b"$4\r\n\xff\x00\r\n\r\n"
+        );
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LPOS", b"binary", value])),
+            b":0\r\n"
+        );
+
+        run_bytes(&encode_bytes(&[b"LSET", b"binary", b"1", b"\xfe"]));
+        run_bytes(&encode_bytes(&[
+            b"LINSERT", b"binary", b"AFTER", value, b"\xfd",
+        ]));
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LRANGE", b"binary", b"0", b"-1"])),
+            b"*3\r\n$4\r\n\xff\x00\r\n\r\n$1\r\n\xfd\r\n$1\r\n\xfe\r\n"
+        );
+
+        // a value that is not valid UTF-8 is still only equal to itself
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LREM", b"binary", b"0", b"\xfc"])),
+            b":0\r\n"
+        );
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LREM", b"binary", b"0", b"\xfd"])),
+            b":1\r\n"
+        );
+
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"RPOP", b"binary", b"2"])),
+            b"*2\r\n$1\r\n\xfe\r\n$4\r\n\xff\x00\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_lset() {
+        run(&["RPUSH", "lset", "a", "b", "c"]);
+
+        assert_eq!(run(&["LSET", "lset", "1", "x"]), "+OK\r\n");
+        assert_eq!(run(&["LSET", "lset", "-1", "y"]), "+OK\r\n");
+        assert_eq!(
+            run(&["LRANGE", "lset", "0", "-1"]),
+            "*3\r\n$1\r\na\r\n$1\r\nx\r\n$1\r\ny\r\n"
+        );
+
+        assert_eq!(
+            run(&["LSET", "lset", "3", "z"]),
+            "-ERR index out of range\r\n"
+        );
+        assert_eq!(
+            run(&["LSET", "lset:missing", "0", "z"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    #[test]
+    fn test_linsert() {
+        run(&["RPUSH", "linsert", "a", "c"]);
+
+        assert_eq!(run(&["LINSERT", "linsert", "BEFORE", "c", "b"]), ":3\r\n");
+        assert_eq!(run(&["LINSERT", "linsert", "AFTER", "c", "d"]), ":4\r\n");
+        assert_eq!(run(&["LINSERT", "linsert", "AFTER", "z", "d"]), ":-1\r\n");
+        assert_eq!(
+            run(&["LINSERT", "linsert:missing", "AFTER", "a", "b"]),
+            ":0\r\n"
+        );
+        assert_eq!(
+            run(&["LRANGE", "linsert", "0", "-1"]),
+            "*4\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\nd\r\n"
+        );
+    }
+
+    #[test]
+    fn test_lrem() {
+        run(&["RPUSH", "lrem", "a", "b", "a", "c", "a"]);
+
+        // from the tail when negative
+        assert_eq!(run(&["LREM", "lrem", "-1", "a"]), ":1\r\n");
+        assert_eq!(
+            run(&["LRANGE", "lrem", "0", "-1"]),
+            "*4\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\na\r\n$1\r\nc\r\n"
+        );
+
+        assert_eq!(run(&["LREM", "lrem", "0", "a"]), ":2\r\n");
+        assert_eq!(run(&["LREM", "lrem", "0", "b"]), ":1\r\n");
+        assert_eq!(run(&["LREM", "lrem", "0", "c"]), ":1\r\n");
+
+        // the emptied list is gone
+        assert_eq!(run(&["EXISTS", "lrem"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_ltrim() {
+        run(&["RPUSH", "ltrim", "a", "b", "c", "d"]);
+
+        assert_eq!(run(&["LTRIM", "ltrim", "1", "-2"]), "+OK\r\n");
+        assert_eq!(
+            run(&["LRANGE", "ltrim", "0", "-1"]),
+            "*2\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+
+        assert_eq!(run(&["LTRIM", "ltrim", "5", "10"]), "+OK\r\n");
+        assert_eq!(run(&["EXISTS", "ltrim"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_lpos() {
+        run(&["RPUSH", "lpos", "a", "b", "c", "b", "b"]);
+
+        assert_eq!(run(&["LPOS", "lpos", "b"]), ":1\r\n");
+        assert_eq!(run(&["LPOS", "lpos", "b", "RANK", "-1"]), ":4\r\n");
+        assert_eq!(run(&["LPOS", "lpos", "b", "RANK", "2"]), ":3\r\n");
+        assert_eq!(
+            run(&["LPOS", "lpos", "b", "COUNT", "0"]),
+            "*3\r\n:1\r\n:3\r\n:4\r\n"
+        );
+        assert_eq!(
+            run(&["LPOS", "lpos", "b", "COUNT", "0", "MAXLEN", "4"]),
+            "*2\r\n:1\r\n:3\r\n"
+        );
+        assert_eq!(run(&["LPOS", "lpos", "z"]), "$-1\r\n");
+        assert_eq!(
+            run(&["LPOS", "lpos", "b", "COUNT", "-1"]),
+            "-ERR COUNT can't be negative\r\n"
+        );
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        run(&["SET", "list:string", "x"]);
+
+        assert_eq!(
+            run(&["LSET", "list:string", "0", "x"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+        assert_eq!(
+            run(&["LPOS", "list:string", "x"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    }
+
+    #[test]
+    fn test_lmove() {
+        run(&["RPUSH", "lmove:src", "a", "b", "c"]);
+
+        assert_eq!(
+            run(&["LMOVE", "lmove:src", "lmove:dst", "LEFT", "RIGHT"]),
+            "$1\r\na\r\n"
+        );
+        assert_eq!(run(&["RPOPLPUSH", "lmove:src", "lmove:dst"]), "$1\r\nc\r\n");
+        assert_eq!(
+            run(&["LRANGE", "lmove:dst", "0", "-1"]),
+            "*2\r\n$1\r\nc\r\n$1\r\na\r\n"
+        );
+        assert_eq!(
+            run(&["LMOVE", "lmove:missing", "lmove:dst", "LEFT", "LEFT"]),
+            "$-1\r\n"
+        );
+
+        // nothing is popped when the destination can not take it
+        run(&["SET", "lmove:string", "x"]);
+        assert_eq!(
+            run(&["LMOVE", "lmove:src", "lmove:string", "LEFT", "LEFT"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+        assert_eq!(run(&["LLEN", "lmove:src"]), ":1\r\n");
+    }
+
+    #[test]
+    fn test_lmove_rotation() {
+        run(&["RPUSH", "rotate", "a", "b", "c"]);
+
+        assert_eq!(
+            run(&["LMOVE", "rotate", "rotate", "LEFT", "RIGHT"]),
+            "$1\r\na\r\n"
+        );
+        assert_eq!(
+            run(&["LRANGE", "rotate", "0", "-1"]),
+            "*3\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\na\r\n"
+        );
+
+        // a single element is rotated in place, keeping the TTL of the list
+        run(&["RPUSH", "rotate:one", "a"]);
+        set_ttl("rotate:one");
+
+        assert_eq!(
+            run(&["RPOPLPUSH", "rotate:one", "rotate:one"]),
+            "$1\r\na\r\n"
+        );
+        assert_eq!(run(&["LLEN", "rotate:one"]), ":1\r\n");
+        assert!(has_ttl("rotate:one"));
+    }
+
+    #[test]
+    fn test_lmpop() {
+        run(&["RPUSH", "lmpop:b", "a", "b", "c"]);
+
+        assert_eq!(
+            run(&["LMPOP", "2", "lmpop:a", "lmpop:b", "RIGHT", "COUNT", "2"]),
+            "*2\r\n$7\r\nlmpop:b\r\n*2\r\n$1\r\nc\r\n$1\r\nb\r\n"
+        );
+        assert_eq!(run(&["LMPOP", "1", "lmpop:a", "LEFT"]), "*-1\r\n");
+        assert_eq!(
+            run(&["LMPOP", "0", "lmpop:a", "LEFT"]),
+            "-ERR numkeys should be greater than 0\r\n"
+        );
+        assert_eq!(
+            run(&["LMPOP", "1", "lmpop:b", "LEFT", "COUNT", "0"]),
+            "-ERR count should be greater than 0\r\n"
+        );
+    }
+
+    #[test]
+    fn test_blpop_huge_timeout() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BLPOP", "blpop:huge", "1e300"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "-ERR timeout is out of range\r\n"
+        );
+
+        // too far away to ever expire, the client waits for a push
+        conn.send(&["BLPOP", "blpop:huge", "1e19"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        let mut other = Connection::open();
+        other.send(&["RPUSH", "blpop:huge", "x"]);
+        assert_eq!(other.reply(WAIT).unwrap(), ":1\r\n");
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "*2\r\n$10\r\nblpop:huge\r\n$1\r\nx\r\n"
+        );
+
+        conn.send(&["LLEN", "blpop:huge"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), ":0\r\n");
+    }
+}
@@ -0,0 +1,568 @@
+use rand::Rng;
+use std::collections::HashSet;
+
+use super::{
+    not_an_integer, parse_rand_count, parse_scan_args, scan_reply, syntax_error, wrong_args,
+    wrong_type,
+};
+use crate::velocity::{
+    database::{DataTypes, Database, DatabaseOps, WrongTypeErr},
+    query::Query,
+    serializer,
+    set::Set,
+};
+
+pub fn handle_sadd(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("sadd");
+    }
+
+    db.transaction(|db| {
+        let set = match db.get_or_create_set(key) {
+            Ok(set) => set,
+            Err(_) => return wrong_type(),
+        };
+
+        let mut added = 0;
+
+        for member in query.args.iter() {
+            if set.insert(member.to_string()) {
+                added += 1;
+            }
+        }
+
+        serializer::int(added)
+    })
+}
+
+pub fn handle_srem(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("srem");
+    }
+
+    db.transaction(|db| {
+        let set = match db.get_set_mut(key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut removed = 0;
+
+        for member in query.args.iter() {
+            if set.remove(member) {
+                removed += 1;
+            }
+        }
+
+        db.remove_if_empty(key);
+        serializer::int(removed)
+    })
+}
+
+pub fn handle_sismember(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("sismember");
+    }
+
+    db.transaction(|db| match db.get_set(key) {
+        Ok(Some(set)) if set.contains(&query.args[0]) => serializer::int(1),
+        Ok(_) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_smismember(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("smismember");
+    }
+
+    db.transaction(|db| {
+        let set = match db.get_set(key) {
+            Ok(set) => set,
+            Err(_) => return wrong_type(),
+        };
+
+        let replies: Vec<Vec<u8>> = query
+            .args
+            .iter()
+            .map(|member| match set {
+                Some(set) if set.contains(member) => serializer::int(1),
+                _ => serializer::int(0),
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+pub fn handle_smembers(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("smembers");
+    }
+
+    db.transaction(|db| match db.get_set(key) {
+        Ok(Some(set)) => {
+            let members: Vec<String> = set.iter().map(|member| member.into_owned()).collect();
+            serializer::str_arr(&members)
+        }
+        Ok(None) => serializer::str_arr(&[]),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_scard(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("scard");
+    }
+
+    db.transaction(|db| match db.get_set(key) {
+        Ok(Some(set)) => serializer::int(set.len() as i64),
+        Ok(None) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_smove(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let src = &query.command_str;
+
+    if src.is_empty() || query.args.len() != 2 {
+        return wrong_args("smove");
+    }
+
+    let dst = &query.args[0];
+    let member = &query.args[1];
+
+    db.transaction(|db| {
+        // both keys are type checked before anything is changed
+        if db.get_set(dst).is_err() {
+            return wrong_type();
+        }
+
+        let removed = match db.get_set_mut(src) {
+            Ok(Some(set)) if src == dst => return serializer::int(set.contains(member) as i64),
+            Ok(Some(set)) => set.remove(member),
+            Ok(None) => false,
+            Err(_) => return wrong_type(),
+        };
+
+        if !removed {
+            return serializer::int(0);
+        }
+
+        db.remove_if_empty(src);
+
+        match db.get_or_create_set(dst) {
+            Ok(set) => set.insert(member.to_string()),
+            Err(_) => unreachable!(),
+        };
+
+        serializer::int(1)
+    })
+}
+
+/* ------------------------------------------------------------------- */
+/* ------------------------- Set Algebra ----------------------------- */
+/* ------------------------------------------------------------------- */
+
+#[derive(Debug, Clone, Copy)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+// a missing key is an empty set, any other type is an error
+fn lookup_sets<'a>(
+    db: &'a Database,
+    keys: &[String],
+) -> Result<Vec<Option<&'a Set>>, WrongTypeErr> {
+    keys.iter().map(|key| db.get_set(key)).collect()
+}
+
+/*
+ * walks the smallest set and keeps the members every other set has. stops as
+ * soon as `limit` members were found
+ */
+fn intersect(sets: &[Option<&Set>], limit: usize) -> Vec<String> {
+    let mut sets: Vec<&Set> = match sets.iter().copied().collect::<Option<Vec<&Set>>>() {
+        Some(sets) => sets,
+        None => return vec![],
+    };
+
+    sets.sort_by_key(|set| set.len());
+
+    let (smallest, others) = match sets.split_first() {
+        Some(split) => split,
+        None => return vec![],
+    };
+
+    let mut members: Vec<String> = vec![];
+
+    for member in smallest.iter() {
+        if members.len() >= limit {
+            break;
+        }
+
+        if others.iter().all(|set| set.contains(&member)) {
+            members.push(member.into_owned());
+        }
+    }
+
+    members
+}
+
+fn union(sets: &[Option<&Set>]) -> Vec<String> {
+    let mut members: HashSet<String> = HashSet::new();
+
+    for set in sets.iter().flatten() {
+        members.extend(set.iter().map(|member| member.into_owned()));
+    }
+
+    members.into_iter().collect()
+}
+
+// members of the first set that are in none of the others
+fn diff(sets: &[Option<&Set>]) -> Vec<String> {
+    let (first, others) = match sets.split_first() {
+        Some((Some(first), others)) => (first, others),
+        _ => return vec![],
+    };
+
+    first
+        .iter()
+        .filter(|member| !others.iter().flatten().any(|set| set.contains(member)))
+        .map(|member| member.into_owned())
+        .collect()
+}
+
+fn compute(db: &Database, keys: &[String], op: SetOp) -> Result<Vec<String>, WrongTypeErr> {
+    let sets = lookup_sets(db, keys)?;
+
+    let members = match op {
+        SetOp::Inter => intersect(&sets, usize::MAX),
+        SetOp::Union => union(&sets),
+        SetOp::Diff => diff(&sets),
+    };
+
+    Ok(members)
+}
+
+// SINTER, SUNION & SDIFF
+pub fn handle_set_op(query: &Query, command: &str, op: SetOp) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() {
+        return wrong_args(command);
+    }
+
+    let mut keys = vec![query.command_str.to_string()];
+    keys.extend(query.args.iter().cloned());
+
+    db.transaction(|db| match compute(db, &keys, op) {
+        Ok(members) => serializer::str_arr(&members),
+        Err(_) => wrong_type(),
+    })
+}
+
+// SINTERSTORE, SUNIONSTORE & SDIFFSTORE
+pub fn handle_set_op_store(query: &Query, command: &str, op: SetOp) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let dst = &query.command_str;
+
+    if dst.is_empty() || query.args.is_empty() {
+        return wrong_args(command);
+    }
+
+    db.transaction(|db| {
+        let members = match compute(db, &query.args, op) {
+            Ok(members) => members,
+            Err(_) => return wrong_type(),
+        };
+
+        let count = members.len();
+
+        // the destination is overwritten whatever its type, or deleted if the
+        // result is empty
+        db.remove(dst);
+
+        if count > 0 {
+            let mut set = Set::new();
+
+            for member in members {
+                set.insert(member);
+            }
+
+            db.insert(dst.to_string(), DataTypes::Set(set));
+        }
+
+        serializer::int(count as i64)
+    })
+}
+
+// SINTERCARD numkeys key [key ...] [LIMIT limit]
+pub fn handle_sintercard(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.is_empty() {
+        return wrong_args("sintercard");
+    }
+
+    let numkeys = match query.command_str.parse::<i64>() {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return serializer::err("ERR numkeys should be greater than 0"),
+        Err(_) => return not_an_integer(),
+    };
+
+    if numkeys > query.args.len() {
+        return serializer::err("ERR Number of keys can't be greater than number of args");
+    }
+
+    let keys = &query.args[..numkeys];
+
+    // LIMIT 0 means no limit
+    let limit = match &query.args[numkeys..] {
+        [] => usize::MAX,
+        [option, limit] if option.to_uppercase() == "LIMIT" => match limit.parse::<i64>() {
+            Ok(0) => usize::MAX,
+            Ok(limit) if limit > 0 => limit as usize,
+            Ok(_) => return serializer::err("ERR LIMIT can't be negative"),
+            Err(_) => return not_an_integer(),
+        },
+        _ => return syntax_error(),
+    };
+
+    db.transaction(|db| match lookup_sets(db, keys) {
+        Ok(sets) => serializer::int(intersect(&sets, limit).len() as i64),
+        Err(_) => wrong_type(),
+    })
+}
+
+/* ------------------------------------------------------------------- */
+/* ------------------------ Random Members --------------------------- */
+/* ------------------------------------------------------------------- */
+
+/*
+ * when the requested count is close to the size of the set, picking members
+ * at random would mostly pick members that were already picked. these are
+ * the thresholds redis uses to switch to a strategy that does not degrade
+ */
+const SRANDMEMBER_SUB_STRATEGY_MUL: usize = 3;
+const SPOP_MOVE_STRATEGY_MUL: usize = 5;
+
+// picks `count` distinct members, `count` being lower than the set size
+fn random_distinct_members(set: &Set, count: usize, rng: &mut impl Rng) -> Vec<String> {
+    if count * SRANDMEMBER_SUB_STRATEGY_MUL > set.len() {
+        // copy the whole set, then drop random members until `count` are left
+        let mut members: Vec<String> = set.iter().map(|member| member.into_owned()).collect();
+
+        while members.len() > count {
+            let index = rng.gen_range(0..members.len());
+            members.swap_remove(index);
+        }
+
+        return members;
+    }
+
+    // pick random members, ignoring the ones already picked
+    let mut picked: HashSet<usize> = HashSet::new();
+
+    while picked.len() < count {
+        picked.insert(rng.gen_range(0..set.len()));
+    }
+
+    picked
+        .into_iter()
+        .map(|index| set.member_at(index).into_owned())
+        .collect()
+}
+
+fn parse_count(query: &Query) -> Result<Option<i64>, Vec<u8>> {
+    match query.args.first() {
+        Some(count) => match count.parse::<i64>() {
+            Ok(count) => Ok(Some(count)),
+            Err(_) => Err(not_an_integer()),
+        },
+        None => Ok(None),
+    }
+}
+
+/*
+ * SRANDMEMBER key [count]
+ *
+ * a positive count returns distinct members, a negative one may return the
+ * same member several times
+ */
+pub fn handle_srandmember(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 1 {
+        return wrong_args("srandmember");
+    }
+
+    let count = match query.args.first() {
+        Some(count) => match parse_rand_count(count, false) {
+            Ok(count) => Some(count),
+            Err(err) => return err,
+        },
+        None => None,
+    };
+
+    db.transaction(|db| {
+        let set = match db.get_set(key) {
+            Ok(Some(set)) => set,
+            Ok(None) if count.is_some() => return serializer::str_arr(&[]),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut rng = rand::thread_rng();
+
+        let count = match count {
+            Some(count) => count,
+            None => return serializer::bulk_str(&set.random_member(&mut rng).unwrap()),
+        };
+
+        let members: Vec<String> = if count < 0 {
+            // with repeats, the reply grows with what is actually picked
+            let mut members = vec![];
+
+            for _ in 0..count.unsigned_abs() {
+                members.push(set.random_member(&mut rng).unwrap().into_owned());
+            }
+
+            members
+        } else if count as usize >= set.len() {
+            set.iter().map(|member| member.into_owned()).collect()
+        } else {
+            random_distinct_members(set, count as usize, &mut rng)
+        };
+
+        serializer::str_arr(&members)
+    })
+}
+
+// SPOP key [count]
+pub fn handle_spop(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 1 {
+        return wrong_args("spop");
+    }
+
+    let count = match parse_count(query) {
+        Ok(Some(count)) if count < 0 => {
+            return serializer::err("ERR value is out of range, must be positive")
+        }
+        Ok(count) => count.map(|count| count as usize),
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let set = match db.get_set_mut(key) {
+            Ok(Some(set)) => set,
+            Ok(None) if count.is_some() => return serializer::str_arr(&[]),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut rng = rand::thread_rng();
+
+        let popped: Vec<String> = match count {
+            None => {
+                let member = set.pop_random(&mut rng).unwrap();
+                db.remove_if_empty(key);
+                return serializer::bulk_str(&member);
+            }
+
+            // the whole set goes away
+            Some(count) if count >= set.len() => {
+                let members = set.iter().map(|member| member.into_owned()).collect();
+                db.remove(key);
+                members
+            }
+
+            // few members are popped, compared to what remains: pop them one
+            // by one
+            Some(count) if (set.len() - count) * SPOP_MOVE_STRATEGY_MUL > count => (0..count)
+                .map(|_| set.pop_random(&mut rng).unwrap())
+                .collect(),
+
+            // most of the set is popped: pick the members that remain instead,
+            // and return everything else
+            Some(count) => {
+                let remaining = random_distinct_members(set, set.len() - count, &mut rng);
+                let mut kept = Set::new();
+
+                for member in remaining {
+                    kept.insert(member);
+                }
+
+                let popped = set
+                    .iter()
+                    .filter(|member| !kept.contains(member))
+                    .map(|member| member.into_owned())
+                    .collect();
+
+                *set = kept;
+                popped
+            }
+        };
+
+        serializer::str_arr(&popped)
+    })
+}
+
+// SSCAN key cursor [MATCH pattern] [COUNT count]
+pub fn handle_sscan(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("sscan");
+    }
+
+    let opts = match parse_scan_args(&query.args, false) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let set = match db.get_set(key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return scan_reply(0, &[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let (cursor, found) = set.scan(opts.cursor, opts.count);
+
+        let items: Vec<String> = found
+            .into_iter()
+            .filter(|member| opts.matches(member))
+            .map(|member| member.into_owned())
+            .collect();
+
+        scan_reply(cursor, &items)
+    })
+}
@@ -0,0 +1,373 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::super::spec_helper::*;
+
+    const WRONG_TYPE: &str =
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+    // the members of a set reply, in order
+    fn members(reply: &str) -> Vec<String> {
+        let mut members: Vec<String> = reply
+            .split("\r\n")
+            .skip(2)
+            .step_by(2)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+
+        members.sort();
+        members
+    }
+
+    fn smembers(key: &str) -> Vec<String> {
+        members(&run(&["SMEMBERS", key]))
+    }
+
+    fn encoding(key: &str) -> String {
+        run(&["OBJECT", "ENCODING", key])
+    }
+
+    #[test]
+    fn test_sadd_srem() {
+        assert_eq!(run(&["SADD", "sadd", "a", "b", "a"]), ":2\r\n");
+        assert_eq!(run(&["SADD", "sadd", "b", "c"]), ":1\r\n");
+        assert_eq!(run(&["SCARD", "sadd"]), ":3\r\n");
+        assert_eq!(run(&["SCARD", "sadd:missing"]), ":0\r\n");
+
+        assert_eq!(run(&["SISMEMBER", "sadd", "a"]), ":1\r\n");
+        assert_eq!(run(&["SISMEMBER", "sadd", "z"]), ":0\r\n");
+        assert_eq!(run(&["SISMEMBER", "sadd:missing", "a"]), ":0\r\n");
+        assert_eq!(
+            run(&["SMISMEMBER", "sadd", "a", "z", "c"]),
+            "*3\r\n:1\r\n:0\r\n:1\r\n"
+        );
+        assert_eq!(run(&["SMISMEMBER", "sadd:missing", "a"]), "*1\r\n:0\r\n");
+
+        assert_eq!(run(&["SREM", "sadd", "a", "z"]), ":1\r\n");
+        assert_eq!(run(&["SREM", "sadd:missing", "a"]), ":0\r\n");
+
+        // the key is gone with its last member
+        assert_eq!(run(&["SREM", "sadd", "b", "c"]), ":2\r\n");
+        assert_eq!(run(&["EXISTS", "sadd"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_intset_conversion() {
+        run(&["SADD", "intset", "3", "1", "-2"]);
+        assert_eq!(encoding("intset"), "$6\r\nintset\r\n");
+        assert_eq!(smembers("intset"), ["-2", "1", "3"]);
+
+        // "012" is not how 12 is written, it can not go in the intset
+        assert_eq!(run(&["SADD", "intset", "12", "012"]), ":2\r\n");
+        assert_eq!(encoding("intset"), "$8\r\nlistpack\r\n");
+        assert_eq!(run(&["SISMEMBER", "intset", "012"]), ":1\r\n");
+        assert_eq!(run(&["SCARD", "intset"]), ":5\r\n");
+
+        // past set_max_intset_entries, straight to a hash table
+        for i in 0..512 {
+            run(&["SADD", "intset:big", &i.to_string()]);
+        }
+        assert_eq!(encoding("intset:big"), "$6\r\nintset\r\n");
+        run(&["SADD", "intset:big", "512"]);
+        assert_eq!(encoding("intset:big"), "$9\r\nhashtable\r\n");
+        assert_eq!(run(&["SCARD", "intset:big"]), ":513\r\n");
+        assert_eq!(run(&["SISMEMBER", "intset:big", "511"]), ":1\r\n");
+
+        // and it never goes back
+        for i in 0..510 {
+            run(&["SREM", "intset:big", &i.to_string()]);
+        }
+        assert_eq!(smembers("intset:big"), ["510", "511", "512"]);
+        assert_eq!(encoding("intset:big"), "$9\r\nhashtable\r\n");
+    }
+
+    #[test]
+    fn test_smove() {
+        run(&["SADD", "smove:src", "1", "a"]);
+        run(&["SET", "smove:string", "x"]);
+
+        assert_eq!(run(&["SMOVE", "smove:src", "smove:dst", "1"]), ":1\r\n");
+        assert_eq!(smembers("smove:dst"), ["1"]);
+        assert_eq!(encoding("smove:dst"), "$6\r\nintset\r\n");
+        assert_eq!(run(&["SMOVE", "smove:src", "smove:dst", "1"]), ":0\r\n");
+        assert_eq!(run(&["SMOVE", "smove:missing", "smove:dst", "1"]), ":0\r\n");
+
+        // moving a member onto its own set changes nothing
+        assert_eq!(run(&["SMOVE", "smove:src", "smove:src", "a"]), ":1\r\n");
+        assert_eq!(smembers("smove:src"), ["a"]);
+
+        // nothing is removed when the destination has the wrong type
+        assert_eq!(
+            run(&["SMOVE", "smove:src", "smove:string", "a"]),
+            WRONG_TYPE
+        );
+        assert_eq!(smembers("smove:src"), ["a"]);
+
+        // the source is gone with its last member
+        assert_eq!(run(&["SMOVE", "smove:src", "smove:dst", "a"]), ":1\r\n");
+        assert_eq!(run(&["EXISTS", "smove:src"]), ":0\r\n");
+        assert_eq!(smembers("smove:dst"), ["1", "a"]);
+    }
+
+    #[test]
+    fn test_sinter_sunion_sdiff() {
+        run(&["SADD", "algebra:a", "1", "2", "3", "x"]);
+        run(&["SADD", "algebra:b", "2", "3", "4"]);
+
+        assert_eq!(
+            members(&run(&["SINTER", "algebra:a", "algebra:b"])),
+            ["2", "3"]
+        );
+        assert_eq!(
+            members(&run(&["SUNION", "algebra:a", "algebra:b"])),
+            ["1", "2", "3", "4", "x"]
+        );
+        assert_eq!(
+            members(&run(&["SDIFF", "algebra:a", "algebra:b"])),
+            ["1", "x"]
+        );
+
+        // a missing key is an empty set
+        assert_eq!(run(&["SINTER", "algebra:a", "algebra:missing"]), "*0\r\n");
+        assert_eq!(
+            members(&run(&["SDIFF", "algebra:a", "algebra:missing"])),
+            ["1", "2", "3", "x"]
+        );
+    }
+
+    #[test]
+    fn test_store() {
+        run(&["SADD", "store:a", "1", "2", "3"]);
+        run(&["SADD", "store:b", "2", "3", "4"]);
+
+        assert_eq!(
+            run(&["SINTERSTORE", "store:inter", "store:a", "store:b"]),
+            ":2\r\n"
+        );
+        assert_eq!(smembers("store:inter"), ["2", "3"]);
+
+        assert_eq!(
+            run(&["SUNIONSTORE", "store:union", "store:a", "store:b"]),
+            ":4\r\n"
+        );
+        assert_eq!(smembers("store:union"), ["1", "2", "3", "4"]);
+
+        assert_eq!(
+            run(&["SDIFFSTORE", "store:diff", "store:a", "store:b"]),
+            ":1\r\n"
+        );
+        assert_eq!(smembers("store:diff"), ["1"]);
+
+        // the destination may be one of the sources
+        assert_eq!(
+            run(&["SDIFFSTORE", "store:a", "store:a", "store:diff"]),
+            ":2\r\n"
+        );
+        assert_eq!(smembers("store:a"), ["2", "3"]);
+    }
+
+    #[test]
+    fn test_store_replaces_destination() {
+        run(&["SADD", "replace:a", "1"]);
+
+        assert_store_replaces(
+            "replace:dst",
+            &["SUNIONSTORE", "replace:dst", "replace:a"],
+            &["SINTERSTORE", "replace:dst", "replace:a", "replace:missing"],
+            "set",
+        );
+    }
+
+    #[test]
+    fn test_sintercard() {
+        run(&["SADD", "card:a", "1", "2", "3", "4"]);
+        run(&["SADD", "card:b", "2", "3", "4", "5"]);
+
+        assert_eq!(run(&["SINTERCARD", "2", "card:a", "card:b"]), ":3\r\n");
+        assert_eq!(
+            run(&["SINTERCARD", "2", "card:a", "card:b", "LIMIT", "2"]),
+            ":2\r\n"
+        );
+        // 0 is no limit
+        assert_eq!(
+            run(&["SINTERCARD", "2", "card:a", "card:b", "LIMIT", "0"]),
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(&["SINTERCARD", "2", "card:a", "card:missing"]),
+            ":0\r\n"
+        );
+
+        assert_eq!(
+            run(&["SINTERCARD", "0", "card:a"]),
+            "-ERR numkeys should be greater than 0\r\n"
+        );
+        assert_eq!(
+            run(&["SINTERCARD", "3", "card:a", "card:b"]),
+            "-ERR Number of keys can't be greater than number of args\r\n"
+        );
+        assert_eq!(
+            run(&["SINTERCARD", "2", "card:a", "card:b", "LIMIT", "-1"]),
+            "-ERR LIMIT can't be negative\r\n"
+        );
+    }
+
+    #[test]
+    fn test_srandmember() {
+        run(&["SADD", "srandmember", "a", "b"]);
+
+        assert_eq!(
+            members(&run(&["SRANDMEMBER", "srandmember", "5"])),
+            ["a", "b"]
+        );
+        assert_eq!(
+            members(&run(&["SRANDMEMBER", "srandmember", "-5"])).len(),
+            5
+        );
+        assert_eq!(run(&["SRANDMEMBER", "srandmember:missing", "-3"]), "*0\r\n");
+
+        // any positive count is fine, it is capped by the size of the key
+        assert_eq!(
+            run(&["SRANDMEMBER", "srandmember", "9223372036854775807"])
+                .lines()
+                .count(),
+            1 + 2 * 2
+        );
+
+        // out of range counts are refused before anything is allocated
+        assert_eq!(
+            run(&["SRANDMEMBER", "srandmember", "-9223372036854775808"]),
+            "-ERR value is out of range\r\n"
+        );
+        assert_eq!(run(&["SCARD", "srandmember"]), ":2\r\n");
+    }
+
+    // the next cursor and the members of an SSCAN reply
+    fn sscan(args: &[&str]) -> (String, Vec<String>) {
+        let reply = run(args);
+        let lines: Vec<&str> = reply.split("\r\n").collect();
+        let members = lines[4..]
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string());
+
+        (lines[2].to_string(), members.collect())
+    }
+
+    #[test]
+    fn test_sscan() {
+        for i in 0..200 {
+            run(&["SADD", "sscan", &format!("m{}", i)]);
+        }
+
+        let mut seen = HashSet::new();
+        let mut cursor = "0".to_string();
+        let mut calls = 0;
+
+        loop {
+            let (next, members) = sscan(&["SSCAN", "sscan", &cursor, "COUNT", "10"]);
+
+            for member in members {
+                assert!(seen.insert(member));
+            }
+
+            /*
+             * members removed or added along the way may or may not be
+             * returned, those present all along are returned once
+             */
+            run(&["SREM", "sscan", &format!("m{}", 199 - calls)]);
+            run(&["SADD", "sscan", &format!("new{}", calls)]);
+            calls += 1;
+
+            cursor = next;
+
+            if cursor == "0" {
+                break;
+            }
+        }
+
+        for i in 0..200 - calls {
+            assert!(seen.contains(&format!("m{}", i)), "m{} was missed", i);
+        }
+    }
+
+    #[test]
+    fn test_sscan_options() {
+        for i in 0..50 {
+            run(&["SADD", "sscan:options", &format!("m{}", i)]);
+        }
+
+        let (cursor, mut members) = sscan(&[
+            "SSCAN",
+            "sscan:options",
+            "0",
+            "MATCH",
+            "m1?",
+            "COUNT",
+            "1000",
+        ]);
+        members.sort();
+        assert_eq!(cursor, "0");
+        assert_eq!(members.len(), 10);
+        assert_eq!(members[0], "m10");
+
+        // an intset is returned whole, whatever COUNT says
+        run(&["SADD", "sscan:intset", "1", "2", "3"]);
+        let (cursor, members) = sscan(&["SSCAN", "sscan:intset", "0", "COUNT", "1"]);
+        assert_eq!(cursor, "0");
+        assert_eq!(members.len(), 3);
+
+        assert_eq!(
+            run(&["SSCAN", "sscan:missing", "0"]),
+            "*2\r\n$1\r\n0\r\n*0\r\n"
+        );
+        assert_eq!(
+            run(&["SSCAN", "sscan:options", "x"]),
+            "-ERR invalid cursor\r\n"
+        );
+        assert_eq!(
+            run(&["SSCAN", "sscan:options", "0", "COUNT", "0"]),
+            "-ERR syntax error\r\n"
+        );
+        assert_eq!(
+            run(&["SSCAN", "sscan:options", "0", "NOVALUES"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        run(&["SADD", "algebra:set", "1"]);
+        run(&["SET", "algebra:string", "x"]);
+
+        assert_eq!(
+            run(&["SINTER", "algebra:set", "algebra:string"]),
+            WRONG_TYPE
+        );
+        assert_eq!(
+            run(&["SUNION", "algebra:set", "algebra:string"]),
+            WRONG_TYPE
+        );
+        assert_eq!(run(&["SDIFF", "algebra:string", "algebra:set"]), WRONG_TYPE);
+        assert_eq!(
+            run(&["SINTERCARD", "2", "algebra:set", "algebra:string"]),
+            WRONG_TYPE
+        );
+
+        // nothing is stored when a source has the wrong type
+        assert_eq!(
+            run(&[
+                "SUNIONSTORE",
+                "algebra:dst",
+                "algebra:set",
+                "algebra:string"
+            ]),
+            WRONG_TYPE
+        );
+        assert_eq!(run(&["EXISTS", "algebra:dst"]), ":0\r\n");
+    }
+}
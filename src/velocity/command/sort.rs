@@ -0,0 +1,286 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use super::{not_an_integer, parse_float, syntax_error, wrong_args, wrong_type};
+use crate::velocity::{
+    database::{DataTypes, Database, DatabaseOps},
+    query::Query,
+    serializer,
+};
+
+#[derive(Debug)]
+struct SortOpts {
+    by: Option<String>,
+    dont_sort: bool,
+    limit: Option<(i64, i64)>, // offset, count
+    get: Vec<String>,
+    desc: bool,
+    alpha: bool,
+    store: Option<String>,
+}
+
+fn parse_sort_args(args: &[String], read_only: bool) -> Result<SortOpts, Vec<u8>> {
+    let mut opts = SortOpts {
+        by: None,
+        dont_sort: false,
+        limit: None,
+        get: vec![],
+        desc: false,
+        alpha: false,
+        store: None,
+    };
+
+    let mut i = 0;
+
+    while i < args.len() {
+        let remaining = args.len() - i - 1;
+
+        match args[i].to_lowercase().as_str() {
+            "asc" => opts.desc = false,
+            "desc" => opts.desc = true,
+            "alpha" => opts.alpha = true,
+            "limit" if remaining >= 2 => {
+                let offset = args[i + 1].parse::<i64>().map_err(|_| not_an_integer())?;
+                let count = args[i + 2].parse::<i64>().map_err(|_| not_an_integer())?;
+
+                opts.limit = Some((offset, count));
+                i += 2;
+            }
+            "store" if !read_only && remaining >= 1 => {
+                opts.store = Some(args[i + 1].to_string());
+                i += 1;
+            }
+            "by" if remaining >= 1 => {
+                // a pattern without `*` is the same for every element (`nosort`)
+                let pattern = &args[i + 1];
+
+                opts.dont_sort = !pattern.contains('*');
+                opts.by = (!opts.dont_sort).then(|| pattern.to_string());
+                i += 1;
+            }
+            "get" if remaining >= 1 => {
+                opts.get.push(args[i + 1].to_string());
+                i += 1;
+            }
+            _ => return Err(syntax_error()),
+        }
+
+        i += 1;
+    }
+
+    Ok(opts)
+}
+
+/*
+ * what a BY or GET pattern points to for `element`. the first `*` of the
+ * pattern is replaced with the element to get the name of a string key, or
+ * of a hash when the pattern ends with `->field`. `#` is the element itself.
+ * anything missing, or of another type, is nil
+ */
+fn lookup(db: &mut Database, pattern: &str, element: &[u8]) -> Option<Vec<u8>> {
+    if pattern == "#" {
+        return Some(element.to_vec());
+    }
+
+    let star = pattern.find('*')?;
+
+    let (key_pattern, field) = match pattern[star + 1..].find("->") {
+        Some(arrow) if star + 1 + arrow + 2 < pattern.len() => {
+            let arrow = star + 1 + arrow;
+            (&pattern[..arrow], Some(&pattern[arrow + 2..]))
+        }
+        _ => (pattern, None),
+    };
+
+    let key = key_pattern.replacen('*', &String::from_utf8_lossy(element), 1);
+
+    match field {
+        Some(field) => match db.get_hash(&key) {
+            Ok(Some(hash)) => hash.get(field).map(|value| value.as_bytes().to_vec()),
+            _ => None,
+        },
+        None => match db.get_string(&key) {
+            Ok(Some(data)) => Some(data.clone()),
+            _ => None,
+        },
+    }
+}
+
+// an element along with what it is sorted by
+struct SortItem {
+    element: Vec<u8>,
+    by: Option<Vec<u8>>, // with ALPHA and BY
+    score: f64,          // without ALPHA
+}
+
+fn sort_items(
+    db: &mut Database,
+    elements: Vec<Vec<u8>>,
+    opts: &SortOpts,
+) -> Result<Vec<Vec<u8>>, Vec<u8>> {
+    let mut items = Vec::with_capacity(elements.len());
+
+    for element in elements {
+        let by = match &opts.by {
+            Some(pattern) => lookup(db, pattern, &element),
+            None => None,
+        };
+
+        // elements without a weight key weigh 0
+        let score = match (opts.alpha, &opts.by, &by) {
+            (true, _, _) | (false, Some(_), None) => 0.0,
+            (false, Some(_), Some(weight)) => parse_weight(weight)?,
+            (false, None, _) => parse_weight(&element)?,
+        };
+
+        items.push(SortItem { element, by, score });
+    }
+
+    let compare = |a: &SortItem, b: &SortItem| -> Ordering {
+        match (opts.alpha, opts.by.is_some()) {
+            // equal scores are ordered by the elements, so the result is stable
+            (false, _) => a
+                .score
+                .partial_cmp(&b.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.element.cmp(&b.element)),
+            // a missing weight goes first
+            (true, true) => a.by.cmp(&b.by),
+            (true, false) => a.element.cmp(&b.element),
+        }
+    };
+
+    match opts.desc {
+        true => items.sort_by(|a, b| compare(b, a)),
+        false => items.sort_by(compare),
+    }
+
+    Ok(items.into_iter().map(|item| item.element).collect())
+}
+
+fn parse_weight(weight: &[u8]) -> Result<f64, Vec<u8>> {
+    std::str::from_utf8(weight)
+        .ok()
+        .and_then(parse_float)
+        .ok_or_else(|| serializer::err("ERR One or more scores can't be converted into double"))
+}
+
+// the elements at LIMIT `offset` `count`, all of them if `count` is negative
+fn apply_limit(elements: Vec<Vec<u8>>, limit: Option<(i64, i64)>) -> Vec<Vec<u8>> {
+    let (offset, count) = match limit {
+        Some(limit) => limit,
+        None => return elements,
+    };
+
+    let skip = offset.max(0) as usize;
+    let take = match count {
+        count if count < 0 => usize::MAX,
+        count => count as usize,
+    };
+
+    elements.into_iter().skip(skip).take(take).collect()
+}
+
+/*
+ * SORT and SORT_RO, which can not STORE. lists, sets and sorted sets are
+ * sorted by their elements (as numbers unless ALPHA), or by the keys a BY
+ * pattern points to. with `BY nosort` lists keep their order and sorted sets
+ * their ranking
+ */
+pub fn handle_sort(query: &Query, read_only: bool) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+    let command = if read_only { "sort_ro" } else { "sort" };
+
+    if key.is_empty() {
+        return wrong_args(command);
+    }
+
+    let mut opts = match parse_sort_args(&query.args, read_only) {
+        Ok(opts) => opts,
+        Err(reply) => return reply,
+    };
+
+    db.transaction(|db| {
+        let (elements, is_set) = match db.lookup(key) {
+            Some(DataTypes::List(list)) => (list.iter().cloned().collect(), false),
+            Some(DataTypes::Set(set)) => {
+                let members = set.iter().map(|member| member.as_bytes().to_vec());
+                (members.collect(), true)
+            }
+            Some(DataTypes::SortedSet(zset)) => {
+                let mut members: Vec<Vec<u8>> = zset
+                    .iter()
+                    .map(|(member, _)| member.as_bytes().to_vec())
+                    .collect();
+
+                if opts.dont_sort && opts.desc {
+                    members.reverse();
+                }
+
+                (members, false)
+            }
+            Some(_) => return wrong_type(),
+            None => (vec![], false),
+        };
+
+        // a set has no order of its own, what gets stored must not be random
+        if opts.dont_sort && is_set && opts.store.is_some() {
+            opts.dont_sort = false;
+            opts.alpha = true;
+        }
+
+        let elements = match opts.dont_sort {
+            true => elements,
+            false => match sort_items(db, elements, &opts) {
+                Ok(elements) => elements,
+                Err(reply) => return reply,
+            },
+        };
+
+        let elements = apply_limit(elements, opts.limit);
+
+        let mut rows: Vec<Option<Vec<u8>>> = vec![];
+
+        for element in &elements {
+            if opts.get.is_empty() {
+                rows.push(Some(element.clone()));
+            }
+
+            for pattern in &opts.get {
+                rows.push(lookup(db, pattern, element));
+            }
+        }
+
+        let dest = match &opts.store {
+            Some(dest) => dest,
+            None => {
+                let replies: Vec<Vec<u8>> = rows
+                    .iter()
+                    .map(|row| match row {
+                        Some(data) => serializer::bulk_bytes(data),
+                        None => serializer::nil(),
+                    })
+                    .collect();
+
+                return serializer::arr(&replies);
+            }
+        };
+
+        // missing values are stored as empty strings
+        let list: VecDeque<Vec<u8>> = rows
+            .into_iter()
+            .map(|row| row.unwrap_or_default())
+            .collect();
+
+        let len = list.len();
+
+        match len {
+            0 => {
+                db.remove(dest);
+            }
+            _ => db.insert(dest.to_string(), DataTypes::List(list)),
+        }
+
+        serializer::int(len as i64)
+    })
+}
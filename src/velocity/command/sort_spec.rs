@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+    use super::super::spec_helper::*;
+
+    #[test]
+    fn test_sort() {
+        run(&["RPUSH", "sort:list", "3", "1", "2", "10"]);
+
+        assert_eq!(
+            run(&["SORT", "sort:list"]),
+            "*4\r\n$1\r\n1\r\n$1\r\n2\r\n$1\r\n3\r\n$2\r\n10\r\n"
+        );
+        assert_eq!(
+            run(&["SORT", "sort:list", "DESC", "LIMIT", "1", "2"]),
+            "*2\r\n$1\r\n3\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            run(&["SORT", "sort:list", "ALPHA"]),
+            "*4\r\n$1\r\n1\r\n$2\r\n10\r\n$1\r\n2\r\n$1\r\n3\r\n"
+        );
+
+        run(&["RPUSH", "sort:words", "b", "a"]);
+        assert_eq!(
+            run(&["SORT", "sort:words"]),
+            "-ERR One or more scores can't be converted into double\r\n"
+        );
+        assert_eq!(run(&["SORT", "sort:missing"]), "*0\r\n");
+        assert_eq!(
+            run(&["SORT", "sort:list", "STORE"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_get() {
+        run(&["RPUSH", "sortby:ids", "1", "2", "3"]);
+        run(&["SET", "sortby:weight:1", "30"]);
+        run(&["SET", "sortby:weight:2", "10"]);
+        run(&["SET", "sortby:weight:3", "20"]);
+        run(&["HSET", "sortby:user:1", "name", "ann"]);
+        run(&["HSET", "sortby:user:3", "name", "cid"]);
+
+        assert_eq!(
+            run(&["SORT", "sortby:ids", "BY", "sortby:weight:*"]),
+            "*3\r\n$1\r\n2\r\n$1\r\n3\r\n$1\r\n1\r\n"
+        );
+
+        // a missing hash or field is nil
+        assert_eq!(
+            run(&[
+                "SORT",
+                "sortby:ids",
+                "BY",
+                "sortby:weight:*",
+                "GET",
+                "#",
+                "GET",
+                "sortby:user:*->name"
+            ]),
+            "*6\r\n$1\r\n2\r\n$-1\r\n$1\r\n3\r\n$3\r\ncid\r\n$1\r\n1\r\n$3\r\nann\r\n"
+        );
+
+        // `nosort` keeps the order of the list
+        assert_eq!(
+            run(&["SORT", "sortby:ids", "BY", "nosort", "DESC"]),
+            "*3\r\n$1\r\n1\r\n$1\r\n2\r\n$1\r\n3\r\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_store() {
+        run(&["SADD", "sortstore:set", "c", "a", "b"]);
+
+        // a set has no order of its own, what is stored is sorted anyway
+        assert_eq!(
+            run(&[
+                "SORT",
+                "sortstore:set",
+                "BY",
+                "nosort",
+                "STORE",
+                "sortstore:dst"
+            ]),
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(&["LRANGE", "sortstore:dst", "0", "-1"]),
+            "*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n"
+        );
+
+        // missing values are stored as empty strings
+        assert_eq!(
+            run(&[
+                "SORT",
+                "sortstore:set",
+                "ALPHA",
+                "GET",
+                "sortstore:missing:*",
+                "STORE",
+                "sortstore:dst"
+            ]),
+            ":3\r\n"
+        );
+        assert_eq!(
+            run(&["LRANGE", "sortstore:dst", "0", "-1"]),
+            "*3\r\n$0\r\n\r\n$0\r\n\r\n$0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_store_replaces_destination() {
+        run(&["RPUSH", "sortreplace:src", "2", "1"]);
+
+        assert_store_replaces(
+            "sortreplace:dst",
+            &["SORT", "sortreplace:src", "STORE", "sortreplace:dst"],
+            &["SORT", "sortreplace:missing", "STORE", "sortreplace:dst"],
+            "list",
+        );
+    }
+
+    #[test]
+    fn test_sort_ro() {
+        run(&["RPUSH", "sortro", "2", "1"]);
+
+        assert_eq!(run(&["SORT_RO", "sortro"]), "*2\r\n$1\r\n1\r\n$1\r\n2\r\n");
+        assert_eq!(
+            run(&["SORT_RO", "sortro", "STORE", "sortro:dst"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        run(&["SET", "sort:string", "x"]);
+
+        assert_eq!(
+            run(&["SORT", "sort:string"]),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_elements() {
+        run_bytes(&encode_bytes(&[
+            b"RPUSH",
+            b"sort:binary",
+            b"\xff",
+            b"\x01",
+            b"\xfe",
+        ]));
+
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"SORT", b"sort:binary", b"ALPHA"])),
+            b"*3\r\n$1\r\n\x01\r\n$1\r\n\xfe\r\n$1\r\n\xff\r\n"
+        );
+
+        // the elements are stored as they are, not as valid UTF-8
+        assert_eq!(
+            run_bytes(&encode_bytes(&[
+                b"SORT",
+                b"sort:binary",
+                b"ALPHA",
+                b"DESC",
+                b"STORE",
+                b"sort:binary:dst",
+            ])),
+            b":3\r\n"
+        );
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LRANGE", b"sort:binary:dst", b"0", b"-1"])),
+            b"*3\r\n$1\r\n\xff\r\n$1\r\n\xfe\r\n$1\r\n\x01\r\n"
+        );
+
+        // and so are the values GET points to
+        run_bytes(&encode_bytes(&[b"SET", b"sort:binary:a", b"\xff\x00"]));
+        run(&["RPUSH", "sort:binary:keys", "a", "b"]);
+        assert_eq!(
+            run(&[
+                "SORT",
+                "sort:binary:keys",
+                "BY",
+                "nosort",
+                "GET",
+                "sort:binary:*",
+                "STORE",
+                "sort:binary:dst",
+            ]),
+            ":2\r\n"
+        );
+        assert_eq!(
+            run_bytes(&encode_bytes(&[b"LRANGE", b"sort:binary:dst", b"0", b"-1"])),
+            b"*2\r\n$2\r\n\xff\x00\r\n$0\r\n\r\n"
+        );
+    }
+}
@@ -0,0 +1,1426 @@
+use rand::{seq::index, Rng};
+use std::{borrow::Cow, collections::HashMap, time::Duration};
+
+use super::{
+    format_float, normalize_range, not_an_integer, parse_float, parse_rand_count, parse_scan_args,
+    parse_timeout, scan_reply, syntax_error, wrong_args, wrong_type, Attempt,
+};
+use crate::velocity::{
+    blocking,
+    client::Client,
+    database::{DataTypes, Database, DatabaseOps, WrongTypeErr},
+    query::Query,
+    serializer,
+    set::Set,
+    sorted_set::{LexBound, LexRange, ScoreBound, ScoreRange, SortedSet},
+};
+
+fn not_a_float() -> Vec<u8> {
+    serializer::err("ERR value is not a valid float")
+}
+
+fn nan_score() -> Vec<u8> {
+    serializer::err("ERR resulting score is not a number (NaN)")
+}
+
+// flat `member score member score ...` array
+fn members_with_scores(members: &[(String, f64)]) -> Vec<u8> {
+    let items: Vec<String> = members
+        .iter()
+        .flat_map(|(member, score)| [member.to_string(), format_float(*score)])
+        .collect();
+
+    serializer::str_arr(&items)
+}
+
+// "1.5", "(1.5" (exclusive), "-inf", "+inf"
+fn parse_score_bound(bound: &str) -> Option<ScoreBound> {
+    let (value, exclusive) = match bound.strip_prefix('(') {
+        Some(value) => (value, true),
+        None => (bound, false),
+    };
+
+    Some(ScoreBound {
+        value: parse_float(value)?,
+        exclusive,
+    })
+}
+
+fn parse_score_range(min: &str, max: &str) -> Result<ScoreRange, Vec<u8>> {
+    match (parse_score_bound(min), parse_score_bound(max)) {
+        (Some(min), Some(max)) => Ok(ScoreRange { min, max }),
+        _ => Err(serializer::err("ERR min or max is not a float")),
+    }
+}
+
+// "-", "+", "[member" (inclusive) or "(member" (exclusive)
+fn parse_lex_bound(bound: &str) -> Option<LexBound> {
+    if bound == "-" {
+        return Some(LexBound::Lowest);
+    }
+
+    if bound == "+" {
+        return Some(LexBound::Highest);
+    }
+
+    if let Some(member) = bound.strip_prefix('[') {
+        return Some(LexBound::Inclusive(member.to_string()));
+    }
+
+    bound
+        .strip_prefix('(')
+        .map(|member| LexBound::Exclusive(member.to_string()))
+}
+
+fn parse_lex_range(min: &str, max: &str) -> Result<LexRange, Vec<u8>> {
+    match (parse_lex_bound(min), parse_lex_bound(max)) {
+        (Some(min), Some(max)) => Ok(LexRange { min, max }),
+        _ => Err(serializer::err(
+            "ERR min or max not valid string range item",
+        )),
+    }
+}
+
+/* ---- ZADD ---- */
+
+#[derive(Debug, Default)]
+pub struct ZAddOpts {
+    pub nx: bool,
+    pub xx: bool,
+    pub gt: bool,
+    pub lt: bool,
+    pub ch: bool,
+    pub incr: bool,
+}
+
+// what happened to a single member
+pub enum ZAddOutcome {
+    Added(f64),
+    Updated(f64),
+    Unchanged(f64),
+    Skipped,
+}
+
+/*
+ * applies one `score member` pair, following the NX/XX/GT/LT/INCR flags.
+ * `Err` means that INCR produced a NaN score
+ */
+pub fn zadd_one(
+    zset: &mut SortedSet,
+    opts: &ZAddOpts,
+    member: &str,
+    score: f64,
+) -> Result<ZAddOutcome, ()> {
+    let current = match zset.score(member) {
+        Some(current) => current,
+        None if opts.xx => return Ok(ZAddOutcome::Skipped),
+        None => {
+            zset.insert(member.to_string(), score);
+            return Ok(ZAddOutcome::Added(score));
+        }
+    };
+
+    if opts.nx {
+        return Ok(ZAddOutcome::Skipped);
+    }
+
+    let score = if opts.incr { current + score } else { score };
+
+    if score.is_nan() {
+        return Err(());
+    }
+
+    if (opts.gt && score <= current) || (opts.lt && score >= current) {
+        return Ok(ZAddOutcome::Skipped);
+    }
+
+    if score == current {
+        return Ok(ZAddOutcome::Unchanged(score));
+    }
+
+    zset.insert(member.to_string(), score);
+    Ok(ZAddOutcome::Updated(score))
+}
+
+pub fn handle_zadd(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 2 {
+        return wrong_args("zadd");
+    }
+
+    let mut opts = ZAddOpts::default();
+    let mut flags = 0;
+
+    for arg in query.args.iter() {
+        match arg.to_uppercase().as_str() {
+            "NX" => opts.nx = true,
+            "XX" => opts.xx = true,
+            "GT" => opts.gt = true,
+            "LT" => opts.lt = true,
+            "CH" => opts.ch = true,
+            "INCR" => opts.incr = true,
+            _ => break,
+        }
+
+        flags += 1;
+    }
+
+    let pairs = &query.args[flags..];
+
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return syntax_error();
+    }
+
+    if opts.nx && opts.xx {
+        return serializer::err("ERR XX and NX options at the same time are not compatible");
+    }
+
+    if (opts.gt || opts.lt) && (opts.nx || (opts.gt && opts.lt)) {
+        return serializer::err(
+            "ERR GT, LT, and/or NX options at the same time are not compatible",
+        );
+    }
+
+    if opts.incr && pairs.len() > 2 {
+        return serializer::err("ERR INCR option supports a single increment-element pair");
+    }
+
+    // every score is checked before anything is added
+    let mut scored = Vec::with_capacity(pairs.len() / 2);
+
+    for pair in pairs.chunks(2) {
+        match parse_float(&pair[0]) {
+            Some(score) => scored.push((score, &pair[1])),
+            None => return not_a_float(),
+        }
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_or_create_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let (mut added, mut updated) = (0, 0);
+        let mut last = None;
+
+        for (score, member) in scored {
+            let outcome = match zadd_one(zset, &opts, member, score) {
+                Ok(outcome) => outcome,
+                Err(_) => {
+                    db.remove_if_empty(key);
+                    return nan_score();
+                }
+            };
+
+            last = match outcome {
+                ZAddOutcome::Added(score) => {
+                    added += 1;
+                    Some(score)
+                }
+                ZAddOutcome::Updated(score) => {
+                    updated += 1;
+                    Some(score)
+                }
+                ZAddOutcome::Unchanged(score) => Some(score),
+                ZAddOutcome::Skipped => None,
+            };
+        }
+
+        // XX or NX may have left a brand new key empty
+        db.remove_if_empty(key);
+
+        if opts.incr {
+            return match last {
+                Some(score) => serializer::bulk_str(&format_float(score)),
+                None => serializer::nil(),
+            };
+        }
+
+        if opts.ch {
+            serializer::int(added + updated)
+        } else {
+            serializer::int(added)
+        }
+    })
+}
+
+pub fn handle_zincrby(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("zincrby");
+    }
+
+    let increment = match parse_float(&query.args[0]) {
+        Some(increment) => increment,
+        None => return not_a_float(),
+    };
+
+    let member = &query.args[1];
+
+    db.transaction(|db| {
+        let zset = match db.get_or_create_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let score = zset.score(member).unwrap_or(0.0) + increment;
+
+        if score.is_nan() {
+            db.remove_if_empty(key);
+            return nan_score();
+        }
+
+        zset.insert(member.to_string(), score);
+        serializer::bulk_str(&format_float(score))
+    })
+}
+
+/* ---- Lookups ---- */
+
+pub fn handle_zscore(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 1 {
+        return wrong_args("zscore");
+    }
+
+    db.transaction(|db| match db.get_zset(key) {
+        Ok(zset) => match zset.and_then(|zset| zset.score(&query.args[0])) {
+            Some(score) => serializer::bulk_str(&format_float(score)),
+            None => serializer::nil(),
+        },
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_zmscore(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("zmscore");
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let replies: Vec<Vec<u8>> = query
+            .args
+            .iter()
+            .map(|member| match zset.and_then(|zset| zset.score(member)) {
+                Some(score) => serializer::bulk_str(&format_float(score)),
+                None => serializer::nil(),
+            })
+            .collect();
+
+        serializer::arr(&replies)
+    })
+}
+
+pub fn handle_zrem(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("zrem");
+    }
+
+    db.transaction(|db| {
+        let zset = match db.get_zset_mut(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let removed = query
+            .args
+            .iter()
+            .filter(|member| zset.remove(member))
+            .count();
+
+        db.remove_if_empty(key);
+        serializer::int(removed as i64)
+    })
+}
+
+pub fn handle_zcard(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("zcard");
+    }
+
+    db.transaction(|db| match db.get_zset(key) {
+        Ok(zset) => serializer::int(zset.map_or(0, |zset| zset.len()) as i64),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_zcount(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("zcount");
+    }
+
+    let range = match parse_score_range(&query.args[0], &query.args[1]) {
+        Ok(range) => range,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| match db.get_zset(key) {
+        Ok(zset) => serializer::int(zset.map_or(0, |zset| zset.count_in_range(&range)) as i64),
+        Err(_) => wrong_type(),
+    })
+}
+
+// ZRANK and ZREVRANK
+pub fn handle_zrank(query: &Query, command: &str, reverse: bool) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() || query.args.len() > 2 {
+        return wrong_args(command);
+    }
+
+    let with_score = match query.args.get(1) {
+        Some(arg) if arg.eq_ignore_ascii_case("WITHSCORE") => true,
+        Some(_) => return syntax_error(),
+        None => false,
+    };
+
+    let member = &query.args[0];
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let rank = zset.and_then(|zset| Some((zset.rank(member, reverse)?, zset.score(member)?)));
+
+        match rank {
+            Some((rank, score)) if with_score => serializer::arr(&[
+                serializer::int(rank as i64),
+                serializer::bulk_str(&format_float(score)),
+            ]),
+            Some((rank, _)) => serializer::int(rank as i64),
+            None if with_score => serializer::null_arr(),
+            None => serializer::nil(),
+        }
+    })
+}
+
+/* ---- Popping ---- */
+
+// popped (member, score) pairs
+type Popped = Vec<(String, f64)>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum PopEnd {
+    Min,
+    Max,
+}
+
+impl PopEnd {
+    fn parse(end: &str) -> Option<PopEnd> {
+        match end.to_uppercase().as_str() {
+            "MIN" => Some(PopEnd::Min),
+            "MAX" => Some(PopEnd::Max),
+            _ => None,
+        }
+    }
+}
+
+// removes up to `count` members from the lowest or highest end of `zset`
+pub fn pop(zset: &mut SortedSet, end: PopEnd, count: usize) -> Popped {
+    let mut popped = Vec::with_capacity(count.min(zset.len()));
+
+    while popped.len() < count {
+        let next = match end {
+            PopEnd::Min => zset.pop_min(),
+            PopEnd::Max => zset.pop_max(),
+        };
+
+        match next {
+            Some(pair) => popped.push(pair),
+            None => break,
+        }
+    }
+
+    popped
+}
+
+// ZPOPMIN and ZPOPMAX
+pub fn handle_zpop(query: &Query, command: &str, end: PopEnd) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 1 {
+        return wrong_args(command);
+    }
+
+    let count = match query.args.first().map(|count| count.parse::<i64>()) {
+        Some(Ok(count)) if count < 0 => {
+            return serializer::err("ERR value is out of range, must be positive")
+        }
+        Some(Ok(count)) => count as usize,
+        Some(Err(_)) => return not_an_integer(),
+        None => 1,
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset_mut(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) => return serializer::str_arr(&[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let popped = pop(zset, end, count);
+
+        db.remove_if_empty(key);
+        members_with_scores(&popped)
+    })
+}
+
+/*
+ * pops up to `count` members from the first non-empty sorted set among
+ * `keys`, returning the key they were popped from
+ */
+fn pop_first_non_empty(
+    db: &mut Database,
+    keys: &[String],
+    end: PopEnd,
+    count: usize,
+) -> Result<Option<(String, Popped)>, WrongTypeErr> {
+    for key in keys {
+        let zset = match db.get_zset_mut(key)? {
+            Some(zset) => zset,
+            None => continue,
+        };
+
+        let popped = pop(zset, end, count);
+
+        db.remove_if_empty(key);
+        return Ok(Some((key.to_string(), popped)));
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug)]
+struct MPopArgs {
+    keys: Vec<String>,
+    end: PopEnd,
+    count: usize,
+}
+
+// parses `key [key ...] MIN|MAX [COUNT count]`, `numkeys` being the number of keys
+fn parse_mpop_args(numkeys: &str, args: &[String]) -> Result<MPopArgs, Vec<u8>> {
+    let numkeys = match numkeys.parse::<i64>() {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return Err(serializer::err("ERR numkeys should be greater than 0")),
+        Err(_) => return Err(not_an_integer()),
+    };
+
+    if args.len() < numkeys + 1 {
+        return Err(syntax_error());
+    }
+
+    let keys = args[..numkeys].to_vec();
+    let end = match PopEnd::parse(&args[numkeys]) {
+        Some(end) => end,
+        None => return Err(syntax_error()),
+    };
+
+    let count = match &args[numkeys + 1..] {
+        [] => 1,
+        [modifier, count] if modifier.to_uppercase() == "COUNT" => match count.parse::<i64>() {
+            Ok(count) if count > 0 => count as usize,
+            Ok(_) => return Err(serializer::err("ERR count should be greater than 0")),
+            Err(_) => return Err(not_an_integer()),
+        },
+        _ => return Err(syntax_error()),
+    };
+
+    Ok(MPopArgs { keys, end, count })
+}
+
+// `key member score`, for BZPOPMIN & BZPOPMAX
+fn bzpop_reply(key: String, popped: Popped) -> Vec<u8> {
+    let (member, score) = &popped[0];
+
+    serializer::arr(&[
+        serializer::bulk_str(&key),
+        serializer::bulk_str(member),
+        serializer::bulk_str(&format_float(*score)),
+    ])
+}
+
+// `key [[member score] ...]`, for ZMPOP & BZMPOP
+fn mpop_reply(key: String, popped: Popped) -> Vec<u8> {
+    let pairs: Vec<Vec<u8>> = popped
+        .iter()
+        .map(|(member, score)| {
+            serializer::arr(&[
+                serializer::bulk_str(member),
+                serializer::bulk_str(&format_float(*score)),
+            ])
+        })
+        .collect();
+
+    serializer::arr(&[serializer::bulk_str(&key), serializer::arr(&pairs)])
+}
+
+pub fn handle_zmpop(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.len() < 2 {
+        return wrong_args("zmpop");
+    }
+
+    let args = match parse_mpop_args(&query.command_str, &query.args) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    db.transaction(
+        |db| match pop_first_non_empty(db, &args.keys, args.end, args.count) {
+            Ok(Some((key, popped))) => mpop_reply(key, popped),
+            Ok(None) => serializer::null_arr(),
+            Err(_) => wrong_type(),
+        },
+    )
+}
+
+/*
+ * the first attempt of BZPOPMIN, BZPOPMAX and BZMPOP. when every key is
+ * empty, the client is blocked until one of them gets a member
+ */
+fn blocking_pop(
+    db: &mut Database,
+    client: &Client,
+    args: MPopArgs,
+    timeout: Option<Duration>,
+    reply: fn(String, Popped) -> Vec<u8>,
+) -> Attempt {
+    let MPopArgs { keys, end, count } = args;
+
+    match pop_first_non_empty(db, &keys, end, count) {
+        Ok(Some((key, popped))) => Attempt::Reply(reply(key, popped)),
+        Err(_) => Attempt::Reply(wrong_type()),
+        Ok(None) => {
+            let serve = move |db: &mut Database, key: &str| {
+                match pop_first_non_empty(db, &[key.to_string()], end, count) {
+                    Ok(Some((key, popped))) => Some(reply(key, popped)),
+                    // the key may have been created with another type
+                    Ok(None) | Err(_) => None,
+                }
+            };
+
+            blocking::block(db, client, &keys, timeout, Box::new(serve));
+            Attempt::Blocked
+        }
+    }
+}
+
+// BZPOPMIN & BZPOPMAX
+pub fn handle_bzpop(query: &Query, client: &Client, command: &str, end: PopEnd) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.is_empty() {
+        return wrong_args(command);
+    }
+
+    let mut keys = vec![query.command_str.to_string()];
+    keys.extend(query.args.iter().cloned());
+
+    let timeout = match parse_timeout(&keys.pop().unwrap()) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+
+    let args = MPopArgs {
+        keys,
+        end,
+        count: 1,
+    };
+
+    match db.transaction(|db| blocking_pop(db, client, args, timeout, bzpop_reply)) {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
+
+pub fn handle_bzmpop(query: &Query, client: &Client) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.len() < 3 {
+        return wrong_args("bzmpop");
+    }
+
+    let timeout = match parse_timeout(&query.command_str) {
+        Ok(timeout) => timeout,
+        Err(err) => return err,
+    };
+
+    let args = match parse_mpop_args(&query.args[0], &query.args[1..]) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    match db.transaction(|db| blocking_pop(db, client, args, timeout, mpop_reply)) {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
+
+/* ---- Ranges ---- */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangeBy {
+    Rank,
+    Score,
+    Lex,
+}
+
+// the members selected by ZRANGE & co, before REV and LIMIT are applied
+enum Selection {
+    Rank(i64, i64),
+    Score(ScoreRange),
+    Lex(LexRange),
+}
+
+struct RangeOpts {
+    selection: Selection,
+    reverse: bool,
+    offset: i64,
+    count: i64, // negative for "all of them"
+    with_scores: bool,
+}
+
+fn parse_selection(by: RangeBy, min: &str, max: &str) -> Result<Selection, Vec<u8>> {
+    match by {
+        RangeBy::Rank => match (min.parse::<i64>(), max.parse::<i64>()) {
+            (Ok(start), Ok(stop)) => Ok(Selection::Rank(start, stop)),
+            _ => Err(not_an_integer()),
+        },
+        RangeBy::Score => parse_score_range(min, max).map(Selection::Score),
+        RangeBy::Lex => parse_lex_range(min, max).map(Selection::Lex),
+    }
+}
+
+/*
+ * parses `start stop [BYSCORE|BYLEX] [REV] [LIMIT offset count] [WITHSCORES]`.
+ * the legacy commands (ZRANGEBYSCORE, ZREVRANGE, ...) pass the kind of range
+ * and the direction they imply, and those can then not be given as options.
+ * ZRANGESTORE does not accept WITHSCORES
+ */
+fn parse_range_opts(
+    args: &[String],
+    mut by: Option<RangeBy>,
+    mut reverse: Option<bool>,
+    store: bool,
+) -> Result<RangeOpts, Vec<u8>> {
+    let mut limit = None;
+    let mut with_scores = false;
+    let mut options = args[2..].iter();
+
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "WITHSCORES" if !store => with_scores = true,
+            "BYSCORE" if by.is_none() => by = Some(RangeBy::Score),
+            "BYLEX" if by.is_none() => by = Some(RangeBy::Lex),
+            "REV" if reverse.is_none() => reverse = Some(true),
+            "LIMIT" => {
+                let (offset, count) = match (options.next(), options.next()) {
+                    (Some(offset), Some(count)) => (offset, count),
+                    _ => return Err(syntax_error()),
+                };
+
+                limit = match (offset.parse::<i64>(), count.parse::<i64>()) {
+                    (Ok(offset), Ok(count)) => Some((offset, count)),
+                    _ => return Err(not_an_integer()),
+                };
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+
+    let by = by.unwrap_or(RangeBy::Rank);
+    let reverse = reverse.unwrap_or(false);
+
+    if limit.is_some() && by == RangeBy::Rank {
+        return Err(serializer::err(
+            "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+        ));
+    }
+
+    if with_scores && by == RangeBy::Lex {
+        return Err(serializer::err(
+            "ERR syntax error, WITHSCORES not supported in combination with BYLEX",
+        ));
+    }
+
+    // reversed score and lex ranges are given as `max min`
+    let (min, max) = if reverse && by != RangeBy::Rank {
+        (&args[1], &args[0])
+    } else {
+        (&args[0], &args[1])
+    };
+
+    let (offset, count) = limit.unwrap_or((0, -1));
+
+    Ok(RangeOpts {
+        selection: parse_selection(by, min, max)?,
+        reverse,
+        offset,
+        count,
+        with_scores,
+    })
+}
+
+fn select(zset: &SortedSet, opts: &RangeOpts) -> Vec<(String, f64)> {
+    if opts.offset < 0 {
+        return vec![];
+    }
+
+    let offset = opts.offset as usize;
+    let count = if opts.count < 0 {
+        None
+    } else {
+        Some(opts.count as usize)
+    };
+
+    match &opts.selection {
+        Selection::Rank(start, stop) => match normalize_range(*start, *stop, zset.len()) {
+            Some((start, stop)) => zset.range_by_rank(start, stop, opts.reverse),
+            None => vec![],
+        },
+        Selection::Score(range) => zset.range(range, opts.reverse, offset, count),
+        Selection::Lex(range) => zset.range(range, opts.reverse, offset, count),
+    }
+}
+
+fn range_reply(members: &[(String, f64)], with_scores: bool) -> Vec<u8> {
+    if with_scores {
+        return members_with_scores(members);
+    }
+
+    let members: Vec<String> = members
+        .iter()
+        .map(|(member, _)| member.to_string())
+        .collect();
+    serializer::str_arr(&members)
+}
+
+/*
+ * ZRANGE, plus the legacy ZREVRANGE, ZRANGEBYSCORE, ZREVRANGEBYSCORE,
+ * ZRANGEBYLEX and ZREVRANGEBYLEX, which come with `by` and `reverse` set
+ */
+pub fn handle_zrange(
+    query: &Query,
+    command: &str,
+    by: Option<RangeBy>,
+    reverse: Option<bool>,
+) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 2 {
+        return wrong_args(command);
+    }
+
+    let opts = match parse_range_opts(&query.args, by, reverse, false) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| match db.get_zset(key) {
+        Ok(Some(zset)) => range_reply(&select(zset, &opts), opts.with_scores),
+        Ok(None) => serializer::str_arr(&[]),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_zrangestore(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let dst = &query.command_str;
+
+    if dst.is_empty() || query.args.len() < 3 {
+        return wrong_args("zrangestore");
+    }
+
+    let src = &query.args[0];
+
+    let opts = match parse_range_opts(&query.args[1..], None, None, true) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let members = match db.get_zset(src) {
+            Ok(Some(zset)) => select(zset, &opts),
+            Ok(None) => vec![],
+            Err(_) => return wrong_type(),
+        };
+
+        // the destination is overwritten whatever its type (and TTL), or
+        // deleted if the result is empty
+        db.remove(dst);
+
+        if members.is_empty() {
+            return serializer::int(0);
+        }
+
+        let mut zset = SortedSet::new();
+
+        for (member, score) in members {
+            zset.insert(member, score);
+        }
+
+        let stored = zset.len();
+        db.insert(dst.to_string(), DataTypes::SortedSet(zset));
+
+        serializer::int(stored as i64)
+    })
+}
+
+pub fn handle_zlexcount(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args("zlexcount");
+    }
+
+    let range = match parse_lex_range(&query.args[0], &query.args[1]) {
+        Ok(range) => range,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| match db.get_zset(key) {
+        Ok(zset) => serializer::int(zset.map_or(0, |zset| zset.count_in_range(&range)) as i64),
+        Err(_) => wrong_type(),
+    })
+}
+
+// ZREMRANGEBYRANK, ZREMRANGEBYSCORE and ZREMRANGEBYLEX
+pub fn handle_zremrange(query: &Query, command: &str, by: RangeBy) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() != 2 {
+        return wrong_args(command);
+    }
+
+    let opts = match parse_selection(by, &query.args[0], &query.args[1]) {
+        Ok(selection) => RangeOpts {
+            selection,
+            reverse: false,
+            offset: 0,
+            count: -1,
+            with_scores: false,
+        },
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset_mut(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let members = select(zset, &opts);
+
+        for (member, _) in members.iter() {
+            zset.remove(member);
+        }
+
+        db.remove_if_empty(key);
+        serializer::int(members.len() as i64)
+    })
+}
+
+/* ---- Union, Intersection & Difference ---- */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZSetOp {
+    Union,
+    Inter,
+    Diff,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            // inf + -inf
+            Aggregate::Sum if (a + b).is_nan() => 0.0,
+            Aggregate::Sum => a + b,
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+// inf * 0 is NaN, which redis turns into 0
+fn weighted(score: f64, weight: f64) -> f64 {
+    let score = score * weight;
+
+    if score.is_nan() {
+        0.0
+    } else {
+        score
+    }
+}
+
+/*
+ * ZUNION, ZINTER and ZDIFF also take plain sets, whose members all have a
+ * score of 1
+ */
+enum Input<'a> {
+    ZSet(&'a SortedSet),
+    Set(&'a Set),
+}
+
+impl Input<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Input::ZSet(zset) => zset.len(),
+            Input::Set(set) => set.len(),
+        }
+    }
+
+    fn score(&self, member: &str) -> Option<f64> {
+        match self {
+            Input::ZSet(zset) => zset.score(member),
+            Input::Set(set) if set.contains(member) => Some(1.0),
+            Input::Set(_) => None,
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (Cow<'_, str>, f64)> + '_> {
+        match self {
+            Input::ZSet(zset) => Box::new(zset.iter().map(|(m, score)| (Cow::Borrowed(m), score))),
+            Input::Set(set) => Box::new(set.iter().map(|member| (member, 1.0))),
+        }
+    }
+}
+
+// missing keys are `None`
+fn lookup_inputs<'a>(
+    db: &'a Database,
+    keys: &[String],
+) -> Result<Vec<Option<Input<'a>>>, WrongTypeErr> {
+    keys.iter()
+        .map(|key| match db.get_zset(key) {
+            Ok(zset) => Ok(zset.map(Input::ZSet)),
+            Err(_) => Ok(Some(Input::Set(db.get_set(key)?.unwrap()))),
+        })
+        .collect()
+}
+
+struct ZSetOpArgs<'a> {
+    keys: &'a [String],
+    weights: Vec<f64>,
+    aggregate: Aggregate,
+    with_scores: bool,
+}
+
+/*
+ * parses `numkeys key [key ...] [WEIGHTS weight [weight ...]]
+ * [AGGREGATE SUM|MIN|MAX] [WITHSCORES]`. ZDIFF takes neither WEIGHTS nor
+ * AGGREGATE, and the STORE variants do not take WITHSCORES
+ */
+fn parse_zset_op_args<'a>(
+    numkeys: &str,
+    args: &'a [String],
+    command: &str,
+    op: ZSetOp,
+    store: bool,
+) -> Result<ZSetOpArgs<'a>, Vec<u8>> {
+    let numkeys = match numkeys.parse::<i64>() {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => {
+            let msg = format!(
+                "ERR at least 1 input key is needed for '{}' command",
+                command
+            );
+            return Err(serializer::err(&msg));
+        }
+        Err(_) => return Err(not_an_integer()),
+    };
+
+    if numkeys > args.len() {
+        return Err(syntax_error());
+    }
+
+    let mut opts = ZSetOpArgs {
+        keys: &args[..numkeys],
+        weights: vec![1.0; numkeys],
+        aggregate: Aggregate::Sum,
+        with_scores: false,
+    };
+
+    let mut options = args[numkeys..].iter();
+
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "WITHSCORES" if !store => opts.with_scores = true,
+
+            "WEIGHTS" if op != ZSetOp::Diff => {
+                for weight in opts.weights.iter_mut() {
+                    *weight = match options.next().map(|weight| parse_float(weight)) {
+                        Some(Some(value)) => value,
+                        Some(None) => {
+                            return Err(serializer::err("ERR weight value is not a float"))
+                        }
+                        None => return Err(syntax_error()),
+                    };
+                }
+            }
+
+            "AGGREGATE" if op != ZSetOp::Diff => {
+                opts.aggregate = match options.next().map(|value| value.to_uppercase()) {
+                    Some(value) if value == "SUM" => Aggregate::Sum,
+                    Some(value) if value == "MIN" => Aggregate::Min,
+                    Some(value) if value == "MAX" => Aggregate::Max,
+                    _ => return Err(syntax_error()),
+                };
+            }
+
+            _ => return Err(syntax_error()),
+        }
+    }
+
+    Ok(opts)
+}
+
+/*
+ * walks the smallest input and keeps the members every other input has, up to
+ * `limit` of them. the scores are weighted and aggregated
+ */
+fn intersect(
+    inputs: &[Option<Input>],
+    weights: &[f64],
+    aggregate: Aggregate,
+    limit: usize,
+) -> Vec<(String, f64)> {
+    let mut inputs: Vec<(&Input, f64)> = match inputs
+        .iter()
+        .zip(weights.iter())
+        .map(|(input, weight)| input.as_ref().map(|input| (input, *weight)))
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(inputs) => inputs,
+        None => return vec![],
+    };
+
+    inputs.sort_by_key(|(input, _)| input.len());
+
+    let ((smallest, weight), others) = match inputs.split_first() {
+        Some(split) => split,
+        None => return vec![],
+    };
+
+    let mut members = vec![];
+
+    'members: for (member, score) in smallest.iter() {
+        if members.len() >= limit {
+            break;
+        }
+
+        let mut score = weighted(score, *weight);
+
+        for (other, weight) in others {
+            match other.score(&member) {
+                Some(other) => score = aggregate.apply(score, weighted(other, *weight)),
+                None => continue 'members,
+            }
+        }
+
+        members.push((member.into_owned(), score));
+    }
+
+    members
+}
+
+fn union(inputs: &[Option<Input>], weights: &[f64], aggregate: Aggregate) -> Vec<(String, f64)> {
+    let mut scores: HashMap<String, f64> = HashMap::new();
+
+    for (input, weight) in inputs.iter().zip(weights.iter()) {
+        for (member, score) in input.iter().flat_map(|input| input.iter()) {
+            let score = weighted(score, *weight);
+
+            match scores.get_mut(member.as_ref()) {
+                Some(current) => *current = aggregate.apply(*current, score),
+                None => {
+                    scores.insert(member.into_owned(), score);
+                }
+            }
+        }
+    }
+
+    scores.into_iter().collect()
+}
+
+// members of the first input that no other input has, with their score
+fn diff(inputs: &[Option<Input>]) -> Vec<(String, f64)> {
+    let (first, others) = match inputs.split_first() {
+        Some((Some(first), others)) => (first, others),
+        _ => return vec![],
+    };
+
+    first
+        .iter()
+        .filter(|(member, _)| {
+            others
+                .iter()
+                .flatten()
+                .all(|other| other.score(member).is_none())
+        })
+        .map(|(member, score)| (member.into_owned(), score))
+        .collect()
+}
+
+// the result, as a sorted set
+fn compute(db: &Database, op: ZSetOp, args: &ZSetOpArgs) -> Result<SortedSet, WrongTypeErr> {
+    let inputs = lookup_inputs(db, args.keys)?;
+
+    let members = match op {
+        ZSetOp::Union => union(&inputs, &args.weights, args.aggregate),
+        ZSetOp::Inter => intersect(&inputs, &args.weights, args.aggregate, usize::MAX),
+        ZSetOp::Diff => diff(&inputs),
+    };
+
+    let mut zset = SortedSet::new();
+
+    for (member, score) in members {
+        zset.insert(member, score);
+    }
+
+    Ok(zset)
+}
+
+// ZUNION, ZINTER and ZDIFF
+pub fn handle_zset_op(query: &Query, command: &str, op: ZSetOp) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.is_empty() {
+        return wrong_args(command);
+    }
+
+    let opts = match parse_zset_op_args(&query.command_str, &query.args, command, op, false) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| match compute(db, op, &opts) {
+        Ok(zset) => {
+            let members: Vec<(String, f64)> = zset
+                .iter()
+                .map(|(member, score)| (member.to_string(), score))
+                .collect();
+
+            range_reply(&members, opts.with_scores)
+        }
+        Err(_) => wrong_type(),
+    })
+}
+
+// ZUNIONSTORE, ZINTERSTORE and ZDIFFSTORE
+pub fn handle_zset_op_store(query: &Query, command: &str, op: ZSetOp) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let dst = &query.command_str;
+
+    if dst.is_empty() || query.args.len() < 2 {
+        return wrong_args(command);
+    }
+
+    let opts = match parse_zset_op_args(&query.args[0], &query.args[1..], command, op, true) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let zset = match compute(db, op, &opts) {
+            Ok(zset) => zset,
+            Err(_) => return wrong_type(),
+        };
+
+        let stored = zset.len();
+
+        // the destination is overwritten whatever its type (and TTL), or
+        // deleted if the result is empty
+        db.remove(dst);
+
+        if stored > 0 {
+            db.insert(dst.to_string(), DataTypes::SortedSet(zset));
+        }
+
+        serializer::int(stored as i64)
+    })
+}
+
+pub fn handle_zintercard(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() || query.args.is_empty() {
+        return wrong_args("zintercard");
+    }
+
+    let numkeys = match query.command_str.parse::<i64>() {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return serializer::err("ERR numkeys should be greater than 0"),
+        Err(_) => return not_an_integer(),
+    };
+
+    if numkeys > query.args.len() {
+        return serializer::err("ERR Number of keys can't be greater than number of args");
+    }
+
+    let keys = &query.args[..numkeys];
+
+    // LIMIT 0 means no limit
+    let limit = match &query.args[numkeys..] {
+        [] => usize::MAX,
+        [option, limit] if option.to_uppercase() == "LIMIT" => match limit.parse::<i64>() {
+            Ok(0) => usize::MAX,
+            Ok(limit) if limit > 0 => limit as usize,
+            Ok(_) => return serializer::err("ERR LIMIT can't be negative"),
+            Err(_) => return not_an_integer(),
+        },
+        _ => return syntax_error(),
+    };
+
+    db.transaction(|db| match lookup_inputs(db, keys) {
+        Ok(inputs) => {
+            let weights = vec![1.0; inputs.len()];
+            let members = intersect(&inputs, &weights, Aggregate::Sum, limit);
+
+            serializer::int(members.len() as i64)
+        }
+        Err(_) => wrong_type(),
+    })
+}
+
+/* ---- Random Members & Scan ---- */
+
+pub fn handle_zrandmember(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() > 2 {
+        return wrong_args("zrandmember");
+    }
+
+    let with_scores = match query.args.get(1) {
+        Some(arg) if arg.to_uppercase() == "WITHSCORES" => true,
+        Some(_) => return syntax_error(),
+        None => false,
+    };
+
+    let count = match query.args.first() {
+        Some(count) => match parse_rand_count(count, with_scores) {
+            Ok(count) => Some(count),
+            Err(err) => return err,
+        },
+        None => None,
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) if count.is_some() => return serializer::str_arr(&[]),
+            Ok(None) => return serializer::nil(),
+            Err(_) => return wrong_type(),
+        };
+
+        let mut rng = rand::thread_rng();
+
+        let count = match count {
+            Some(count) => count,
+            None => {
+                let (member, _) = zset.member_at(rng.gen_range(0..zset.len())).unwrap();
+                return serializer::bulk_str(member);
+            }
+        };
+
+        let mut items: Vec<String> = vec![];
+
+        let mut push = |rank: usize| {
+            let (member, score) = zset.member_at(rank).unwrap();
+            items.push(member.to_string());
+
+            if with_scores {
+                items.push(format_float(score));
+            }
+        };
+
+        // a positive count picks distinct members, a negative one may repeat
+        // them, and the reply then grows with what is actually picked
+        if count >= 0 {
+            let amount = (count as usize).min(zset.len());
+            index::sample(&mut rng, zset.len(), amount)
+                .into_iter()
+                .for_each(&mut push);
+        } else {
+            for _ in 0..count.unsigned_abs() {
+                push(rng.gen_range(0..zset.len()));
+            }
+        }
+
+        serializer::str_arr(&items)
+    })
+}
+
+pub fn handle_zscan(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("zscan");
+    }
+
+    let opts = match parse_scan_args(&query.args, false) {
+        Ok(opts) => opts,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let zset = match db.get_zset(key) {
+            Ok(Some(zset)) => zset,
+            Ok(None) => return scan_reply(0, &[]),
+            Err(_) => return wrong_type(),
+        };
+
+        let (cursor, found) = zset.scan(opts.cursor, opts.count);
+
+        let items: Vec<String> = found
+            .into_iter()
+            .filter(|(member, _)| opts.matches(member))
+            .flat_map(|(member, score)| [member.to_string(), format_float(score)])
+            .collect();
+
+        scan_reply(cursor, &items)
+    })
+}
@@ -0,0 +1,635 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::spec_helper::*;
+
+    const WAIT: Duration = Duration::from_secs(2);
+    // long enough for a command sent before to be blocked
+    const SETTLE: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_zadd_options() {
+        assert_eq!(run(&["ZADD", "zadd", "1", "a", "2", "b"]), ":2\r\n");
+
+        assert_eq!(run(&["ZADD", "zadd", "NX", "5", "a", "3", "c"]), ":1\r\n");
+        assert_eq!(run(&["ZSCORE", "zadd", "a"]), "$1\r\n1\r\n");
+        assert_eq!(run(&["ZADD", "zadd", "XX", "5", "a", "4", "d"]), ":0\r\n");
+        assert_eq!(run(&["ZSCORE", "zadd", "a"]), "$1\r\n5\r\n");
+        assert_eq!(run(&["ZSCORE", "zadd", "d"]), "$-1\r\n");
+
+        // CH counts the updated members too
+        assert_eq!(run(&["ZADD", "zadd", "XX", "CH", "6", "a"]), ":1\r\n");
+        assert_eq!(run(&["ZADD", "zadd", "GT", "CH", "4", "a"]), ":0\r\n");
+        assert_eq!(run(&["ZADD", "zadd", "GT", "CH", "7", "a"]), ":1\r\n");
+        assert_eq!(
+            run(&["ZADD", "zadd", "LT", "CH", "8", "a", "1", "b"]),
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&["ZMSCORE", "zadd", "a", "b", "z"]),
+            "*3\r\n$1\r\n7\r\n$1\r\n1\r\n$-1\r\n"
+        );
+
+        // INCR replies with the new score, or nil when a condition failed
+        assert_eq!(run(&["ZADD", "zadd", "INCR", "2", "a"]), "$1\r\n9\r\n");
+        assert_eq!(run(&["ZADD", "zadd", "NX", "INCR", "1", "a"]), "$-1\r\n");
+        assert_eq!(run(&["ZADD", "zadd", "GT", "INCR", "-1", "a"]), "$-1\r\n");
+        assert_eq!(run(&["ZSCORE", "zadd", "a"]), "$1\r\n9\r\n");
+
+        // XX on a missing key does not leave an empty one behind
+        assert_eq!(run(&["ZADD", "zadd:missing", "XX", "1", "a"]), ":0\r\n");
+        assert_eq!(run(&["EXISTS", "zadd:missing"]), ":0\r\n");
+
+        assert_eq!(
+            run(&["ZADD", "zadd", "NX", "XX", "1", "a"]),
+            "-ERR XX and NX options at the same time are not compatible\r\n"
+        );
+        assert_eq!(
+            run(&["ZADD", "zadd", "GT", "LT", "1", "a"]),
+            "-ERR GT, LT, and/or NX options at the same time are not compatible\r\n"
+        );
+        assert_eq!(
+            run(&["ZADD", "zadd", "INCR", "1", "a", "2", "b"]),
+            "-ERR INCR option supports a single increment-element pair\r\n"
+        );
+        assert_eq!(
+            run(&["ZADD", "zadd", "1", "a", "x", "b"]),
+            "-ERR value is not a valid float\r\n"
+        );
+        // nothing is added when one of the scores is wrong
+        assert_eq!(run(&["ZCARD", "zadd"]), ":3\r\n");
+    }
+
+    #[test]
+    fn test_zincrby() {
+        assert_eq!(run(&["ZINCRBY", "zincrby", "1.5", "a"]), "$3\r\n1.5\r\n");
+        assert_eq!(run(&["ZINCRBY", "zincrby", "-2", "a"]), "$4\r\n-0.5\r\n");
+
+        run(&["ZADD", "zincrby", "+inf", "b"]);
+        assert_eq!(
+            run(&["ZINCRBY", "zincrby", "-inf", "b"]),
+            "-ERR resulting score is not a number (NaN)\r\n"
+        );
+        assert_eq!(run(&["ZSCORE", "zincrby", "b"]), "$3\r\ninf\r\n");
+    }
+
+    #[test]
+    fn test_rank_after_deletes() {
+        // more members than a listpack holds, so the skiplist is used
+        for i in 0..200 {
+            run(&["ZADD", "zrank", &i.to_string(), &format!("m{}", i)]);
+        }
+        assert_eq!(run(&["OBJECT", "ENCODING", "zrank"]), "$8\r\nskiplist\r\n");
+
+        for i in (1..200).step_by(2) {
+            assert_eq!(run(&["ZREM", "zrank", &format!("m{}", i)]), ":1\r\n");
+        }
+        assert_eq!(
+            run(&["ZPOPMIN", "zrank", "2"]),
+            "*4\r\n$2\r\nm0\r\n$1\r\n0\r\n$2\r\nm2\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            run(&["ZPOPMAX", "zrank"]),
+            "*2\r\n$4\r\nm198\r\n$3\r\n198\r\n"
+        );
+        assert_eq!(run(&["ZCARD", "zrank"]), ":97\r\n");
+
+        // m4, m6, ... m196 are left, ranked from 0 to 96
+        for i in (4..198).step_by(2) {
+            let member = format!("m{}", i);
+            let rank = (i - 4) / 2;
+
+            assert_eq!(run(&["ZRANK", "zrank", &member]), format!(":{}\r\n", rank));
+            assert_eq!(
+                run(&["ZREVRANK", "zrank", &member]),
+                format!(":{}\r\n", 96 - rank)
+            );
+        }
+
+        assert_eq!(
+            run(&["ZRANK", "zrank", "m100", "WITHSCORE"]),
+            "*2\r\n:48\r\n$3\r\n100\r\n"
+        );
+        assert_eq!(run(&["ZRANK", "zrank", "m1"]), "$-1\r\n");
+        assert_eq!(run(&["ZREM", "zrank", "m1", "m4", "m4"]), ":1\r\n");
+        assert_eq!(run(&["ZRANK", "zrank", "m6"]), ":0\r\n");
+
+        assert_eq!(run(&["ZCOUNT", "zrank", "10", "20"]), ":6\r\n");
+        assert_eq!(run(&["ZCOUNT", "zrank", "(10", "(20"]), ":4\r\n");
+        assert_eq!(run(&["ZCOUNT", "zrank", "-inf", "+inf"]), ":96\r\n");
+        assert_eq!(run(&["ZCOUNT", "zrank", "(6", "6"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_zpop() {
+        run(&["ZADD", "zpop", "1", "a", "2", "b"]);
+
+        assert_eq!(run(&["ZPOPMIN", "zpop", "0"]), "*0\r\n");
+        assert_eq!(
+            run(&["ZPOPMIN", "zpop", "-1"]),
+            "-ERR value is out of range, must be positive\r\n"
+        );
+        assert_eq!(
+            run(&["ZPOPMAX", "zpop", "10"]),
+            "*4\r\n$1\r\nb\r\n$1\r\n2\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+
+        // the emptied key is gone
+        assert_eq!(run(&["EXISTS", "zpop"]), ":0\r\n");
+        assert_eq!(run(&["ZPOPMIN", "zpop"]), "*0\r\n");
+    }
+
+    // the bulk strings of a flat array reply, in order
+    fn items(reply: &str) -> Vec<String> {
+        reply
+            .split("\r\n")
+            .skip(2)
+            .step_by(2)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn zrange(args: &[&str]) -> Vec<String> {
+        items(&run(args))
+    }
+
+    #[test]
+    fn test_zrange() {
+        run(&[
+            "ZADD", "zrange", "1", "a", "2", "b", "3", "c", "4", "d", "5", "e",
+        ]);
+
+        assert_eq!(zrange(&["ZRANGE", "zrange", "1", "3"]), ["b", "c", "d"]);
+        assert_eq!(
+            zrange(&["ZRANGE", "zrange", "0", "-1", "REV"]),
+            ["e", "d", "c", "b", "a"]
+        );
+        assert!(zrange(&["ZRANGE", "zrange", "3", "1"]).is_empty());
+
+        // score ranges, given as `max min` when reversed
+        assert_eq!(
+            zrange(&["ZRANGE", "zrange", "(1", "4", "BYSCORE"]),
+            ["b", "c", "d"]
+        );
+        assert_eq!(
+            zrange(&["ZRANGE", "zrange", "4", "(1", "BYSCORE", "REV"]),
+            ["d", "c", "b"]
+        );
+        assert_eq!(
+            zrange(&["ZRANGE", "zrange", "-inf", "+inf", "BYSCORE", "LIMIT", "1", "2"]),
+            ["b", "c"]
+        );
+        assert_eq!(
+            zrange(&["ZRANGE", "zrange", "-inf", "+inf", "BYSCORE", "LIMIT", "2", "-1"]),
+            ["c", "d", "e"]
+        );
+        assert!(
+            zrange(&["ZRANGE", "zrange", "-inf", "+inf", "BYSCORE", "LIMIT", "-1", "5"]).is_empty()
+        );
+        assert_eq!(
+            run(&[
+                "ZRANGE",
+                "zrange",
+                "+inf",
+                "-inf",
+                "BYSCORE",
+                "REV",
+                "LIMIT",
+                "0",
+                "1",
+                "WITHSCORES"
+            ]),
+            "*2\r\n$1\r\ne\r\n$1\r\n5\r\n"
+        );
+
+        assert_eq!(
+            run(&["ZRANGE", "zrange", "0", "1", "LIMIT", "0", "1"]),
+            "-ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "zrange", "a", "b", "BYSCORE"]),
+            "-ERR min or max is not a float\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "zrange", "0", "1", "BYSCORE", "BYLEX"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_zrange_bylex() {
+        run(&[
+            "ZADD", "zlex", "0", "a", "0", "b", "0", "c", "0", "d", "0", "e",
+        ]);
+
+        assert_eq!(zrange(&["ZRANGE", "zlex", "[b", "(d", "BYLEX"]), ["b", "c"]);
+        assert_eq!(
+            zrange(&["ZRANGE", "zlex", "-", "+", "BYLEX", "LIMIT", "1", "2"]),
+            ["b", "c"]
+        );
+        assert_eq!(
+            zrange(&["ZRANGE", "zlex", "(d", "-", "BYLEX", "REV"]),
+            ["c", "b", "a"]
+        );
+        assert!(zrange(&["ZRANGE", "zlex", "+", "-", "BYLEX"]).is_empty());
+        assert_eq!(run(&["ZLEXCOUNT", "zlex", "(a", "[c"]), ":2\r\n");
+
+        assert_eq!(
+            run(&["ZRANGE", "zlex", "b", "d", "BYLEX"]),
+            "-ERR min or max not valid string range item\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "zlex", "-", "+", "BYLEX", "WITHSCORES"]),
+            "-ERR syntax error, WITHSCORES not supported in combination with BYLEX\r\n"
+        );
+    }
+
+    #[test]
+    fn test_legacy_ranges() {
+        run(&["ZADD", "zlegacy", "1", "a", "2", "b", "3", "c", "4", "d"]);
+        run(&[
+            "ZADD",
+            "zlegacy:lex",
+            "0",
+            "a",
+            "0",
+            "b",
+            "0",
+            "c",
+            "0",
+            "d",
+        ]);
+
+        assert_eq!(zrange(&["ZREVRANGE", "zlegacy", "0", "1"]), ["d", "c"]);
+        assert_eq!(
+            run(&[
+                "ZRANGEBYSCORE",
+                "zlegacy",
+                "2",
+                "4",
+                "WITHSCORES",
+                "LIMIT",
+                "0",
+                "1"
+            ]),
+            "*2\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        );
+        assert_eq!(
+            zrange(&["ZREVRANGEBYSCORE", "zlegacy", "4", "(2"]),
+            ["d", "c"]
+        );
+        assert_eq!(
+            zrange(&["ZRANGEBYLEX", "zlegacy:lex", "[c", "+"]),
+            ["c", "d"]
+        );
+        assert_eq!(zrange(&["ZREVRANGEBYLEX", "zlegacy:lex", "+", "(c"]), ["d"]);
+
+        // the kind of range and its direction are implied
+        assert_eq!(
+            run(&["ZRANGEBYSCORE", "zlegacy", "1", "2", "REV"]),
+            "-ERR syntax error\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGEBYLEX", "zlegacy:lex", "-", "+", "BYSCORE"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_zremrange() {
+        run(&[
+            "ZADD",
+            "zremrange",
+            "1",
+            "a",
+            "2",
+            "b",
+            "3",
+            "c",
+            "4",
+            "d",
+            "5",
+            "e",
+        ]);
+
+        assert_eq!(run(&["ZREMRANGEBYRANK", "zremrange", "0", "1"]), ":2\r\n");
+        assert_eq!(
+            run(&["ZREMRANGEBYSCORE", "zremrange", "(3", "+inf"]),
+            ":2\r\n"
+        );
+        assert_eq!(zrange(&["ZRANGE", "zremrange", "0", "-1"]), ["c"]);
+
+        // the emptied key is gone
+        assert_eq!(run(&["ZREMRANGEBYRANK", "zremrange", "0", "-1"]), ":1\r\n");
+        assert_eq!(run(&["EXISTS", "zremrange"]), ":0\r\n");
+
+        run(&[
+            "ZADD",
+            "zremrange:lex",
+            "0",
+            "a",
+            "0",
+            "b",
+            "0",
+            "c",
+            "0",
+            "d",
+        ]);
+        assert_eq!(
+            run(&["ZREMRANGEBYLEX", "zremrange:lex", "[b", "[c"]),
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&["ZREMRANGEBYLEX", "zremrange:lex", "(d", "+"]),
+            ":0\r\n"
+        );
+        assert_eq!(zrange(&["ZRANGE", "zremrange:lex", "0", "-1"]), ["a", "d"]);
+    }
+
+    #[test]
+    fn test_zrangestore() {
+        run(&["ZADD", "zrangestore:src", "1", "a", "2", "b", "3", "c"]);
+
+        assert_eq!(
+            run(&[
+                "ZRANGESTORE",
+                "zrangestore:dst",
+                "zrangestore:src",
+                "1",
+                "-1"
+            ]),
+            ":2\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "zrangestore:dst", "0", "-1", "WITHSCORES"]),
+            "*4\r\n$1\r\nb\r\n$1\r\n2\r\n$1\r\nc\r\n$1\r\n3\r\n"
+        );
+
+        assert_eq!(
+            run(&[
+                "ZRANGESTORE",
+                "zrangestore:dst",
+                "zrangestore:src",
+                "(1",
+                "+inf",
+                "BYSCORE",
+                "LIMIT",
+                "0",
+                "1"
+            ]),
+            ":1\r\n"
+        );
+        assert_eq!(
+            run(&["ZRANGE", "zrangestore:dst", "0", "-1"]),
+            "*1\r\n$1\r\nb\r\n"
+        );
+    }
+
+    #[test]
+    fn test_zrangestore_replaces_destination() {
+        run(&["ZADD", "zrangestore:from", "1", "a"]);
+
+        assert_store_replaces(
+            "zrangestore:to",
+            &[
+                "ZRANGESTORE",
+                "zrangestore:to",
+                "zrangestore:from",
+                "0",
+                "-1",
+            ],
+            &[
+                "ZRANGESTORE",
+                "zrangestore:to",
+                "zrangestore:from",
+                "5",
+                "10",
+            ],
+            "zset",
+        );
+    }
+
+    #[test]
+    fn test_zunion_zinter_zdiff() {
+        run(&["ZADD", "zop:a", "1", "a", "2", "b", "3", "c"]);
+        run(&["ZADD", "zop:b", "10", "b", "20", "c", "30", "d"]);
+
+        assert_eq!(
+            run(&["ZUNION", "2", "zop:a", "zop:b", "WITHSCORES"]),
+            "*8\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$2\r\n12\r\n\
+             $1\r\nc\r\n$2\r\n23\r\n$1\r\nd\r\n$2\r\n30\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "ZINTER",
+                "2",
+                "zop:a",
+                "zop:b",
+                "WEIGHTS",
+                "2",
+                "1",
+                "AGGREGATE",
+                "MAX",
+                "WITHSCORES"
+            ]),
+            "*4\r\n$1\r\nb\r\n$2\r\n10\r\n$1\r\nc\r\n$2\r\n20\r\n"
+        );
+        assert_eq!(run(&["ZDIFF", "2", "zop:a", "zop:b"]), "*1\r\n$1\r\na\r\n");
+        assert_eq!(run(&["ZINTERCARD", "2", "zop:a", "zop:b"]), ":2\r\n");
+        assert_eq!(
+            run(&["ZINTERCARD", "2", "zop:a", "zop:b", "LIMIT", "1"]),
+            ":1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_zstore() {
+        run(&["ZADD", "zstore:a", "1", "a", "2", "b"]);
+        run(&["ZADD", "zstore:b", "3", "b"]);
+
+        assert_eq!(
+            run(&["ZUNIONSTORE", "zstore:union", "2", "zstore:a", "zstore:b"]),
+            ":2\r\n"
+        );
+        assert_eq!(run(&["ZSCORE", "zstore:union", "b"]), "$1\r\n5\r\n");
+
+        assert_eq!(
+            run(&["ZINTERSTORE", "zstore:inter", "2", "zstore:a", "zstore:b"]),
+            ":1\r\n"
+        );
+        assert_eq!(run(&["ZSCORE", "zstore:inter", "b"]), "$1\r\n5\r\n");
+
+        assert_eq!(
+            run(&["ZDIFFSTORE", "zstore:diff", "2", "zstore:a", "zstore:b"]),
+            ":1\r\n"
+        );
+        assert_eq!(run(&["ZSCORE", "zstore:diff", "a"]), "$1\r\n1\r\n");
+    }
+
+    #[test]
+    fn test_zstore_replaces_destination() {
+        run(&["ZADD", "zreplace:a", "1", "a"]);
+
+        for command in ["ZUNIONSTORE", "ZINTERSTORE", "ZDIFFSTORE"] {
+            assert_store_replaces(
+                "zreplace:dst",
+                &[command, "zreplace:dst", "1", "zreplace:a"],
+                &[command, "zreplace:dst", "1", "zreplace:missing"],
+                "zset",
+            );
+        }
+    }
+
+    #[test]
+    fn test_zmpop() {
+        run(&["ZADD", "zmpop:b", "1", "a", "2", "b", "3", "c"]);
+
+        assert_eq!(
+            run(&["ZMPOP", "2", "zmpop:a", "zmpop:b", "MIN"]),
+            "*2\r\n$7\r\nzmpop:b\r\n*1\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            run(&["ZMPOP", "1", "zmpop:b", "MAX", "COUNT", "5"]),
+            "*2\r\n$7\r\nzmpop:b\r\n*2\r\n*2\r\n$1\r\nc\r\n$1\r\n3\r\n\
+             *2\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        );
+
+        // the emptied key is gone
+        assert_eq!(run(&["EXISTS", "zmpop:b"]), ":0\r\n");
+        assert_eq!(run(&["ZMPOP", "1", "zmpop:b", "MIN"]), "*-1\r\n");
+
+        assert_eq!(
+            run(&["ZMPOP", "0", "zmpop:b", "MIN"]),
+            "-ERR numkeys should be greater than 0\r\n"
+        );
+        assert_eq!(
+            run(&["ZMPOP", "1", "zmpop:b", "MIN", "COUNT", "0"]),
+            "-ERR count should be greater than 0\r\n"
+        );
+        assert_eq!(
+            run(&["ZMPOP", "1", "zmpop:b", "MIDDLE"]),
+            "-ERR syntax error\r\n"
+        );
+    }
+
+    #[test]
+    fn test_bzpop_served_right_away() {
+        run(&["ZADD", "bzpop:now", "1", "a", "2", "b"]);
+
+        let mut conn = Connection::open();
+
+        conn.send(&["BZPOPMAX", "bzpop:empty", "bzpop:now", "0"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "*3\r\n$9\r\nbzpop:now\r\n$1\r\nb\r\n$1\r\n2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_bzpop_served_by_zadd() {
+        let mut first = Connection::open();
+        let mut second = Connection::open();
+
+        first.send(&["BZPOPMIN", "bzpop:a", "bzpop:b", "0"]);
+        assert_eq!(first.reply(SETTLE), None);
+        second.send(&["BZMPOP", "0", "1", "bzpop:b", "MIN", "COUNT", "2"]);
+        assert_eq!(second.reply(SETTLE), None);
+
+        // the first client to block is the first one served
+        run(&["ZADD", "bzpop:b", "1", "x", "2", "y", "3", "z"]);
+        assert_eq!(
+            first.reply(WAIT).unwrap(),
+            "*3\r\n$7\r\nbzpop:b\r\n$1\r\nx\r\n$1\r\n1\r\n"
+        );
+        assert_eq!(
+            second.reply(WAIT).unwrap(),
+            "*2\r\n$7\r\nbzpop:b\r\n*2\r\n*2\r\n$1\r\ny\r\n$1\r\n2\r\n\
+             *2\r\n$1\r\nz\r\n$1\r\n3\r\n"
+        );
+    }
+
+    #[test]
+    fn test_bzpop_timeout() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BZPOPMIN", "bzpop:timeout", "0.1"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "*-1\r\n");
+
+        conn.send(&["BZMPOP", "0.1", "1", "bzpop:timeout", "MAX"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "*-1\r\n");
+    }
+
+    #[test]
+    fn test_bzpop_huge_timeout() {
+        let mut conn = Connection::open();
+
+        conn.send(&["BZPOPMIN", "bzpop:huge", "1e300"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "-ERR timeout is out of range\r\n"
+        );
+        conn.send(&["BZMPOP", "1e300", "1", "bzpop:huge", "MIN"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "-ERR timeout is out of range\r\n"
+        );
+
+        // too far away to ever expire, the client waits for a ZADD
+        conn.send(&["BZPOPMAX", "bzpop:huge", "1e19"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        let mut other = Connection::open();
+        other.send(&["ZADD", "bzpop:huge", "1", "a"]);
+        assert_eq!(other.reply(WAIT).unwrap(), ":1\r\n");
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            "*3\r\n$10\r\nbzpop:huge\r\n$1\r\na\r\n$1\r\n1\r\n"
+        );
+
+        conn.send(&["ZCARD", "bzpop:huge"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), ":0\r\n");
+    }
+
+    #[test]
+    fn test_zrandmember() {
+        run(&["ZADD", "zrandmember", "1", "a", "2", "b"]);
+
+        assert_eq!(
+            run(&["ZRANDMEMBER", "zrandmember", "5"]).lines().count(),
+            1 + 2 * 2
+        );
+        assert_eq!(
+            run(&["ZRANDMEMBER", "zrandmember", "-5", "WITHSCORES"])
+                .lines()
+                .count(),
+            1 + 5 * 4
+        );
+        assert_eq!(run(&["ZRANDMEMBER", "zrandmember:missing", "-3"]), "*0\r\n");
+
+        // any positive count is fine, it is capped by the size of the key
+        assert_eq!(
+            run(&["ZRANDMEMBER", "zrandmember", "9223372036854775807"])
+                .lines()
+                .count(),
+            1 + 2 * 2
+        );
+
+        // out of range counts are refused before anything is allocated
+        assert_eq!(
+            run(&["ZRANDMEMBER", "zrandmember", "-9223372036854775808"]),
+            "-ERR value is out of range\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "ZRANDMEMBER",
+                "zrandmember",
+                "-4611686018427387904",
+                "WITHSCORES"
+            ]),
+            "-ERR value is out of range\r\n"
+        );
+        assert_eq!(run(&["ZCARD", "zrandmember"]), ":2\r\n");
+    }
+}
@@ -0,0 +1,166 @@
+#![cfg(test)]
+
+use once_cell::sync::Lazy;
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use crate::velocity::{client::Client, database::DatabaseOps, query::Query};
+
+/*
+ * helpers for the command specs. commands go through the same parsing and
+ * dispatch as the ones sent over the network, against the one global
+ * database. specs run in parallel, so each of them sticks to keys of its own
+ */
+
+thread_local! {
+    static CLIENT: Client = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        Client::new(stream)
+    };
+}
+
+// a server of its own, for the commands that block the connection
+static SERVER: Lazy<SocketAddr> = Lazy::new(|| {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            thread::spawn(move || Client::new(stream).run());
+        }
+    });
+
+    address
+});
+
+// the RESP array a client would send for `args`
+pub fn encode(args: &[&str]) -> Vec<u8> {
+    let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+    encode_bytes(&args)
+}
+
+pub fn encode_bytes(args: &[&[u8]]) -> Vec<u8> {
+    let mut query = format!("*{}\r\n", args.len()).into_bytes();
+
+    for arg in args {
+        query.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        query.extend_from_slice(arg);
+        query.extend_from_slice(b"\r\n");
+    }
+
+    query
+}
+
+// runs a command and returns its reply as text
+pub fn run(args: &[&str]) -> String {
+    String::from_utf8_lossy(&run_bytes(&encode(args))).into_owned()
+}
+
+// runs an encoded command and returns its reply untouched
+pub fn run_bytes(query: &[u8]) -> Vec<u8> {
+    let query = Query::new(query);
+    CLIENT.with(|client| query.create_response(client))
+}
+
+// SET EX gives the TTL from another thread, this is immediate
+pub fn set_ttl(key: &str) {
+    let at_unix_time = chrono::Utc::now().timestamp() + 1000;
+    DatabaseOps.transaction(|db| db.set_expires_at(key, Some(at_unix_time)));
+}
+
+pub fn has_ttl(key: &str) -> bool {
+    DatabaseOps.transaction(|db| db.expires_at(key).is_some())
+}
+
+/*
+ * a STORE command replaces whatever `dst` held, TTL included, with a value of
+ * `type_name`. `empty` is the same command with an empty result, which
+ * deletes `dst` instead
+ */
+pub fn assert_store_replaces(dst: &str, store: &[&str], empty: &[&str], type_name: &str) {
+    run(&["SET", dst, "x"]);
+    set_ttl(dst);
+
+    let reply = run(store);
+    assert!(reply.starts_with(':') && reply != ":0\r\n", "{}", reply);
+    assert_eq!(run(&["TYPE", dst]), format!("+{}\r\n", type_name));
+    assert!(!has_ttl(dst));
+
+    assert_eq!(run(empty), ":0\r\n");
+    assert_eq!(run(&["EXISTS", dst]), ":0\r\n");
+}
+
+pub struct Connection {
+    stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl Connection {
+    pub fn open() -> Connection {
+        Connection {
+            stream: TcpStream::connect(*SERVER).unwrap(),
+            buffer: vec![],
+        }
+    }
+
+    pub fn send(&mut self, args: &[&str]) {
+        self.stream.write_all(&encode(args)).unwrap();
+    }
+
+    // the next reply, `None` when it takes longer than `timeout`
+    pub fn reply(&mut self, timeout: Duration) -> Option<String> {
+        let mut chunk = [0; 1024];
+
+        self.stream.set_read_timeout(Some(timeout)).unwrap();
+
+        loop {
+            if let Some(len) = frame_len(&self.buffer) {
+                let reply: Vec<u8> = self.buffer.drain(..len).collect();
+                return Some(String::from_utf8_lossy(&reply).into_owned());
+            }
+
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return None,
+                Ok(size) => self.buffer.extend_from_slice(&chunk[..size]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return None
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+// the length of the first complete RESP reply in `data`
+fn frame_len(data: &[u8]) -> Option<usize> {
+    let end = data.windows(2).position(|window| window == b"\r\n")?;
+    let line = std::str::from_utf8(&data[1..end]).ok()?;
+    let header = end + 2;
+
+    match data.first()? {
+        b'$' => match line.parse::<i64>().ok()? {
+            len if len < 0 => Some(header),
+            len => {
+                let len = header + len as usize + 2;
+                (data.len() >= len).then_some(len)
+            }
+        },
+        b'*' => {
+            let mut len = header;
+
+            for _ in 0..line.parse::<i64>().ok()?.max(0) {
+                len += frame_len(&data[len..])?;
+            }
+
+            Some(len)
+        }
+        _ => Some(header),
+    }
+}
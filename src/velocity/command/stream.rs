@@ -0,0 +1,1664 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::{not_an_integer, syntax_error, wrong_args, wrong_type, Attempt};
+use crate::velocity::{
+    blocking,
+    client::Client,
+    database::{Database, DatabaseOps},
+    query::Query,
+    serializer,
+    stream::{
+        ConsumerGroup, Entry, Fields, IdErr, IdSpec, Stream, StreamId, Trim, TrimStrategy,
+        APPROX_TRIM_LIMIT,
+    },
+};
+
+fn invalid_id() -> Vec<u8> {
+    serializer::err("ERR Invalid stream ID specified as stream command argument")
+}
+
+// "ms-seq", or just "ms" with the sequence number defaulting to `missing_seq`
+fn parse_id(value: &str, missing_seq: u64) -> Option<StreamId> {
+    match value.split_once('-') {
+        Some((ms, seq)) => Some(StreamId::new(ms.parse().ok()?, seq.parse().ok()?)),
+        None => Some(StreamId::new(value.parse().ok()?, missing_seq)),
+    }
+}
+
+/*
+ * an end of an XRANGE interval: "-", "+", or an ID, which may be incomplete
+ * and/or prefixed with "(" to exclude it
+ */
+fn parse_range_bound(value: &str, is_start: bool) -> Result<StreamId, Vec<u8>> {
+    let missing_seq = if is_start { 0 } else { u64::MAX };
+
+    let exclusive = match value.strip_prefix('(') {
+        Some(id) if value.len() > 1 => id,
+        _ => {
+            return match value {
+                "-" => Ok(StreamId::MIN),
+                "+" => Ok(StreamId::MAX),
+                _ => parse_id(value, missing_seq).ok_or_else(invalid_id),
+            }
+        }
+    };
+
+    let id = parse_id(exclusive, missing_seq).ok_or_else(invalid_id)?;
+
+    let id = if is_start { id.next() } else { id.prev() };
+
+    id.ok_or_else(|| match is_start {
+        true => serializer::err("ERR invalid start ID for the interval"),
+        false => serializer::err("ERR invalid end ID for the interval"),
+    })
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+fn no_group(key: &str, group: &str) -> Vec<u8> {
+    let msg = format!(
+        "NOGROUP No such key '{}' or consumer group '{}'",
+        key, group
+    );
+    serializer::err(&msg)
+}
+
+fn fields_reply(fields: &Fields) -> Vec<u8> {
+    let items: Vec<String> = fields
+        .iter()
+        .flat_map(|(field, value)| [field.to_string(), value.to_string()])
+        .collect();
+
+    serializer::str_arr(&items)
+}
+
+// an entry that was deleted since it was delivered has no fields
+fn entry_reply(id: StreamId, fields: Option<&Fields>) -> Vec<u8> {
+    let fields = match fields {
+        Some(fields) => fields_reply(fields),
+        None => serializer::null_arr(),
+    };
+
+    serializer::arr(&[serializer::bulk_str(&id.to_string()), fields])
+}
+
+fn entries_reply(entries: &[Entry]) -> Vec<u8> {
+    let entries: Vec<Vec<u8>> = entries
+        .iter()
+        .map(|entry| entry_reply(entry.id, Some(&entry.fields)))
+        .collect();
+
+    serializer::arr(&entries)
+}
+
+fn ids_reply(ids: &[StreamId]) -> Vec<u8> {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    serializer::str_arr(&ids)
+}
+
+/* ---- Trimming ---- */
+
+#[derive(Debug, Default)]
+struct TrimArgs {
+    strategy: Option<TrimStrategy>,
+    approx: bool,
+    limit: Option<usize>,
+}
+
+/*
+ * parses the trimming option at `args[i]` (`MAXLEN|MINID [=|~] threshold` or
+ * `LIMIT count`), returning the position of the next argument. `None` means
+ * `args[i]` is not a trimming option
+ */
+fn parse_trim_option(
+    args: &[String],
+    i: usize,
+    trim: &mut TrimArgs,
+) -> Result<Option<usize>, Vec<u8>> {
+    let option = args[i].to_uppercase();
+
+    if option == "LIMIT" {
+        let limit = match args.get(i + 1).map(|limit| limit.parse::<i64>()) {
+            Some(Ok(limit)) if limit >= 0 => limit as usize,
+            Some(Ok(_)) => return Err(serializer::err("ERR The LIMIT argument must be >= 0.")),
+            Some(Err(_)) => return Err(not_an_integer()),
+            None => return Err(syntax_error()),
+        };
+
+        trim.limit = Some(limit);
+        return Ok(Some(i + 2));
+    }
+
+    if option != "MAXLEN" && option != "MINID" {
+        return Ok(None);
+    }
+
+    if trim.strategy.is_some() {
+        return Err(serializer::err(
+            "ERR syntax error, MAXLEN and MINID options at the same time are not compatible",
+        ));
+    }
+
+    let mut i = i + 1;
+
+    match args.get(i).map(|modifier| modifier.as_str()) {
+        Some("~") => {
+            trim.approx = true;
+            i += 1;
+        }
+        Some("=") => i += 1,
+        _ => {}
+    }
+
+    let threshold = match args.get(i) {
+        Some(threshold) => threshold,
+        None => return Err(syntax_error()),
+    };
+
+    trim.strategy = Some(if option == "MAXLEN" {
+        match threshold.parse::<i64>() {
+            Ok(max) if max >= 0 => TrimStrategy::MaxLen(max as usize),
+            Ok(_) => return Err(serializer::err("ERR The MAXLEN argument must be >= 0.")),
+            Err(_) => return Err(not_an_integer()),
+        }
+    } else {
+        TrimStrategy::MinId(parse_id(threshold, 0).ok_or_else(invalid_id)?)
+    });
+
+    Ok(Some(i + 1))
+}
+
+fn build_trim(args: TrimArgs) -> Result<Option<Trim>, Vec<u8>> {
+    if args.limit.is_some() && !args.approx {
+        return Err(serializer::err(
+            "ERR syntax error, LIMIT cannot be used without the special ~ option",
+        ));
+    }
+
+    let limit = match args.limit {
+        Some(limit) => limit,
+        None if args.approx => APPROX_TRIM_LIMIT,
+        None => 0,
+    };
+
+    Ok(args.strategy.map(|strategy| Trim {
+        strategy,
+        approx: args.approx,
+        limit,
+    }))
+}
+
+/* ---- Commands ---- */
+
+struct XAddArgs {
+    nomkstream: bool,
+    trim: Option<Trim>,
+    id: IdSpec,
+    fields: Fields,
+}
+
+// parses `[NOMKSTREAM] [MAXLEN|MINID [=|~] threshold [LIMIT count]] id field value ...`
+fn parse_xadd_args(args: &[String]) -> Result<XAddArgs, Vec<u8>> {
+    let mut nomkstream = false;
+    let mut trim = TrimArgs::default();
+    let mut i = 0;
+
+    while i < args.len() {
+        if args[i].eq_ignore_ascii_case("NOMKSTREAM") {
+            nomkstream = true;
+            i += 1;
+            continue;
+        }
+
+        match parse_trim_option(args, i, &mut trim)? {
+            Some(next) => i = next,
+            None => break,
+        }
+    }
+
+    let rest = &args[i..];
+
+    if rest.len() < 3 || rest.len().is_multiple_of(2) {
+        return Err(wrong_args("xadd"));
+    }
+
+    let id = match rest[0].as_str() {
+        "*" => IdSpec::Auto,
+        id => match id.strip_suffix("-*") {
+            Some(ms) => IdSpec::AutoSeq(ms.parse().map_err(|_| invalid_id())?),
+            None => IdSpec::Explicit(parse_id(id, 0).ok_or_else(invalid_id)?),
+        },
+    };
+
+    if let IdSpec::Explicit(StreamId::MIN) = id {
+        return Err(serializer::err(
+            "ERR The ID specified in XADD must be greater than 0-0",
+        ));
+    }
+
+    let fields = rest[1..]
+        .chunks(2)
+        .map(|pair| (pair[0].to_string(), pair[1].to_string()))
+        .collect();
+
+    Ok(XAddArgs {
+        nomkstream,
+        trim: build_trim(trim)?,
+        id,
+        fields,
+    })
+}
+
+pub fn handle_xadd(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 3 {
+        return wrong_args("xadd");
+    }
+
+    let args = match parse_xadd_args(&query.args) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| {
+        let stream = match db.get_stream_mut(key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) if args.nomkstream => return serializer::nil(),
+            Ok(None) => db.get_or_create_stream(key).unwrap(),
+            Err(_) => return wrong_type(),
+        };
+
+        let id = match stream.next_id(args.id, now_ms()) {
+            Ok(id) => id,
+            Err(IdErr::TooSmall) => return serializer::err(
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item",
+            ),
+            Err(IdErr::Exhausted) => {
+                return serializer::err(
+                    "ERR The stream has exhausted the last possible ID, unable to add more items",
+                )
+            }
+        };
+
+        stream.add(id, &args.fields);
+
+        if let Some(trim) = &args.trim {
+            stream.trim(trim);
+        }
+
+        // readers blocked in XREAD are only ever woken up by new entries
+        db.signal_key_ready(key);
+
+        serializer::bulk_str(&id.to_string())
+    })
+}
+
+// XRANGE & XREVRANGE
+pub fn handle_xrange(query: &Query, command: &str, reverse: bool) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || (query.args.len() != 2 && query.args.len() != 4) {
+        return wrong_args(command);
+    }
+
+    // XREVRANGE takes `end start`
+    let (start, end) = if reverse {
+        (&query.args[1], &query.args[0])
+    } else {
+        (&query.args[0], &query.args[1])
+    };
+
+    let (start, end) = match (
+        parse_range_bound(start, true),
+        parse_range_bound(end, false),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    let count = match &query.args[2..] {
+        [] => None,
+        [option, count] if option.eq_ignore_ascii_case("COUNT") => match count.parse::<i64>() {
+            Ok(count) => Some(count.max(0) as usize),
+            Err(_) => return not_an_integer(),
+        },
+        _ => return syntax_error(),
+    };
+
+    if count == Some(0) {
+        return serializer::null_arr();
+    }
+
+    db.transaction(|db| match db.get_stream(key) {
+        Ok(Some(stream)) => entries_reply(&stream.range(start, end, count, reverse)),
+        Ok(None) => serializer::str_arr(&[]),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_xlen(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || !query.args.is_empty() {
+        return wrong_args("xlen");
+    }
+
+    db.transaction(|db| match db.get_stream(key) {
+        Ok(stream) => serializer::int(stream.map_or(0, |stream| stream.len()) as i64),
+        Err(_) => wrong_type(),
+    })
+}
+
+pub fn handle_xdel(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("xdel");
+    }
+
+    let ids: Option<Vec<StreamId>> = query.args.iter().map(|id| parse_id(id, 0)).collect();
+
+    let ids = match ids {
+        Some(ids) => ids,
+        None => return invalid_id(),
+    };
+
+    db.transaction(|db| {
+        let stream = match db.get_stream_mut(key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let deleted = ids.iter().filter(|id| stream.remove(**id)).count();
+        serializer::int(deleted as i64)
+    })
+}
+
+pub fn handle_xtrim(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 2 {
+        return wrong_args("xtrim");
+    }
+
+    let mut trim = TrimArgs::default();
+    let mut i = 0;
+
+    while i < query.args.len() {
+        match parse_trim_option(&query.args, i, &mut trim) {
+            Ok(Some(next)) => i = next,
+            Ok(None) => return syntax_error(),
+            Err(err) => return err,
+        }
+    }
+
+    let trim = match build_trim(trim) {
+        Ok(Some(trim)) => trim,
+        Ok(None) => return syntax_error(),
+        Err(err) => return err,
+    };
+
+    db.transaction(|db| match db.get_stream_mut(key) {
+        Ok(Some(stream)) => serializer::int(stream.trim(&trim) as i64),
+        Ok(None) => serializer::int(0),
+        Err(_) => wrong_type(),
+    })
+}
+
+// XSETID key last-id [ENTRIESADDED entries-added] [MAXDELETEDID max-deleted-id]
+pub fn handle_xsetid(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("xsetid");
+    }
+
+    let id = match parse_id(&query.args[0], 0) {
+        Some(id) => id,
+        None => return invalid_id(),
+    };
+
+    let mut entries_added = None;
+    let mut max_deleted_id = None;
+    let mut options = query.args[1..].iter();
+
+    while let Some(option) = options.next() {
+        let value = match options.next() {
+            Some(value) => value,
+            None => return syntax_error(),
+        };
+
+        match option.to_uppercase().as_str() {
+            "ENTRIESADDED" => match value.parse::<i64>() {
+                Ok(value) if value >= 0 => entries_added = Some(value as u64),
+                Ok(_) => return serializer::err("ERR entries_added must be positive"),
+                Err(_) => return not_an_integer(),
+            },
+            "MAXDELETEDID" => match parse_id(value, 0) {
+                Some(value) => max_deleted_id = Some(value),
+                None => return invalid_id(),
+            },
+            _ => return syntax_error(),
+        }
+    }
+
+    if max_deleted_id.is_some_and(|max_deleted_id| max_deleted_id > id) {
+        return serializer::err(
+            "ERR The ID specified in XSETID is smaller than the provided max_deleted_entry_id",
+        );
+    }
+
+    db.transaction(|db| {
+        let stream = match db.get_stream_mut(key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return serializer::err("ERR no such key"),
+            Err(_) => return wrong_type(),
+        };
+
+        if !stream.is_empty() {
+            let last = stream.last_entry().unwrap();
+
+            if id < last.id {
+                return serializer::err(
+                    "ERR The ID specified in XSETID is smaller than the target stream top item",
+                );
+            }
+
+            if entries_added.is_some_and(|added| added < stream.len() as u64) {
+                return serializer::err(
+                    "ERR The entries_added specified in XSETID is smaller than the target stream length",
+                );
+            }
+        }
+
+        stream.set_last_id(id);
+
+        if let Some(entries_added) = entries_added {
+            stream.set_entries_added(entries_added);
+        }
+
+        if let Some(max_deleted_id) = max_deleted_id {
+            stream.set_max_deleted_id(max_deleted_id);
+        }
+
+        serializer::str("OK")
+    })
+}
+
+/* ---- Reading ---- */
+
+#[derive(Debug, Clone)]
+struct ReadOpts {
+    count: Option<usize>,
+    group: Option<(String, String)>, // group and consumer, for XREADGROUP
+    noack: bool,
+}
+
+#[derive(Debug)]
+struct XReadArgs {
+    opts: ReadOpts,
+    block: Option<Option<Duration>>, // `Some(None)` blocks forever
+    keys: Vec<String>,
+    ids: Vec<String>,
+}
+
+// BLOCK takes milliseconds, 0 meaning forever
+fn parse_block(value: &str) -> Result<Option<Duration>, Vec<u8>> {
+    match value.parse::<i64>() {
+        Ok(0) => Ok(None),
+        Ok(ms) if ms > 0 => Ok(Some(Duration::from_millis(ms as u64))),
+        Ok(_) => Err(serializer::err("ERR timeout is negative")),
+        Err(_) => Err(serializer::err(
+            "ERR timeout is not an integer or out of range",
+        )),
+    }
+}
+
+/*
+ * parses `[GROUP group consumer] [COUNT count] [BLOCK milliseconds] [NOACK]
+ * STREAMS key [key ...] id [id ...]`, GROUP and NOACK being only valid for
+ * XREADGROUP
+ */
+fn parse_xread_args(args: &[String], command: &str) -> Result<XReadArgs, Vec<u8>> {
+    let is_group = command == "xreadgroup";
+
+    let mut opts = ReadOpts {
+        count: None,
+        group: None,
+        noack: false,
+    };
+    let mut block = None;
+    let mut i = 0;
+
+    loop {
+        let option = match args.get(i) {
+            Some(option) => option.to_uppercase(),
+            None => return Err(syntax_error()),
+        };
+        let more_args = args.len() - i - 1;
+
+        match option.as_str() {
+            "STREAMS" if more_args > 0 => break,
+            "COUNT" if more_args > 0 => {
+                opts.count = match args[i + 1].parse::<i64>() {
+                    // a count of 0 is no count at all
+                    Ok(count) if count > 0 => Some(count as usize),
+                    Ok(_) => None,
+                    Err(_) => return Err(not_an_integer()),
+                };
+                i += 2;
+            }
+            "BLOCK" if more_args > 0 => {
+                block = Some(parse_block(&args[i + 1])?);
+                i += 2;
+            }
+            "GROUP" if more_args > 1 => {
+                if !is_group {
+                    return Err(serializer::err(
+                        "ERR The GROUP option is only supported by XREADGROUP. You called XREAD instead.",
+                    ));
+                }
+
+                opts.group = Some((args[i + 1].to_string(), args[i + 2].to_string()));
+                i += 3;
+            }
+            "NOACK" => {
+                if !is_group {
+                    return Err(serializer::err(
+                        "ERR The NOACK option is only supported by XREADGROUP. You called XREAD instead.",
+                    ));
+                }
+
+                opts.noack = true;
+                i += 1;
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+
+    if is_group && opts.group.is_none() {
+        return Err(serializer::err("ERR Missing GROUP option for XREADGROUP"));
+    }
+
+    let streams = &args[i + 1..];
+
+    if !streams.len().is_multiple_of(2) {
+        let msg = format!(
+            "ERR Unbalanced '{}' list of streams: for each stream key an ID or '$' must be specified.",
+            command
+        );
+        return Err(serializer::err(&msg));
+    }
+
+    let (keys, ids) = streams.split_at(streams.len() / 2);
+
+    Ok(XReadArgs {
+        opts,
+        block,
+        keys: keys.to_vec(),
+        ids: ids.to_vec(),
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ReadFrom {
+    After(StreamId),
+    New, // ">": what was not delivered to the group yet
+}
+
+/*
+ * turns the IDs given to XREAD into where to read from: "$" is the last ID of
+ * the stream, and "+" its last entry, which behaves as "$" when there is none
+ */
+fn resolve_ids(db: &Database, args: &XReadArgs) -> Result<Vec<ReadFrom>, Vec<u8>> {
+    let mut resolved = vec![];
+
+    for (key, id) in args.keys.iter().zip(&args.ids) {
+        let stream = db.get_stream(key).map_err(|_| wrong_type())?;
+
+        if let Some((group, _)) = &args.opts.group {
+            if stream.and_then(|stream| stream.group(group)).is_none() {
+                let msg = format!(
+                    "NOGROUP No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                    key, group
+                );
+                return Err(serializer::err(&msg));
+            }
+        }
+
+        let last_id = stream.map_or(StreamId::MIN, |stream| stream.last_id());
+
+        let from = match (id.as_str(), args.opts.group.is_some()) {
+            ("$", true) => {
+                return Err(serializer::err(
+                    "ERR The $ ID is meaningless in the context of XREADGROUP: you want to read the history of this consumer by specifying a proper ID, or use the > ID to get new messages. The $ ID would just return an empty result set.",
+                ))
+            }
+            (">", false) => {
+                return Err(serializer::err(
+                    "ERR The > ID can be specified only when calling XREADGROUP using the GROUP <group> <consumer> option.",
+                ))
+            }
+            ("$", false) => ReadFrom::After(last_id),
+            (">", true) => ReadFrom::New,
+            ("+", false) => match stream.and_then(|stream| stream.last_entry()) {
+                // 0-0 is never the ID of an entry, so `prev` can not fail
+                Some(last) => ReadFrom::After(last.id.prev().unwrap()),
+                None => ReadFrom::After(last_id),
+            },
+            (id, _) => ReadFrom::After(parse_id(id, 0).ok_or_else(invalid_id)?),
+        };
+
+        resolved.push(from);
+    }
+
+    Ok(resolved)
+}
+
+// up to `count` entries with an ID greater than `after`
+fn read_after(stream: &Stream, after: StreamId, count: Option<usize>) -> Vec<Entry> {
+    match after.next() {
+        Some(start) => stream.range(start, StreamId::MAX, count, false),
+        None => vec![],
+    }
+}
+
+/*
+ * the part of the XREAD reply for `key`. `None` when there is nothing to
+ * reply for it, as is the case for a stream with no new entries. a consumer
+ * reading its history gets a reply even if it has no pending entries
+ */
+fn read_stream(db: &mut Database, key: &str, from: ReadFrom, opts: &ReadOpts) -> Option<Vec<u8>> {
+    let stream = db.get_stream_mut(key).ok()??;
+
+    let group = match &opts.group {
+        Some((group, consumer)) => {
+            stream.group_mut(group)?.see_consumer(consumer, now_ms());
+            Some((group, consumer))
+        }
+        None => None,
+    };
+
+    let entries = match (from, group) {
+        (ReadFrom::After(after), None) => read_after(stream, after, opts.count),
+        (ReadFrom::New, Some((group, consumer))) => {
+            stream.read_group(group, consumer, opts.count, opts.noack, now_ms())
+        }
+        (ReadFrom::After(after), Some((group, consumer))) => {
+            let entries = stream.read_pending(group, consumer, after, opts.count, now_ms());
+
+            let entries: Vec<Vec<u8>> = entries
+                .iter()
+                .map(|(id, fields)| entry_reply(*id, fields.as_ref()))
+                .collect();
+
+            return Some(serializer::arr(&[
+                serializer::bulk_str(key),
+                serializer::arr(&entries),
+            ]));
+        }
+        (ReadFrom::New, None) => return None,
+    };
+
+    if entries.is_empty() {
+        return None;
+    }
+
+    Some(serializer::arr(&[
+        serializer::bulk_str(key),
+        entries_reply(&entries),
+    ]))
+}
+
+fn xread(db: &mut Database, client: &Client, args: XReadArgs) -> Attempt {
+    let from = match resolve_ids(db, &args) {
+        Ok(from) => from,
+        Err(err) => return Attempt::Reply(err),
+    };
+
+    let streams: Vec<Vec<u8>> = args
+        .keys
+        .iter()
+        .zip(&from)
+        .filter_map(|(key, from)| read_stream(db, key, *from, &args.opts))
+        .collect();
+
+    if !streams.is_empty() {
+        return Attempt::Reply(serializer::arr(&streams));
+    }
+
+    if args.block.is_none() {
+        return Attempt::Reply(serializer::null_arr());
+    }
+
+    /*
+     * the IDs were resolved now, so "$" keeps meaning "what comes after the
+     * last entry at the time XREAD was called"
+     */
+    let from: HashMap<String, ReadFrom> = args.keys.iter().cloned().zip(from).collect();
+    let timeout = args.block.flatten();
+    let opts = args.opts;
+
+    let serve = move |db: &mut Database, key: &str| {
+        if let Some((group, _)) = &opts.group {
+            // the key may have been created with another type
+            let stream = db.get_stream(key).ok()??;
+
+            if stream.group(group).is_none() {
+                return Some(serializer::err(
+                    "NOGROUP the consumer group this client was blocked on no longer exists",
+                ));
+            }
+        }
+
+        let reply = read_stream(db, key, from[key], &opts)?;
+        Some(serializer::arr(&[reply]))
+    };
+
+    blocking::block(db, client, &args.keys, timeout, Box::new(serve));
+    Attempt::Blocked
+}
+
+// XREAD & XREADGROUP
+pub fn handle_xread(query: &Query, client: &Client, command: &str) -> Vec<u8> {
+    let mut db = DatabaseOps;
+
+    if query.command_str.is_empty() {
+        return wrong_args(command);
+    }
+
+    let mut args = vec![query.command_str.to_string()];
+    args.extend(query.args.iter().cloned());
+
+    let args = match parse_xread_args(&args, command) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+
+    match db.transaction(|db| xread(db, client, args)) {
+        Attempt::Reply(reply) => reply,
+        Attempt::Blocked => vec![],
+    }
+}
+
+/* ---- Consumer groups ---- */
+
+// ENTRIESREAD of XGROUP CREATE and SETID, -1 meaning unknown
+fn parse_entries_read(args: &[String]) -> Result<Option<u64>, Vec<u8>> {
+    match args {
+        [] => Ok(None),
+        [option, value] if option.eq_ignore_ascii_case("ENTRIESREAD") => {
+            match value.parse::<i64>() {
+                Ok(-1) => Ok(None),
+                Ok(value) if value >= 0 => Ok(Some(value as u64)),
+                Ok(_) => Err(serializer::err(
+                    "ERR value for ENTRIESREAD must be positive or -1",
+                )),
+                Err(_) => Err(not_an_integer()),
+            }
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+// the ID a group starts from. "$" is the last ID of the stream
+fn parse_group_id(value: &str, stream: Option<&Stream>) -> Result<StreamId, Vec<u8>> {
+    match value {
+        "$" => Ok(stream.map_or(StreamId::MIN, |stream| stream.last_id())),
+        id => parse_id(id, 0).ok_or_else(invalid_id),
+    }
+}
+
+fn no_such_key() -> Vec<u8> {
+    serializer::err("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.")
+}
+
+fn no_such_group(key: &str, group: &str) -> Vec<u8> {
+    let msg = format!(
+        "NOGROUP No such consumer group '{}' for key name '{}'",
+        group, key
+    );
+    serializer::err(&msg)
+}
+
+fn xgroup_create(db: &mut Database, args: &[String]) -> Vec<u8> {
+    let (key, group) = (&args[0], &args[1]);
+    let mut mkstream = false;
+    let mut options = &args[3..];
+
+    if let Some(option) = options.first() {
+        if option.eq_ignore_ascii_case("MKSTREAM") {
+            mkstream = true;
+            options = &options[1..];
+        }
+    }
+
+    let entries_read = match parse_entries_read(options) {
+        Ok(entries_read) => entries_read,
+        Err(err) => return err,
+    };
+
+    let stream = match db.get_stream(key) {
+        Ok(stream) => stream,
+        Err(_) => return wrong_type(),
+    };
+
+    let id = match parse_group_id(&args[2], stream) {
+        Ok(id) => id,
+        Err(err) => return err,
+    };
+
+    let stream = match db.get_stream_mut(key) {
+        Ok(Some(stream)) => stream,
+        Ok(None) if mkstream => db.get_or_create_stream(key).unwrap(),
+        _ => return no_such_key(),
+    };
+
+    if !stream.create_group(group, id, entries_read) {
+        return serializer::err("BUSYGROUP Consumer Group name already exists");
+    }
+
+    serializer::str("OK")
+}
+
+// XGROUP SETID, DESTROY, CREATECONSUMER & DELCONSUMER
+fn xgroup_update(db: &mut Database, subcommand: &str, args: &[String]) -> Vec<u8> {
+    let (key, group) = (&args[0], &args[1]);
+
+    let stream = match db.get_stream_mut(key) {
+        Ok(Some(stream)) => stream,
+        Ok(None) => return no_such_key(),
+        Err(_) => return wrong_type(),
+    };
+
+    if subcommand == "destroy" {
+        return serializer::int(stream.destroy_group(group) as i64);
+    }
+
+    let id = match subcommand {
+        "setid" => match parse_group_id(&args[2], Some(stream)) {
+            Ok(id) => Some(id),
+            Err(err) => return err,
+        },
+        _ => None,
+    };
+
+    let group = match stream.group_mut(group) {
+        Some(group) => group,
+        None => return no_such_group(key, group),
+    };
+
+    match subcommand {
+        "setid" => match parse_entries_read(&args[3..]) {
+            Ok(entries_read) => {
+                group.set_last_id(id.unwrap(), entries_read);
+                serializer::str("OK")
+            }
+            Err(err) => err,
+        },
+        "createconsumer" => serializer::int(group.create_consumer(&args[2], now_ms()) as i64),
+        _ => serializer::int(group.delete_consumer(&args[2]).unwrap_or(0) as i64),
+    }
+}
+
+pub fn handle_xgroup(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let subcommand = query.command_str.to_lowercase();
+    let args = &query.args;
+
+    if subcommand.is_empty() {
+        return wrong_args("xgroup");
+    }
+
+    let arity_ok = match subcommand.as_str() {
+        "create" => (3..=6).contains(&args.len()),
+        "setid" => (3..=5).contains(&args.len()),
+        "destroy" => args.len() == 2,
+        "createconsumer" | "delconsumer" => args.len() == 3,
+        _ => {
+            let msg = format!(
+                "ERR unknown subcommand '{}'. Try XGROUP HELP.",
+                query.command_str
+            );
+            return serializer::err(&msg);
+        }
+    };
+
+    if !arity_ok {
+        return wrong_args(&format!("xgroup|{}", subcommand));
+    }
+
+    db.transaction(|db| match subcommand.as_str() {
+        "create" => xgroup_create(db, args),
+        subcommand => xgroup_update(db, subcommand, args),
+    })
+}
+
+// XREADGROUP GROUP group consumer ... STREAMS key ... id ...
+pub fn handle_xreadgroup(query: &Query, client: &Client) -> Vec<u8> {
+    handle_xread(query, client, "xreadgroup")
+}
+
+pub fn handle_xack(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 2 {
+        return wrong_args("xack");
+    }
+
+    let group = &query.args[0];
+    let ids: Option<Vec<StreamId>> = query.args[1..].iter().map(|id| parse_id(id, 0)).collect();
+
+    let ids = match ids {
+        Some(ids) => ids,
+        None => return invalid_id(),
+    };
+
+    db.transaction(|db| {
+        let group = match db.get_stream_mut(key) {
+            Ok(Some(stream)) => match stream.group_mut(group) {
+                Some(group) => group,
+                None => return serializer::int(0),
+            },
+            Ok(None) => return serializer::int(0),
+            Err(_) => return wrong_type(),
+        };
+
+        let acked = ids.iter().filter(|id| group.ack(**id)).count();
+        serializer::int(acked as i64)
+    })
+}
+
+/* ---- Pending entries ---- */
+
+#[derive(Debug)]
+struct PendingRange {
+    min_idle: u64,
+    start: StreamId,
+    end: StreamId,
+    count: usize,
+    consumer: Option<String>,
+}
+
+// parses `[IDLE min-idle-time] start end count [consumer]`
+fn parse_pending_range(args: &[String]) -> Result<PendingRange, Vec<u8>> {
+    let (min_idle, args) = match args.first() {
+        Some(option) if option.eq_ignore_ascii_case("IDLE") && args.len() >= 4 => {
+            match args[1].parse::<i64>() {
+                Ok(min_idle) => (min_idle.max(0) as u64, &args[2..]),
+                Err(_) => return Err(not_an_integer()),
+            }
+        }
+        _ => (0, args),
+    };
+
+    if args.len() != 3 && args.len() != 4 {
+        return Err(syntax_error());
+    }
+
+    let start = parse_range_bound(&args[0], true)?;
+    let end = parse_range_bound(&args[1], false)?;
+
+    let count = match args[2].parse::<i64>() {
+        Ok(count) => count.max(0) as usize,
+        Err(_) => return Err(not_an_integer()),
+    };
+
+    Ok(PendingRange {
+        min_idle,
+        start,
+        end,
+        count,
+        consumer: args.get(3).cloned(),
+    })
+}
+
+fn pending_summary(group: &ConsumerGroup) -> Vec<u8> {
+    let pending = group.pending();
+
+    let (first, last) = match (pending.keys().next(), pending.keys().next_back()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return serializer::arr(&[
+                serializer::int(0),
+                serializer::nil(),
+                serializer::nil(),
+                serializer::null_arr(),
+            ])
+        }
+    };
+
+    let consumers: Vec<Vec<u8>> = group
+        .consumers()
+        .iter()
+        .filter(|(_, consumer)| !consumer.pending().is_empty())
+        .map(|(name, consumer)| {
+            serializer::str_arr(&[name.to_string(), consumer.pending().len().to_string()])
+        })
+        .collect();
+
+    serializer::arr(&[
+        serializer::int(pending.len() as i64),
+        serializer::bulk_str(&first.to_string()),
+        serializer::bulk_str(&last.to_string()),
+        serializer::arr(&consumers),
+    ])
+}
+
+fn pending_details(group: &ConsumerGroup, range: &PendingRange) -> Vec<u8> {
+    if range.start > range.end {
+        return serializer::arr(&[]);
+    }
+
+    let now = now_ms();
+
+    let entries: Vec<Vec<u8>> = group
+        .pending()
+        .range(range.start..=range.end)
+        .filter(|(_, entry)| {
+            range
+                .consumer
+                .as_ref()
+                .is_none_or(|consumer| entry.consumer == *consumer)
+        })
+        .map(|(id, entry)| (id, entry, now.saturating_sub(entry.delivery_time)))
+        .filter(|(_, _, idle)| *idle >= range.min_idle)
+        .take(range.count)
+        .map(|(id, entry, idle)| {
+            serializer::arr(&[
+                serializer::bulk_str(&id.to_string()),
+                serializer::bulk_str(&entry.consumer),
+                serializer::int(idle as i64),
+                serializer::int(entry.delivery_count as i64),
+            ])
+        })
+        .collect();
+
+    serializer::arr(&entries)
+}
+
+pub fn handle_xpending(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.is_empty() {
+        return wrong_args("xpending");
+    }
+
+    let group = &query.args[0];
+
+    let range = match query.args.len() {
+        1 => None,
+        _ => match parse_pending_range(&query.args[1..]) {
+            Ok(range) => Some(range),
+            Err(err) => return err,
+        },
+    };
+
+    db.transaction(|db| {
+        let group = match db.get_stream(key) {
+            Ok(stream) => match stream.and_then(|stream| stream.group(group)) {
+                Some(group) => group,
+                None => return no_group(key, group),
+            },
+            Err(_) => return wrong_type(),
+        };
+
+        match &range {
+            Some(range) => pending_details(group, range),
+            None => pending_summary(group),
+        }
+    })
+}
+
+/* ---- Claiming ---- */
+
+fn claimed_reply(stream: &Stream, ids: &[StreamId], justid: bool) -> Vec<u8> {
+    if justid {
+        return ids_reply(ids);
+    }
+
+    let entries: Vec<Entry> = ids.iter().filter_map(|id| stream.entry(*id)).collect();
+    entries_reply(&entries)
+}
+
+#[derive(Debug, Default)]
+struct XClaimOpts {
+    delivery_time: Option<i64>,
+    retry_count: Option<u64>,
+    force: bool,
+    justid: bool,
+    last_id: Option<StreamId>,
+}
+
+fn parse_xclaim_opts(args: &[String]) -> Result<XClaimOpts, Vec<u8>> {
+    let mut opts = XClaimOpts::default();
+    let now = now_ms() as i64;
+    let mut args = args.iter().peekable();
+
+    while let Some(option) = args.next() {
+        let has_value = args.peek().is_some();
+
+        match option.to_uppercase().as_str() {
+            "FORCE" => opts.force = true,
+            "JUSTID" => opts.justid = true,
+            "IDLE" if has_value => match args.next().unwrap().parse::<i64>() {
+                Ok(idle) => opts.delivery_time = Some(now - idle),
+                Err(_) => {
+                    return Err(serializer::err(
+                        "ERR Invalid IDLE option argument for XCLAIM",
+                    ))
+                }
+            },
+            "TIME" if has_value => match args.next().unwrap().parse::<i64>() {
+                Ok(time) => opts.delivery_time = Some(time),
+                Err(_) => {
+                    return Err(serializer::err(
+                        "ERR Invalid TIME option argument for XCLAIM",
+                    ))
+                }
+            },
+            "RETRYCOUNT" if has_value => match args.next().unwrap().parse::<i64>() {
+                Ok(count) => opts.retry_count = u64::try_from(count).ok(),
+                Err(_) => {
+                    return Err(serializer::err(
+                        "ERR Invalid RETRYCOUNT option argument for XCLAIM",
+                    ))
+                }
+            },
+            "LASTID" if has_value => match parse_id(args.next().unwrap(), 0) {
+                Some(id) => opts.last_id = Some(id),
+                None => return Err(invalid_id()),
+            },
+            _ => {
+                let msg = format!("ERR Unrecognized XCLAIM option '{}'", option);
+                return Err(serializer::err(&msg));
+            }
+        }
+    }
+
+    Ok(opts)
+}
+
+fn parse_min_idle(value: &str, command: &str) -> Result<u64, Vec<u8>> {
+    match value.parse::<i64>() {
+        Ok(min_idle) => Ok(min_idle.max(0) as u64),
+        Err(_) => {
+            let msg = format!("ERR Invalid min-idle-time argument for {}", command);
+            Err(serializer::err(&msg))
+        }
+    }
+}
+
+// XCLAIM key group consumer min-idle-time id [id ...] [options]
+pub fn handle_xclaim(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 4 {
+        return wrong_args("xclaim");
+    }
+
+    let (group, consumer) = (&query.args[0], &query.args[1]);
+
+    db.transaction(|db| {
+        let stream = match db.get_stream_mut(key) {
+            Ok(Some(stream)) if stream.group(group).is_some() => stream,
+            Ok(_) => return no_group(key, group),
+            Err(_) => return wrong_type(),
+        };
+
+        let min_idle = match parse_min_idle(&query.args[2], "XCLAIM") {
+            Ok(min_idle) => min_idle,
+            Err(err) => return err,
+        };
+
+        // the IDs go on until the first argument that is not one
+        let ids: Vec<StreamId> = query.args[3..]
+            .iter()
+            .map_while(|id| parse_id(id, 0))
+            .collect();
+
+        let opts = match parse_xclaim_opts(&query.args[3 + ids.len()..]) {
+            Ok(opts) => opts,
+            Err(err) => return err,
+        };
+
+        let now = now_ms();
+
+        // a bogus delivery time is not worth an error
+        let delivery_time = match opts.delivery_time {
+            Some(time) if time >= 0 && time as u64 <= now => time as u64,
+            _ => now,
+        };
+
+        let exists: Vec<bool> = ids.iter().map(|id| stream.entry(*id).is_some()).collect();
+        let group = stream.group_mut(group).unwrap();
+
+        if let Some(last_id) = opts.last_id {
+            if last_id > group.last_id() {
+                group.set_last_id(last_id, group.entries_read());
+            }
+        }
+
+        let mut claimed = vec![];
+
+        for (id, exists) in ids.iter().zip(exists) {
+            // the entry was deleted, there is nothing left to process
+            if !exists {
+                group.ack(*id);
+                continue;
+            }
+
+            let (idle, delivery_count) = match group.pending().get(id) {
+                Some(entry) => (
+                    now.saturating_sub(entry.delivery_time),
+                    entry.delivery_count,
+                ),
+                None if opts.force => (now, 1),
+                None => continue,
+            };
+
+            if idle < min_idle {
+                continue;
+            }
+
+            let delivery_count = match opts.retry_count {
+                Some(count) => count,
+                None if opts.justid => delivery_count,
+                None => delivery_count + 1,
+            };
+
+            group.claim(*id, consumer, delivery_time, delivery_count, now);
+            claimed.push(*id);
+        }
+
+        claimed_reply(stream, &claimed, opts.justid)
+    })
+}
+
+// how many PEL entries XAUTOCLAIM looks at for every entry it may claim
+const AUTOCLAIM_ATTEMPTS_FACTOR: usize = 10;
+
+// XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+pub fn handle_xautoclaim(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let key = &query.command_str;
+
+    if key.is_empty() || query.args.len() < 4 {
+        return wrong_args("xautoclaim");
+    }
+
+    let (group, consumer) = (&query.args[0], &query.args[1]);
+
+    let min_idle = match parse_min_idle(&query.args[2], "XAUTOCLAIM") {
+        Ok(min_idle) => min_idle,
+        Err(err) => return err,
+    };
+
+    let start = match parse_range_bound(&query.args[3], true) {
+        Ok(start) => start,
+        Err(err) => return err,
+    };
+
+    let mut count = 100;
+    let mut justid = false;
+    let mut options = query.args[4..].iter();
+
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "COUNT" => {
+                count = match options.next().map(|count| count.parse::<i64>()) {
+                    // capped so that `count * AUTOCLAIM_ATTEMPTS_FACTOR` can not overflow
+                    Some(Ok(count)) if (1..=i64::MAX / 16).contains(&count) => count as usize,
+                    Some(_) => return serializer::err("ERR COUNT must be > 0"),
+                    None => return syntax_error(),
+                };
+            }
+            "JUSTID" => justid = true,
+            _ => return syntax_error(),
+        }
+    }
+
+    db.transaction(|db| {
+        let stream = match db.get_stream_mut(key) {
+            Ok(Some(stream)) if stream.group(group).is_some() => stream,
+            Ok(_) => return no_group(key, group),
+            Err(_) => return wrong_type(),
+        };
+
+        let now = now_ms();
+        let attempts = count * AUTOCLAIM_ATTEMPTS_FACTOR;
+
+        let scanned: Vec<(StreamId, bool)> = stream
+            .group(group)
+            .unwrap()
+            .pending()
+            .range(start..)
+            .take(attempts)
+            .map(|(id, _)| (*id, stream.entry(*id).is_some()))
+            .collect();
+
+        let group = stream.group_mut(group).unwrap();
+        let mut claimed = vec![];
+        let mut deleted = vec![];
+        let mut last_scanned = None;
+
+        for (id, exists) in scanned {
+            if claimed.len() + deleted.len() >= count {
+                break;
+            }
+
+            last_scanned = Some(id);
+
+            // the entry was deleted, it can not be processed anymore
+            if !exists {
+                group.ack(id);
+                deleted.push(id);
+                continue;
+            }
+
+            let entry = &group.pending()[&id];
+
+            if now.saturating_sub(entry.delivery_time) < min_idle {
+                continue;
+            }
+
+            let delivery_count = match justid {
+                true => entry.delivery_count,
+                false => entry.delivery_count + 1,
+            };
+
+            group.claim(id, consumer, now, delivery_count, now);
+            claimed.push(id);
+        }
+
+        // the next call starts after the last entry looked at, 0-0 once done
+        let next = last_scanned
+            .and_then(|id| id.next())
+            .and_then(|start| group.pending().range(start..).next())
+            .map_or(StreamId::MIN, |(id, _)| *id);
+
+        serializer::arr(&[
+            serializer::bulk_str(&next.to_string()),
+            claimed_reply(stream, &claimed, justid),
+            ids_reply(&deleted),
+        ])
+    })
+}
+
+/* ---- Introspection ---- */
+
+// a map reply, which is a flat array of names and values in RESP2
+fn map_reply(pairs: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    let items: Vec<Vec<u8>> = pairs
+        .iter()
+        .flat_map(|(name, value)| [serializer::bulk_str(name), value.clone()])
+        .collect();
+
+    serializer::arr(&items)
+}
+
+fn id_reply(id: StreamId) -> Vec<u8> {
+    serializer::bulk_str(&id.to_string())
+}
+
+fn optional_int(value: Option<u64>) -> Vec<u8> {
+    match value {
+        Some(value) => serializer::int(value as i64),
+        None => serializer::nil(),
+    }
+}
+
+/*
+ * there is no radix tree here, the blocks being indexed by a `BTreeMap`, so
+ * both radix-tree-keys and radix-tree-nodes report the number of blocks
+ */
+fn stream_info_header(stream: &Stream) -> Vec<(&'static str, Vec<u8>)> {
+    let first_id = stream.first_entry().map_or(StreamId::MIN, |entry| entry.id);
+
+    vec![
+        ("length", serializer::int(stream.len() as i64)),
+        (
+            "radix-tree-keys",
+            serializer::int(stream.block_count() as i64),
+        ),
+        (
+            "radix-tree-nodes",
+            serializer::int(stream.block_count() as i64),
+        ),
+        ("last-generated-id", id_reply(stream.last_id())),
+        ("max-deleted-entry-id", id_reply(stream.max_deleted_id())),
+        (
+            "entries-added",
+            serializer::int(stream.entries_added() as i64),
+        ),
+        ("recorded-first-entry-id", id_reply(first_id)),
+    ]
+}
+
+fn stream_info(stream: &Stream) -> Vec<u8> {
+    let first = stream.first_entry();
+    let last = stream.last_entry();
+
+    let entry = |entry: Option<Entry>| match entry {
+        Some(entry) => entry_reply(entry.id, Some(&entry.fields)),
+        None => serializer::nil(),
+    };
+
+    let mut info = stream_info_header(stream);
+    info.push(("groups", serializer::int(stream.groups().count() as i64)));
+    info.push(("first-entry", entry(first)));
+    info.push(("last-entry", entry(last)));
+
+    map_reply(&info)
+}
+
+// XINFO STREAM key FULL, listing up to `count` entries of every kind
+fn stream_info_full(stream: &Stream, count: Option<usize>) -> Vec<u8> {
+    let limit = count.unwrap_or(usize::MAX);
+
+    let groups: Vec<Vec<u8>> = stream
+        .groups()
+        .map(|(name, group)| {
+            let pending: Vec<Vec<u8>> = group
+                .pending()
+                .iter()
+                .take(limit)
+                .map(|(id, entry)| {
+                    serializer::arr(&[
+                        id_reply(*id),
+                        serializer::bulk_str(&entry.consumer),
+                        serializer::int(entry.delivery_time as i64),
+                        serializer::int(entry.delivery_count as i64),
+                    ])
+                })
+                .collect();
+
+            let consumers: Vec<Vec<u8>> = group
+                .consumers()
+                .iter()
+                .map(|(name, consumer)| {
+                    let pending: Vec<Vec<u8>> = consumer
+                        .pending()
+                        .iter()
+                        .take(limit)
+                        .map(|id| {
+                            let entry = &group.pending()[id];
+
+                            serializer::arr(&[
+                                id_reply(*id),
+                                serializer::int(entry.delivery_time as i64),
+                                serializer::int(entry.delivery_count as i64),
+                            ])
+                        })
+                        .collect();
+
+                    let active_time = consumer.active_time().map_or(-1, |time| time as i64);
+
+                    map_reply(&[
+                        ("name", serializer::bulk_str(name)),
+                        ("seen-time", serializer::int(consumer.seen_time() as i64)),
+                        ("active-time", serializer::int(active_time)),
+                        (
+                            "pel-count",
+                            serializer::int(consumer.pending().len() as i64),
+                        ),
+                        ("pending", serializer::arr(&pending)),
+                    ])
+                })
+                .collect();
+
+            map_reply(&[
+                ("name", serializer::bulk_str(name)),
+                ("last-delivered-id", id_reply(group.last_id())),
+                ("entries-read", optional_int(group.entries_read())),
+                ("lag", optional_int(stream.lag(group))),
+                ("pel-count", serializer::int(group.pending().len() as i64)),
+                ("pending", serializer::arr(&pending)),
+                ("consumers", serializer::arr(&consumers)),
+            ])
+        })
+        .collect();
+
+    let entries = stream.range(StreamId::MIN, StreamId::MAX, count, false);
+
+    let mut info = stream_info_header(stream);
+    info.push(("entries", entries_reply(&entries)));
+    info.push(("groups", serializer::arr(&groups)));
+
+    map_reply(&info)
+}
+
+// parses `[FULL [COUNT count]]`. the outer `None` is for the short form
+fn parse_xinfo_stream_args(args: &[String]) -> Result<Option<Option<usize>>, Vec<u8>> {
+    match args {
+        [] => Ok(None),
+        [full] if full.eq_ignore_ascii_case("FULL") => Ok(Some(Some(10))),
+        [full, option, count]
+            if full.eq_ignore_ascii_case("FULL") && option.eq_ignore_ascii_case("COUNT") =>
+        {
+            match count.parse::<i64>() {
+                Ok(0) => Ok(Some(None)),
+                Ok(count) if count > 0 => Ok(Some(Some(count as usize))),
+                Ok(_) => Ok(Some(Some(10))),
+                Err(_) => Err(not_an_integer()),
+            }
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+fn groups_info(stream: &Stream) -> Vec<u8> {
+    let groups: Vec<Vec<u8>> = stream
+        .groups()
+        .map(|(name, group)| {
+            map_reply(&[
+                ("name", serializer::bulk_str(name)),
+                ("consumers", serializer::int(group.consumers().len() as i64)),
+                ("pending", serializer::int(group.pending().len() as i64)),
+                ("last-delivered-id", id_reply(group.last_id())),
+                ("entries-read", optional_int(group.entries_read())),
+                ("lag", optional_int(stream.lag(group))),
+            ])
+        })
+        .collect();
+
+    serializer::arr(&groups)
+}
+
+fn consumers_info(group: &ConsumerGroup) -> Vec<u8> {
+    let now = now_ms();
+
+    let consumers: Vec<Vec<u8>> = group
+        .consumers()
+        .iter()
+        .map(|(name, consumer)| {
+            // -1 for a consumer that was never handed any entry
+            let inactive = consumer
+                .active_time()
+                .map_or(-1, |time| now.saturating_sub(time) as i64);
+
+            map_reply(&[
+                ("name", serializer::bulk_str(name)),
+                ("pending", serializer::int(consumer.pending().len() as i64)),
+                (
+                    "idle",
+                    serializer::int(now.saturating_sub(consumer.seen_time()) as i64),
+                ),
+                ("inactive", serializer::int(inactive)),
+            ])
+        })
+        .collect();
+
+    serializer::arr(&consumers)
+}
+
+// XINFO STREAM|GROUPS|CONSUMERS
+pub fn handle_xinfo(query: &Query) -> Vec<u8> {
+    let mut db = DatabaseOps;
+    let subcommand = query.command_str.to_lowercase();
+    let args = &query.args;
+
+    if subcommand.is_empty() {
+        return wrong_args("xinfo");
+    }
+
+    let arity_ok = match subcommand.as_str() {
+        "stream" => !args.is_empty(),
+        "groups" => args.len() == 1,
+        "consumers" => args.len() == 2,
+        _ => {
+            let msg = format!(
+                "ERR unknown subcommand '{}'. Try XINFO HELP.",
+                query.command_str
+            );
+            return serializer::err(&msg);
+        }
+    };
+
+    if !arity_ok {
+        return wrong_args(&format!("xinfo|{}", subcommand));
+    }
+
+    let key = &args[0];
+
+    let full = match subcommand.as_str() {
+        "stream" => match parse_xinfo_stream_args(&args[1..]) {
+            Ok(full) => full,
+            Err(err) => return err,
+        },
+        _ => None,
+    };
+
+    db.transaction(|db| {
+        let stream = match db.get_stream(key) {
+            Ok(Some(stream)) => stream,
+            Ok(None) => return serializer::err("ERR no such key"),
+            Err(_) => return wrong_type(),
+        };
+
+        match subcommand.as_str() {
+            "stream" => match full {
+                Some(count) => stream_info_full(stream, count),
+                None => stream_info(stream),
+            },
+            "groups" => groups_info(stream),
+            _ => match stream.group(&args[1]) {
+                Some(group) => consumers_info(group),
+                None => no_such_group(key, &args[1]),
+            },
+        }
+    })
+}
@@ -0,0 +1,669 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::spec_helper::*;
+
+    const WAIT: Duration = Duration::from_secs(2);
+    // long enough for a command sent before to be blocked
+    const SETTLE: Duration = Duration::from_millis(100);
+
+    // the IDs of the entries of an XRANGE reply, in order
+    fn ids(reply: &str) -> Vec<String> {
+        let lines: Vec<&str> = reply.split("\r\n").collect();
+
+        // an entry is `[id, [field, value, ...]]`
+        lines
+            .windows(4)
+            .filter(|window| window[0] == "*2" && window[3].starts_with('*'))
+            .map(|window| window[2].to_string())
+            .collect()
+    }
+
+    // `count` entries with the IDs 1-0, 2-0 ...
+    fn add_entries(key: &str, count: usize) {
+        for ms in 1..=count {
+            run(&["XADD", key, &format!("{}-0", ms), "f", "v"]);
+        }
+    }
+
+    fn first_id(key: &str) -> String {
+        ids(&run(&["XRANGE", key, "-", "+", "COUNT", "1"])).remove(0)
+    }
+
+    // (id, consumer, idle, delivery count) of every pending entry of `group`
+    fn pending(key: &str, group: &str) -> Vec<(String, String, i64, i64)> {
+        let reply = run(&["XPENDING", key, group, "-", "+", "100"]);
+        let lines: Vec<&str> = reply.split("\r\n").skip(1).collect();
+
+        lines
+            .chunks_exact(7)
+            .map(|entry| {
+                (
+                    entry[2].to_string(),
+                    entry[4].to_string(),
+                    entry[5][1..].parse().unwrap(),
+                    entry[6][1..].parse().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn delivered(key: &str, ids: &[&str]) {
+        for id in ids {
+            run(&["XADD", key, id, "f", "v"]);
+        }
+
+        run(&["XGROUP", "CREATE", key, "g", "0"]);
+        run(&["XREADGROUP", "GROUP", "g", "alice", "STREAMS", key, ">"]);
+    }
+
+    #[test]
+    fn test_xadd_ids() {
+        assert_eq!(run(&["XADD", "xadd", "5-1", "f", "v"]), "$3\r\n5-1\r\n");
+        assert_eq!(run(&["XADD", "xadd", "5-*", "f", "v"]), "$3\r\n5-2\r\n");
+        assert_eq!(run(&["XADD", "xadd", "6-*", "f", "v"]), "$3\r\n6-0\r\n");
+
+        // an auto generated ID is always after the last one
+        let id = run(&["XADD", "xadd", "*", "f", "v"]);
+        assert!(id.ends_with("-0\r\n"), "{}", id);
+
+        assert_eq!(
+            run(&["XADD", "xadd", "5-3", "f", "v"]),
+            "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n"
+        );
+        assert_eq!(
+            run(&["XADD", "xadd:zero", "0-0", "f", "v"]),
+            "-ERR The ID specified in XADD must be greater than 0-0\r\n"
+        );
+        assert_eq!(
+            run(&["XADD", "xadd", "x-1", "f", "v"]),
+            "-ERR Invalid stream ID specified as stream command argument\r\n"
+        );
+        assert_eq!(
+            run(&["XADD", "xadd", "*", "f"]),
+            "-ERR wrong number of arguments for 'xadd' command\r\n"
+        );
+        assert_eq!(run(&["XLEN", "xadd"]), ":4\r\n");
+
+        assert_eq!(
+            run(&["XADD", "xadd:nomkstream", "NOMKSTREAM", "*", "f", "v"]),
+            "$-1\r\n"
+        );
+        assert_eq!(run(&["EXISTS", "xadd:nomkstream"]), ":0\r\n");
+    }
+
+    #[test]
+    fn test_xrange() {
+        add_entries("xrange", 5);
+
+        assert_eq!(
+            ids(&run(&["XRANGE", "xrange", "(2-0", "+", "COUNT", "2"])),
+            ["3-0", "4-0"]
+        );
+        assert_eq!(
+            ids(&run(&["XREVRANGE", "xrange", "(5-0", "-", "COUNT", "2"])),
+            ["4-0", "3-0"]
+        );
+        // an incomplete end ID takes every sequence number of its millisecond
+        assert_eq!(ids(&run(&["XRANGE", "xrange", "4", "4"])), ["4-0"]);
+        assert_eq!(run(&["XRANGE", "xrange", "4", "2"]), "*0\r\n");
+        // as redis, COUNT 0 is a null reply
+        assert_eq!(
+            run(&["XRANGE", "xrange", "-", "+", "COUNT", "0"]),
+            "*-1\r\n"
+        );
+
+        // a deleted entry is skipped, and deleting it again does nothing
+        assert_eq!(run(&["XDEL", "xrange", "3-0", "3-0", "9-0"]), ":1\r\n");
+        assert_eq!(
+            ids(&run(&["XRANGE", "xrange", "-", "+"])),
+            ["1-0", "2-0", "4-0", "5-0"]
+        );
+        assert_eq!(run(&["XLEN", "xrange"]), ":4\r\n");
+
+        assert_eq!(
+            run(&[
+                "XRANGE",
+                "xrange",
+                "(18446744073709551615-18446744073709551615",
+                "+"
+            ]),
+            "-ERR invalid start ID for the interval\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xtrim_across_blocks() {
+        // a block holds 100 entries, these take three of them
+        add_entries("xtrim", 250);
+
+        // whole blocks only, as long as they all fit under the threshold
+        assert_eq!(run(&["XTRIM", "xtrim", "MAXLEN", "~", "120"]), ":100\r\n");
+        assert_eq!(run(&["XTRIM", "xtrim", "MAXLEN", "~", "120"]), ":0\r\n");
+        assert_eq!(first_id("xtrim"), "101-0");
+
+        // an exact trim goes into the middle of a block
+        assert_eq!(run(&["XTRIM", "xtrim", "MAXLEN", "=", "120"]), ":30\r\n");
+        assert_eq!(run(&["XLEN", "xtrim"]), ":120\r\n");
+        assert_eq!(first_id("xtrim"), "131-0");
+
+        // deleted entries are not counted
+        run(&["XDEL", "xtrim", "131-0", "133-0"]);
+        assert_eq!(run(&["XTRIM", "xtrim", "MINID", "140"]), ":7\r\n");
+        assert_eq!(first_id("xtrim"), "140-0");
+        assert_eq!(run(&["XLEN", "xtrim"]), ":111\r\n");
+
+        // a block with a single live entry left goes at once
+        assert_eq!(run(&["XTRIM", "xtrim", "MINID", "~", "201"]), ":61\r\n");
+        assert_eq!(first_id("xtrim"), "201-0");
+
+        // XADD trims after adding
+        assert_eq!(
+            run(&["XADD", "xtrim", "MAXLEN", "2", "251-0", "f", "v"]),
+            "$5\r\n251-0\r\n"
+        );
+        assert_eq!(
+            ids(&run(&["XRANGE", "xtrim", "-", "+"])),
+            ["250-0", "251-0"]
+        );
+    }
+
+    #[test]
+    fn test_xtrim_limit() {
+        add_entries("xtrim:limit", 250);
+
+        // the next block would go past the limit
+        assert_eq!(
+            run(&["XTRIM", "xtrim:limit", "MAXLEN", "~", "0", "LIMIT", "150"]),
+            ":100\r\n"
+        );
+        assert_eq!(
+            run(&["XTRIM", "xtrim:limit", "MAXLEN", "~", "0", "LIMIT", "0"]),
+            ":150\r\n"
+        );
+        // the stream stays around, empty
+        assert_eq!(run(&["XLEN", "xtrim:limit"]), ":0\r\n");
+        assert_eq!(run(&["EXISTS", "xtrim:limit"]), ":1\r\n");
+
+        assert_eq!(
+            run(&["XTRIM", "xtrim:limit", "MAXLEN", "0", "LIMIT", "10"]),
+            "-ERR syntax error, LIMIT cannot be used without the special ~ option\r\n"
+        );
+        assert_eq!(
+            run(&["XTRIM", "xtrim:limit", "MAXLEN", "-1"]),
+            "-ERR The MAXLEN argument must be >= 0.\r\n"
+        );
+        assert_eq!(
+            run(&["XTRIM", "xtrim:limit", "MAXLEN", "1", "MINID", "1"]),
+            "-ERR syntax error, MAXLEN and MINID options at the same time are not compatible\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xsetid() {
+        add_entries("xsetid", 2);
+
+        assert_eq!(
+            run(&["XSETID", "xsetid", "1-0"]),
+            "-ERR The ID specified in XSETID is smaller than the target stream top item\r\n"
+        );
+        assert_eq!(run(&["XSETID", "xsetid", "10-0"]), "+OK\r\n");
+        assert_eq!(
+            run(&["XADD", "xsetid", "9-0", "f", "v"]),
+            "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n"
+        );
+        assert_eq!(
+            run(&["XSETID", "xsetid", "11-0", "ENTRIESADDED", "1"]),
+            "-ERR The entries_added specified in XSETID is smaller than the target stream length\r\n"
+        );
+        assert_eq!(
+            run(&["XSETID", "xsetid", "11-0", "MAXDELETEDID", "12-0"]),
+            "-ERR The ID specified in XSETID is smaller than the provided max_deleted_entry_id\r\n"
+        );
+        assert_eq!(
+            run(&["XSETID", "xsetid:missing", "1-0"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    // the XREAD reply for a single entry `id` of `key`
+    fn read_reply(key: &str, id: &str) -> String {
+        format!(
+            "*1\r\n*2\r\n${}\r\n{}\r\n*1\r\n*2\r\n${}\r\n{}\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n",
+            key.len(),
+            key,
+            id.len(),
+            id
+        )
+    }
+
+    #[test]
+    fn test_xread() {
+        add_entries("xread:a", 3);
+        add_entries("xread:b", 1);
+
+        assert_eq!(
+            run(&["XREAD", "COUNT", "1", "STREAMS", "xread:a", "xread:b", "1", "0"]),
+            "*2\r\n*2\r\n$7\r\nxread:a\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n\
+             *2\r\n$7\r\nxread:b\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n"
+        );
+
+        // "+" is the last entry, "$" what comes after it
+        assert_eq!(
+            run(&["XREAD", "STREAMS", "xread:a", "+"]),
+            read_reply("xread:a", "3-0")
+        );
+        assert_eq!(run(&["XREAD", "STREAMS", "xread:a", "$"]), "*-1\r\n");
+        assert_eq!(run(&["XREAD", "STREAMS", "xread:missing", "0"]), "*-1\r\n");
+
+        assert_eq!(
+            run(&["XREAD", "STREAMS", "xread:a", "xread:b", "0"]),
+            "-ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.\r\n"
+        );
+        assert_eq!(
+            run(&["XREAD", "STREAMS", "xread:a", ">"]),
+            "-ERR The > ID can be specified only when calling XREADGROUP using the GROUP <group> <consumer> option.\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xread_block_dollar() {
+        add_entries("xread:dollar", 1);
+
+        let mut conn = Connection::open();
+
+        conn.send(&["XREAD", "BLOCK", "0", "STREAMS", "xread:dollar", "$"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        // only the keys being watched wake the reader up
+        run(&["XADD", "xread:unwatched", "1-0", "f", "v"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        run(&["XADD", "xread:dollar", "2-0", "f", "v"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), read_reply("xread:dollar", "2-0"));
+    }
+
+    #[test]
+    fn test_xread_block_plus() {
+        let mut conn = Connection::open();
+
+        // with no entry to return, "+" waits like "$"
+        conn.send(&["XREAD", "BLOCK", "0", "STREAMS", "xread:plus", "+"]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        run(&["XADD", "xread:plus", "1-0", "f", "v"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), read_reply("xread:plus", "1-0"));
+
+        // and once there is one, it is returned right away
+        conn.send(&["XREAD", "BLOCK", "0", "STREAMS", "xread:plus", "+"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), read_reply("xread:plus", "1-0"));
+    }
+
+    #[test]
+    fn test_xread_block_timeout() {
+        let mut conn = Connection::open();
+
+        conn.send(&["XREAD", "BLOCK", "100", "STREAMS", "xread:timeout", "$"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "*-1\r\n");
+
+        conn.send(&["XREAD", "BLOCK", "-1", "STREAMS", "xread:timeout", "$"]);
+        assert_eq!(conn.reply(WAIT).unwrap(), "-ERR timeout is negative\r\n");
+
+        // too far away to ever expire
+        conn.send(&[
+            "XREAD",
+            "BLOCK",
+            "9223372036854775807",
+            "STREAMS",
+            "xread:timeout",
+            "$",
+        ]);
+        assert_eq!(conn.reply(SETTLE), None);
+
+        run(&["XADD", "xread:timeout", "1-0", "f", "v"]);
+        assert_eq!(
+            conn.reply(WAIT).unwrap(),
+            read_reply("xread:timeout", "1-0")
+        );
+    }
+
+    // the RESP2 map reply of XINFO GROUPS for a single group
+    fn group_info(
+        name: &str,
+        consumers: i64,
+        pending: i64,
+        last: &str,
+        read: &str,
+        lag: &str,
+    ) -> String {
+        format!(
+            "*12\r\n$4\r\nname\r\n${}\r\n{}\r\n$9\r\nconsumers\r\n:{}\r\n\
+             $7\r\npending\r\n:{}\r\n$17\r\nlast-delivered-id\r\n${}\r\n{}\r\n\
+             $12\r\nentries-read\r\n{}\r\n$3\r\nlag\r\n{}\r\n",
+            name.len(),
+            name,
+            consumers,
+            pending,
+            last.len(),
+            last,
+            read,
+            lag
+        )
+    }
+
+    #[test]
+    fn test_xinfo_stream() {
+        add_entries("xinfo:stream", 3);
+        run(&["XDEL", "xinfo:stream", "2-0"]);
+
+        assert_eq!(
+            run(&["XINFO", "STREAM", "xinfo:stream"]),
+            "*20\r\n$6\r\nlength\r\n:2\r\n$15\r\nradix-tree-keys\r\n:1\r\n\
+             $16\r\nradix-tree-nodes\r\n:1\r\n$17\r\nlast-generated-id\r\n$3\r\n3-0\r\n\
+             $20\r\nmax-deleted-entry-id\r\n$3\r\n2-0\r\n$13\r\nentries-added\r\n:3\r\n\
+             $23\r\nrecorded-first-entry-id\r\n$3\r\n1-0\r\n$6\r\ngroups\r\n:0\r\n\
+             $11\r\nfirst-entry\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n\
+             $10\r\nlast-entry\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n"
+        );
+
+        // FULL lists the entries too, as many as COUNT says
+        let full = run(&["XINFO", "STREAM", "xinfo:stream", "FULL", "COUNT", "1"]);
+        assert!(full.contains(
+            "$7\r\nentries\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n$6\r\ngroups\r\n*0\r\n"
+        ));
+
+        // an emptied stream keeps its last ID
+        run(&["XDEL", "xinfo:stream", "1-0", "3-0"]);
+        let info = run(&["XINFO", "STREAM", "xinfo:stream"]);
+        assert!(info.contains("$17\r\nlast-generated-id\r\n$3\r\n3-0\r\n"));
+        assert!(info.ends_with("$11\r\nfirst-entry\r\n$-1\r\n$10\r\nlast-entry\r\n$-1\r\n"));
+
+        assert_eq!(
+            run(&["XINFO", "STREAM", "xinfo:stream", "FULL", "COUNT"]),
+            "-ERR syntax error\r\n"
+        );
+        assert_eq!(
+            run(&["XINFO", "STREAM", "xinfo:missing"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xinfo_groups_lag() {
+        add_entries("xinfo:groups", 5);
+        run(&["XGROUP", "CREATE", "xinfo:groups", "g1", "0"]);
+        run(&["XGROUP", "CREATE", "xinfo:groups", "g2", "$"]);
+        run(&[
+            "XREADGROUP",
+            "GROUP",
+            "g1",
+            "alice",
+            "COUNT",
+            "2",
+            "STREAMS",
+            "xinfo:groups",
+            ">",
+        ]);
+
+        // g2 read nothing, but starting from the last entry it has no lag
+        assert_eq!(
+            run(&["XINFO", "GROUPS", "xinfo:groups"]),
+            format!(
+                "*2\r\n{}{}",
+                group_info("g1", 1, 2, "2-0", ":2", ":3"),
+                group_info("g2", 0, 0, "5-0", "$-1", ":0")
+            )
+        );
+
+        // once g2 falls behind, its lag can't be told without entries-read
+        run(&["XADD", "xinfo:groups", "6-0", "f", "v"]);
+        assert_eq!(
+            run(&["XINFO", "GROUPS", "xinfo:groups"]),
+            format!(
+                "*2\r\n{}{}",
+                group_info("g1", 1, 2, "2-0", ":2", ":4"),
+                group_info("g2", 0, 0, "5-0", "$-1", "$-1")
+            )
+        );
+
+        // a deleted entry in the middle of the stream makes the lag unknown
+        run(&["XDEL", "xinfo:groups", "4-0"]);
+        assert_eq!(
+            run(&["XINFO", "GROUPS", "xinfo:groups"]),
+            format!(
+                "*2\r\n{}{}",
+                group_info("g1", 1, 2, "2-0", ":2", "$-1"),
+                group_info("g2", 0, 0, "5-0", "$-1", "$-1")
+            )
+        );
+
+        assert_eq!(
+            run(&["XINFO", "GROUPS", "xinfo:missing"]),
+            "-ERR no such key\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xinfo_consumers() {
+        delivered("xinfo:consumers", &["1-0", "2-0"]);
+        run(&["XGROUP", "CREATECONSUMER", "xinfo:consumers", "g", "bob"]);
+
+        let info = run(&["XINFO", "CONSUMERS", "xinfo:consumers", "g"]);
+        assert!(
+            info.starts_with("*2\r\n*8\r\n$4\r\nname\r\n$5\r\nalice\r\n$7\r\npending\r\n:2\r\n")
+        );
+        // bob was never handed an entry
+        assert!(info.contains("$4\r\nname\r\n$3\r\nbob\r\n$7\r\npending\r\n:0\r\n"));
+        assert!(info.ends_with("$8\r\ninactive\r\n:-1\r\n"));
+
+        assert_eq!(
+            run(&["XINFO", "CONSUMERS", "xinfo:consumers", "missing"]),
+            "-NOGROUP No such consumer group 'missing' for key name 'xinfo:consumers'\r\n"
+        );
+        assert_eq!(
+            run(&["XINFO", "LAG", "xinfo:consumers"]),
+            "-ERR unknown subcommand 'LAG'. Try XINFO HELP.\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xclaim() {
+        delivered("xclaim", &["1-0", "2-0", "3-0"]);
+
+        // not idle for long enough
+        assert_eq!(
+            run(&["XCLAIM", "xclaim", "g", "bob", "3600000", "1-0"]),
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&["XCLAIM", "xclaim", "g", "bob", "0", "1-0"]),
+            "*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n"
+        );
+
+        // JUSTID does not count as a delivery, RETRYCOUNT sets the count
+        assert_eq!(
+            run(&["XCLAIM", "xclaim", "g", "bob", "0", "2-0", "JUSTID"]),
+            "*1\r\n$3\r\n2-0\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "XCLAIM",
+                "xclaim",
+                "g",
+                "bob",
+                "0",
+                "3-0",
+                "RETRYCOUNT",
+                "5",
+                "JUSTID"
+            ]),
+            "*1\r\n$3\r\n3-0\r\n"
+        );
+
+        let counts: Vec<(String, String, i64)> = pending("xclaim", "g")
+            .into_iter()
+            .map(|(id, consumer, _, count)| (id, consumer, count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("1-0".to_string(), "bob".to_string(), 2),
+                ("2-0".to_string(), "bob".to_string(), 1),
+                ("3-0".to_string(), "bob".to_string(), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xclaim_idle_and_time() {
+        delivered("xclaim:idle", &["1-0", "2-0"]);
+
+        run(&[
+            "XCLAIM",
+            "xclaim:idle",
+            "g",
+            "bob",
+            "0",
+            "1-0",
+            "IDLE",
+            "500000",
+            "JUSTID",
+        ]);
+        // a delivery time way back, the first second of 1970
+        run(&[
+            "XCLAIM",
+            "xclaim:idle",
+            "g",
+            "bob",
+            "0",
+            "2-0",
+            "TIME",
+            "1000",
+            "JUSTID",
+        ]);
+
+        let pending = pending("xclaim:idle", "g");
+        assert!((500000..510000).contains(&pending[0].2));
+        assert!(pending[1].2 > 1_600_000_000_000);
+
+        // which is what min-idle-time is compared to
+        assert_eq!(
+            run(&[
+                "XCLAIM",
+                "xclaim:idle",
+                "g",
+                "carol",
+                "400000",
+                "1-0",
+                "2-0",
+                "JUSTID"
+            ]),
+            "*2\r\n$3\r\n1-0\r\n$3\r\n2-0\r\n"
+        );
+
+        assert_eq!(
+            run(&["XCLAIM", "xclaim:idle", "g", "bob", "0", "1-0", "IDLE", "x"]),
+            "-ERR Invalid IDLE option argument for XCLAIM\r\n"
+        );
+    }
+
+    #[test]
+    fn test_xclaim_force_and_deleted() {
+        delivered("xclaim:force", &["1-0", "2-0"]);
+        run(&["XADD", "xclaim:force", "3-0", "f", "v"]);
+
+        // 3-0 was never delivered, only FORCE claims it
+        assert_eq!(
+            run(&["XCLAIM", "xclaim:force", "g", "bob", "0", "3-0"]),
+            "*0\r\n"
+        );
+        assert_eq!(
+            run(&[
+                "XCLAIM",
+                "xclaim:force",
+                "g",
+                "bob",
+                "0",
+                "3-0",
+                "FORCE",
+                "JUSTID"
+            ]),
+            "*1\r\n$3\r\n3-0\r\n"
+        );
+
+        // a deleted entry is not claimed, and leaves the PEL
+        run(&["XDEL", "xclaim:force", "2-0"]);
+        assert_eq!(
+            run(&["XCLAIM", "xclaim:force", "g", "bob", "0", "2-0", "JUSTID"]),
+            "*0\r\n"
+        );
+
+        let ids: Vec<String> = pending("xclaim:force", "g")
+            .into_iter()
+            .map(|(id, ..)| id)
+            .collect();
+        assert_eq!(ids, ["1-0", "3-0"]);
+    }
+
+    #[test]
+    fn test_xautoclaim() {
+        delivered("xautoclaim", &["1-0", "2-0", "3-0", "4-0"]);
+        run(&["XDEL", "xautoclaim", "2-0"]);
+
+        assert_eq!(
+            run(&[
+                "XAUTOCLAIM",
+                "xautoclaim",
+                "g",
+                "bob",
+                "0",
+                "0",
+                "COUNT",
+                "0"
+            ]),
+            "-ERR COUNT must be > 0\r\n"
+        );
+
+        // nothing is idle for long enough, the whole PEL is scanned
+        assert_eq!(
+            run(&["XAUTOCLAIM", "xautoclaim", "g", "bob", "3600000", "0"]),
+            "*3\r\n$3\r\n0-0\r\n*0\r\n*1\r\n$3\r\n2-0\r\n"
+        );
+
+        // the deleted entry is gone from the PEL now
+        assert_eq!(
+            run(&[
+                "XAUTOCLAIM",
+                "xautoclaim",
+                "g",
+                "bob",
+                "0",
+                "0",
+                "COUNT",
+                "1",
+                "JUSTID"
+            ]),
+            "*3\r\n$3\r\n3-0\r\n*1\r\n$3\r\n1-0\r\n*0\r\n"
+        );
+        assert_eq!(
+            run(&["XAUTOCLAIM", "xautoclaim", "g", "bob", "0", "3-0"]),
+            "*3\r\n$3\r\n0-0\r\n*2\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n\
+             *2\r\n$3\r\n4-0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n*0\r\n"
+        );
+
+        // JUSTID does not count as a delivery
+        let counts: Vec<(String, String, i64)> = pending("xautoclaim", "g")
+            .into_iter()
+            .map(|(id, consumer, _, count)| (id, consumer, count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("1-0".to_string(), "bob".to_string(), 1),
+                ("3-0".to_string(), "bob".to_string(), 2),
+                ("4-0".to_string(), "bob".to_string(), 2),
+            ]
+        );
+    }
+}
@@ -1,5 +1,19 @@
 use once_cell::sync::Lazy;
-use std::{collections::HashMap, sync::Mutex, thread, time, vec};
+use rand::seq::IteratorRandom;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+    thread, time, vec,
+};
+
+use super::{
+    blocking, client,
+    hash::Hash,
+    object::{Encoding, Metadata},
+    set::Set,
+    sorted_set::SortedSet,
+    stream::Stream,
+};
 
 /*
  * redis primarily have these five data-types:
@@ -12,17 +26,79 @@ use std::{collections::HashMap, sync::Mutex, thread, time, vec};
 
 #[derive(Debug)]
 pub enum DataTypes {
-    String(String),
+    String(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(Hash),
+    Set(Set),
+    SortedSet(SortedSet),
+    Stream(Stream),
 }
 
 impl Clone for DataTypes {
     fn clone(&self) -> Self {
         match self {
             DataTypes::String(s) => DataTypes::String(s.clone()),
+            DataTypes::List(l) => DataTypes::List(l.clone()),
+            DataTypes::Hash(h) => DataTypes::Hash(h.clone()),
+            DataTypes::Set(s) => DataTypes::Set(s.clone()),
+            DataTypes::SortedSet(z) => DataTypes::SortedSet(z.clone()),
+            DataTypes::Stream(x) => DataTypes::Stream(x.clone()),
+        }
+    }
+}
+
+impl DataTypes {
+    // as replied by TYPE
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DataTypes::String(_) => "string",
+            DataTypes::List(_) => "list",
+            DataTypes::Hash(_) => "hash",
+            DataTypes::Set(_) => "set",
+            DataTypes::SortedSet(_) => "zset",
+            DataTypes::Stream(_) => "stream",
+        }
+    }
+
+    /*
+     * collection types are never stored empty. redis deletes a key as soon as
+     * the last element of its list/set/hash is removed, and so do we
+     */
+    pub fn is_empty_collection(&self) -> bool {
+        match self {
+            DataTypes::String(_) => false,
+            DataTypes::List(l) => l.is_empty(),
+            DataTypes::Hash(h) => h.is_empty(),
+            DataTypes::Set(s) => s.is_empty(),
+            DataTypes::SortedSet(z) => z.is_empty(),
+            // a stream outlives its entries, as it keeps track of its last ID
+            DataTypes::Stream(_) => false,
         }
     }
 }
 
+// returned when a command is run against a key holding another kind of value
+#[derive(Debug)]
+pub struct WrongTypeErr;
+
+// a value along with what OBJECT reports about it, see `object.rs`
+#[derive(Debug, Clone)]
+pub struct Entry {
+    value: DataTypes,
+    meta: Metadata,
+}
+
+impl Entry {
+    fn new(value: DataTypes) -> Entry {
+        let meta = Metadata::new(Encoding::of(&value, None), now_ms());
+        Entry { value, meta }
+    }
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
 /* ------------------------------------------------------------------- */
 /* ---------------- Key Expiry Information --------------------------- */
 /* ------------------------------------------------------------------- */
@@ -33,7 +109,7 @@ struct KeyExpiryInfo {
 }
 
 // expiry
-static mut EXPIRY_INFO: Lazy<Mutex<KeyExpiryInfo>> = Lazy::new(|| {
+static EXPIRY_INFO: Lazy<Mutex<KeyExpiryInfo>> = Lazy::new(|| {
     Mutex::new(KeyExpiryInfo {
         data: HashMap::new(),
     })
@@ -44,45 +120,430 @@ static mut EXPIRY_INFO: Lazy<Mutex<KeyExpiryInfo>> = Lazy::new(|| {
 /* ------------------------------------------------------------------- */
 
 #[derive(Debug)]
-struct Database {
-    data: HashMap<String, DataTypes>,
+pub struct Database {
+    data: HashMap<String, Entry>,
+
+    // keys written by the running transaction, their encoding may change
+    written: HashSet<String>,
+
+    // keys that may unblock waiting clients, see `blocking.rs`
+    ready_keys: VecDeque<String>,
+
+    // hashes with at least one field that has a TTL
+    volatile_hashes: HashSet<String>,
 }
 
 unsafe impl Sync for Database {}
 
 // persistant db variable through out the program
-static mut DB: Lazy<Mutex<Database>> = Lazy::new(|| {
+static DB: Lazy<Mutex<Database>> = Lazy::new(|| {
     Mutex::new(Database {
         data: HashMap::new(),
+        written: HashSet::new(),
+        ready_keys: VecDeque::new(),
+        volatile_hashes: HashSet::new(),
     })
 });
 
+impl Database {
+    /*
+     * a blocked client can only be served once the key it waits on exists
+     * again (collections are never stored empty), so keys are marked as ready
+     * whenever they are created
+     */
+    pub fn signal_key_ready(&mut self, key: &str) {
+        self.ready_keys.push_back(key.to_string());
+    }
+
+    pub fn next_ready_key(&mut self) -> Option<String> {
+        self.ready_keys.pop_front()
+    }
+
+    /*
+     * sets `key` to a whole new value (the STORE variants of commands). like a
+     * delete followed by a write, whatever TTL the key had is gone
+     */
+    pub fn insert(&mut self, key: String, value: DataTypes) {
+        self.remove(&key);
+        self.signal_key_ready(&key);
+        self.data.insert(key, Entry::new(value));
+    }
+
+    // replaces the value of `key` in place, keeping its TTL (PFADD & co.)
+    pub fn update(&mut self, key: &str, value: DataTypes) {
+        match self.lookup_mut(key) {
+            Some(current) => *current = value,
+            None => self.insert(key.to_string(), value),
+        }
+    }
+
+    // deletes `key` along with its TTL
+    pub fn remove(&mut self, key: &str) -> Option<DataTypes> {
+        let entry = self.data.remove(key)?;
+
+        self.set_expires_at(key, None);
+        self.volatile_hashes.remove(key);
+
+        Some(entry.value)
+    }
+
+    // drops `key` if a command has just removed the last element it held
+    pub fn remove_if_empty(&mut self, key: &str) {
+        let is_empty = match self.data.get(key) {
+            Some(entry) => entry.value.is_empty_collection(),
+            None => false,
+        };
+
+        if is_empty {
+            self.remove(key);
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.data.contains_key(key)
+    }
+
+    /*
+     * unlike the typed accessors below, these do not count as an access to
+     * `key` (TYPE and OBJECT only peek at it)
+     */
+    pub fn get_value(&self, key: &str) -> Option<&DataTypes> {
+        self.data.get(key).map(|entry| &entry.value)
+    }
+
+    pub fn get_metadata(&self, key: &str) -> Option<&Metadata> {
+        self.data.get(key).map(|entry| &entry.meta)
+    }
+
+    // records an access to `key`, even if the client asked for CLIENT NO-TOUCH
+    pub fn touch(&self, key: &str) -> bool {
+        match self.data.get(key) {
+            Some(entry) => {
+                entry.meta.touch(now_ms(), rand::random());
+                true
+            }
+            None => false,
+        }
+    }
+
+    // like `get_value`, but counts as an access, for commands taking any type
+    pub fn lookup(&self, key: &str) -> Option<&DataTypes> {
+        let entry = self.data.get(key)?;
+
+        if !client::no_touch() {
+            entry.meta.touch(now_ms(), rand::random());
+        }
+
+        Some(&entry.value)
+    }
+
+    fn lookup_mut(&mut self, key: &str) -> Option<&mut DataTypes> {
+        let entry = self.data.get_mut(key)?;
+
+        if !client::no_touch() {
+            entry.meta.touch(now_ms(), rand::random());
+        }
+
+        self.written.insert(key.to_string());
+        Some(&mut entry.value)
+    }
+
+    fn lookup_or_insert(
+        &mut self,
+        key: &str,
+        create: impl FnOnce() -> DataTypes,
+    ) -> &mut DataTypes {
+        if !self.data.contains_key(key) {
+            self.data.insert(key.to_string(), Entry::new(create()));
+        }
+
+        self.lookup_mut(key).unwrap()
+    }
+
+    /*
+     * a write may have grown (or shrunk) a collection past the size of its
+     * compact encoding. called once the transaction is over, so that a
+     * command touching many elements converts the value only once
+     */
+    fn update_encodings(&mut self) {
+        for key in std::mem::take(&mut self.written) {
+            if let Some(entry) = self.data.get_mut(&key) {
+                let encoding = Encoding::of(&entry.value, Some(entry.meta.encoding()));
+                entry.meta.set_encoding(encoding);
+            }
+        }
+    }
+
+    pub fn random_key(&self) -> Option<String> {
+        let mut rng = rand::thread_rng();
+        self.data.keys().choose(&mut rng).cloned()
+    }
+
+    // the unix time (in seconds) `key` expires at, if it has a TTL
+    pub fn expires_at(&self, key: &str) -> Option<i64> {
+        EXPIRY_INFO.lock().unwrap().data.get(key).copied()
+    }
+
+    pub fn set_expires_at(&mut self, key: &str, at_unix_time: Option<i64>) {
+        let mut expiry_info = EXPIRY_INFO.lock().unwrap();
+
+        match at_unix_time {
+            Some(at_unix_time) => expiry_info.data.insert(key.to_string(), at_unix_time),
+            None => expiry_info.data.remove(key),
+        };
+    }
+
+    /*
+     * moves the value of `from` (and its TTL) to `to`, replacing whatever `to`
+     * held. returns false if there is nothing to move
+     */
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        let volatile = self.volatile_hashes.contains(from);
+        let expires_at = self.expires_at(from);
+
+        let entry = match self.data.remove(from) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        self.set_expires_at(from, None);
+        self.volatile_hashes.remove(from);
+
+        self.put(to, entry, expires_at, volatile);
+        true
+    }
+
+    // like `rename`, but `from` is kept
+    pub fn copy(&mut self, from: &str, to: &str) -> bool {
+        let volatile = self.volatile_hashes.contains(from);
+        let expires_at = self.expires_at(from);
+
+        let entry = match self.data.get(from) {
+            Some(entry) => Entry::new(entry.value.clone()),
+            None => return false,
+        };
+
+        self.put(to, entry, expires_at, volatile);
+        true
+    }
+
+    fn put(&mut self, key: &str, entry: Entry, expires_at: Option<i64>, volatile: bool) {
+        self.remove(key);
+        self.signal_key_ready(key);
+        self.data.insert(key.to_string(), entry);
+        self.set_expires_at(key, expires_at);
+
+        if volatile {
+            self.track_hash_expires(key);
+        }
+    }
+
+    pub fn get_string(&self, key: &str) -> Result<Option<&Vec<u8>>, WrongTypeErr> {
+        match self.lookup(key) {
+            Some(DataTypes::String(data)) => Ok(Some(data)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> Result<Option<&VecDeque<Vec<u8>>>, WrongTypeErr> {
+        match self.lookup(key) {
+            Some(DataTypes::List(list)) => Ok(Some(list)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_list_mut(
+        &mut self,
+        key: &str,
+    ) -> Result<Option<&mut VecDeque<Vec<u8>>>, WrongTypeErr> {
+        match self.lookup_mut(key) {
+            Some(DataTypes::List(list)) => Ok(Some(list)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    // like `get_list_mut`, but creates an empty list when `key` is missing
+    pub fn get_or_create_list(
+        &mut self,
+        key: &str,
+    ) -> Result<&mut VecDeque<Vec<u8>>, WrongTypeErr> {
+        if !self.data.contains_key(key) {
+            self.signal_key_ready(key);
+        }
+
+        let value = self.lookup_or_insert(key, || DataTypes::List(VecDeque::new()));
+
+        match value {
+            DataTypes::List(list) => Ok(list),
+            _ => Err(WrongTypeErr),
+        }
+    }
+
+    /*
+     * hash fields with a TTL are deleted lazily, whenever the hash is looked up,
+     * and actively, by `delete_expired_keys`. this is why even reading a hash
+     * needs mutable access
+     */
+    fn expire_hash_fields(&mut self, key: &str) {
+        if !self.volatile_hashes.contains(key) {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let has_expires = match self.data.get_mut(key).map(|entry| &mut entry.value) {
+            Some(DataTypes::Hash(hash)) => {
+                hash.remove_expired(now);
+                hash.has_expires()
+            }
+            _ => false,
+        };
+
+        if !has_expires {
+            self.volatile_hashes.remove(key);
+        }
+
+        self.remove_if_empty(key);
+    }
+
+    // must be called after giving a TTL to one of the fields of `key`
+    pub fn track_hash_expires(&mut self, key: &str) {
+        self.volatile_hashes.insert(key.to_string());
+    }
+
+    pub fn get_hash(&mut self, key: &str) -> Result<Option<&Hash>, WrongTypeErr> {
+        self.expire_hash_fields(key);
+
+        match self.lookup(key) {
+            Some(DataTypes::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_hash_mut(&mut self, key: &str) -> Result<Option<&mut Hash>, WrongTypeErr> {
+        self.expire_hash_fields(key);
+
+        match self.lookup_mut(key) {
+            Some(DataTypes::Hash(hash)) => Ok(Some(hash)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_hash(&mut self, key: &str) -> Result<&mut Hash, WrongTypeErr> {
+        self.expire_hash_fields(key);
+
+        let value = self.lookup_or_insert(key, || DataTypes::Hash(Hash::new()));
+
+        match value {
+            DataTypes::Hash(hash) => Ok(hash),
+            _ => Err(WrongTypeErr),
+        }
+    }
+
+    pub fn get_set(&self, key: &str) -> Result<Option<&Set>, WrongTypeErr> {
+        match self.lookup(key) {
+            Some(DataTypes::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_set_mut(&mut self, key: &str) -> Result<Option<&mut Set>, WrongTypeErr> {
+        match self.lookup_mut(key) {
+            Some(DataTypes::Set(set)) => Ok(Some(set)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_set(&mut self, key: &str) -> Result<&mut Set, WrongTypeErr> {
+        let value = self.lookup_or_insert(key, || DataTypes::Set(Set::new()));
+
+        match value {
+            DataTypes::Set(set) => Ok(set),
+            _ => Err(WrongTypeErr),
+        }
+    }
+
+    pub fn get_zset(&self, key: &str) -> Result<Option<&SortedSet>, WrongTypeErr> {
+        match self.lookup(key) {
+            Some(DataTypes::SortedSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_zset_mut(&mut self, key: &str) -> Result<Option<&mut SortedSet>, WrongTypeErr> {
+        match self.lookup_mut(key) {
+            Some(DataTypes::SortedSet(zset)) => Ok(Some(zset)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_zset(&mut self, key: &str) -> Result<&mut SortedSet, WrongTypeErr> {
+        if !self.data.contains_key(key) {
+            self.signal_key_ready(key);
+        }
+
+        let value = self.lookup_or_insert(key, || DataTypes::SortedSet(SortedSet::new()));
+
+        match value {
+            DataTypes::SortedSet(zset) => Ok(zset),
+            _ => Err(WrongTypeErr),
+        }
+    }
+
+    pub fn get_stream(&self, key: &str) -> Result<Option<&Stream>, WrongTypeErr> {
+        match self.lookup(key) {
+            Some(DataTypes::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_stream_mut(&mut self, key: &str) -> Result<Option<&mut Stream>, WrongTypeErr> {
+        match self.lookup_mut(key) {
+            Some(DataTypes::Stream(stream)) => Ok(Some(stream)),
+            Some(_) => Err(WrongTypeErr),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or_create_stream(&mut self, key: &str) -> Result<&mut Stream, WrongTypeErr> {
+        let value = self.lookup_or_insert(key, || DataTypes::Stream(Stream::new()));
+
+        match value {
+            DataTypes::Stream(stream) => Ok(stream),
+            _ => Err(WrongTypeErr),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DatabaseOps;
 
 impl DatabaseOps {
     pub fn set(&mut self, key: String, value: DataTypes) {
-        let mut db = unsafe { DB.lock().unwrap() };
+        let mut db = DB.lock().unwrap();
 
-        db.data.insert(key, value);
+        db.data.insert(key, Entry::new(value));
     }
 
     pub fn get(&self, key: String) -> Option<DataTypes> {
-        let db = unsafe { DB.lock().unwrap() };
+        let db = DB.lock().unwrap();
 
         let key = &key[..];
-        let data = db.data.get(key);
-
-        let owned_data = data.clone();
-
-        match owned_data {
-            Some(data) => Some(data.clone()),
-            None => None,
-        }
+        db.lookup(key).cloned()
     }
 
     pub fn keys(&self) -> Vec<String> {
-        let db = unsafe { DB.lock().unwrap() };
+        let db = DB.lock().unwrap();
         let keys = db.data.keys();
         let mut keys_list: Vec<String> = vec![];
 
@@ -93,19 +554,26 @@ impl DatabaseOps {
         keys_list
     }
 
-    pub fn del(&self, key: String) -> Option<DataTypes> {
-        let mut db = unsafe { DB.lock().unwrap() };
-        let key = &key[..];
+    /*
+     * runs `f` while holding the database lock. commands that read a value and
+     * then write it back (or touch more than one key) must go through here,
+     * otherwise another client may sneak in between the `get` and the `set`
+     */
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Database) -> T) -> T {
+        let mut db = DB.lock().unwrap();
+        let result = f(&mut db);
 
-        let removed_key = db.data.remove(key);
+        // serving blocked clients writes too
+        blocking::handle_ready_keys(&mut db);
+        db.update_encodings();
 
-        removed_key
+        result
     }
 
     pub fn expire(&mut self, key: String, at_unix_time: i64) {
         thread::spawn(move || {
-            let mut db = unsafe { DB.lock().unwrap() };
-            let mut expiry_info_db = unsafe { EXPIRY_INFO.lock().unwrap() };
+            let mut db = DB.lock().unwrap();
+            let mut expiry_info_db = EXPIRY_INFO.lock().unwrap();
 
             if at_unix_time == -1 {
                 db.data.remove(&key);
@@ -126,8 +594,8 @@ impl DatabaseOps {
      * values are moved as soon as the function returns
      */
     fn expire_keys_helper() {
-        let mut db = unsafe { DB.lock().unwrap() };
-        let mut expiry_info_db = unsafe { EXPIRY_INFO.lock().unwrap() };
+        let mut db = DB.lock().unwrap();
+        let mut expiry_info_db = EXPIRY_INFO.lock().unwrap();
         let mut keys_to_delete: Vec<String> = vec![];
 
         for (key, value) in expiry_info_db.data.iter() {
@@ -141,6 +609,15 @@ impl DatabaseOps {
             db.data.remove(&key);
             expiry_info_db.data.remove(&key);
         }
+
+        // removing an emptied hash takes the expiry lock again
+        drop(expiry_info_db);
+
+        let volatile_hashes: Vec<String> = db.volatile_hashes.iter().cloned().collect();
+
+        for key in volatile_hashes {
+            db.expire_hash_fields(&key);
+        }
     }
 
     pub fn delete_expired_keys(&mut self) {
@@ -0,0 +1,454 @@
+/*
+ * geohashes, as used by redis' GEO commands
+ * -----------------------------------------
+ * a position is stored as a sorted set member whose score is a 52 bit
+ * geohash: the longitude and latitude are each mapped to a 26 bit integer
+ * (their offset within the valid range) and the bits of both are interleaved,
+ * latitude in the even bits and longitude in the odd ones. nearby positions
+ * thus share a prefix and end up close to each other in the sorted set
+ *
+ * a hash of fewer steps (bits per coordinate) is a bigger cell, and every
+ * position within it falls in a single, contiguous range of scores. searching
+ * around a point is done by picking a step whose cells are about as big as
+ * the searched area, then walking the cell of the point and its 8 neighbours
+ */
+
+pub const LONG_MIN: f64 = -180.0;
+pub const LONG_MAX: f64 = 180.0;
+// the limits of EPSG:3857 (web mercator)
+pub const LAT_MIN: f64 = -85.05112878;
+pub const LAT_MAX: f64 = 85.05112878;
+
+pub const STEP_MAX: u8 = 26;
+
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+
+const ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+const WGS84_LONG: CoordRange = CoordRange {
+    min: LONG_MIN,
+    max: LONG_MAX,
+};
+
+const WGS84_LAT: CoordRange = CoordRange {
+    min: LAT_MIN,
+    max: LAT_MAX,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoHash {
+    pub bits: u64,
+    pub step: u8,
+}
+
+// the cell a hash stands for
+#[derive(Debug, Clone, Copy)]
+pub struct Area {
+    pub longitude: CoordRange,
+    pub latitude: CoordRange,
+}
+
+// what is searched around the center. sizes are in meters
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Search {
+    pub longitude: f64,
+    pub latitude: f64,
+    pub shape: Shape,
+}
+
+pub fn is_valid(longitude: f64, latitude: f64) -> bool {
+    (LONG_MIN..=LONG_MAX).contains(&longitude) && (LAT_MIN..=LAT_MAX).contains(&latitude)
+}
+
+// spreads the 32 bits of `value` over the even bits of the result
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+
+    x = (x | (x << 16)) & 0x0000ffff0000ffff;
+    x = (x | (x << 8)) & 0x00ff00ff00ff00ff;
+    x = (x | (x << 4)) & 0x0f0f0f0f0f0f0f0f;
+    x = (x | (x << 2)) & 0x3333333333333333;
+    x = (x | (x << 1)) & 0x5555555555555555;
+
+    x
+}
+
+// the reverse of `spread`, the odd bits are ignored
+fn squash(value: u64) -> u32 {
+    let mut x = value & 0x5555555555555555;
+
+    x = (x | (x >> 1)) & 0x3333333333333333;
+    x = (x | (x >> 2)) & 0x0f0f0f0f0f0f0f0f;
+    x = (x | (x >> 4)) & 0x00ff00ff00ff00ff;
+    x = (x | (x >> 8)) & 0x0000ffff0000ffff;
+    x = (x | (x >> 16)) & 0x00000000ffffffff;
+
+    x as u32
+}
+
+fn encode_in(
+    long_range: CoordRange,
+    lat_range: CoordRange,
+    longitude: f64,
+    latitude: f64,
+    step: u8,
+) -> Option<GeoHash> {
+    if !is_valid(longitude, latitude)
+        || longitude < long_range.min
+        || longitude > long_range.max
+        || latitude < lat_range.min
+        || latitude > lat_range.max
+    {
+        return None;
+    }
+
+    let cells = (1u64 << step) as f64;
+    let lat_offset = (latitude - lat_range.min) / (lat_range.max - lat_range.min) * cells;
+    let long_offset = (longitude - long_range.min) / (long_range.max - long_range.min) * cells;
+
+    Some(GeoHash {
+        bits: spread(lat_offset as u32) | (spread(long_offset as u32) << 1),
+        step,
+    })
+}
+
+fn decode_in(long_range: CoordRange, lat_range: CoordRange, hash: GeoHash) -> Area {
+    let cells = (1u64 << hash.step) as f64;
+    let lat_cell = squash(hash.bits) as f64;
+    let long_cell = squash(hash.bits >> 1) as f64;
+
+    let lat_scale = lat_range.max - lat_range.min;
+    let long_scale = long_range.max - long_range.min;
+
+    Area {
+        latitude: CoordRange {
+            min: lat_range.min + lat_cell / cells * lat_scale,
+            max: lat_range.min + (lat_cell + 1.0) / cells * lat_scale,
+        },
+        longitude: CoordRange {
+            min: long_range.min + long_cell / cells * long_scale,
+            max: long_range.min + (long_cell + 1.0) / cells * long_scale,
+        },
+    }
+}
+
+pub fn encode(longitude: f64, latitude: f64, step: u8) -> Option<GeoHash> {
+    encode_in(WGS84_LONG, WGS84_LAT, longitude, latitude, step)
+}
+
+pub fn decode(hash: GeoHash) -> Area {
+    decode_in(WGS84_LONG, WGS84_LAT, hash)
+}
+
+impl Area {
+    // (longitude, latitude) of the middle of the cell
+    pub fn center(&self) -> (f64, f64) {
+        let longitude = (self.longitude.min + self.longitude.max) / 2.0;
+        let latitude = (self.latitude.min + self.latitude.max) / 2.0;
+
+        (
+            longitude.clamp(LONG_MIN, LONG_MAX),
+            latitude.clamp(LAT_MIN, LAT_MAX),
+        )
+    }
+}
+
+// the sorted set score a position is stored with
+pub fn score(longitude: f64, latitude: f64) -> Option<f64> {
+    encode(longitude, latitude, STEP_MAX).map(|hash| hash.bits as f64)
+}
+
+// the position back from a score, up to the precision of the hash
+pub fn position(score: f64) -> (f64, f64) {
+    let hash = GeoHash {
+        bits: score as u64,
+        step: STEP_MAX,
+    };
+
+    decode(hash).center()
+}
+
+/*
+ * the usual 11 character geohash of a stored position. these are computed
+ * against the whole [-90, 90] latitude range rather than the mercator one
+ * scores use, so that they can be used with other geohash tools
+ */
+pub fn to_string(score: f64) -> String {
+    let (longitude, latitude) = position(score);
+
+    let long_range = CoordRange {
+        min: -180.0,
+        max: 180.0,
+    };
+    let lat_range = CoordRange {
+        min: -90.0,
+        max: 90.0,
+    };
+
+    let bits =
+        encode_in(long_range, lat_range, longitude, latitude, STEP_MAX).map_or(0, |hash| hash.bits);
+
+    // only 52 bits are available, the last character is always "0"
+    (0..11)
+        .map(|i| match i {
+            10 => ALPHABET[0] as char,
+            _ => ALPHABET[((bits >> (52 - (i + 1) * 5)) & 0x1f) as usize] as char,
+        })
+        .collect()
+}
+
+fn deg_rad(degrees: f64) -> f64 {
+    degrees.to_radians()
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS_IN_METERS * (deg_rad(lat2) - deg_rad(lat1)).abs()
+}
+
+// distance in meters between two positions, with the haversine formula
+pub fn distance(long1: f64, lat1: f64, long2: f64, lat2: f64) -> f64 {
+    let v = ((deg_rad(long2) - deg_rad(long1)) / 2.0).sin();
+
+    // same meridian, no need for the expensive math
+    if v == 0.0 {
+        return lat_distance(lat1, lat2);
+    }
+
+    let (lat1, lat2) = (deg_rad(lat1), deg_rad(lat2));
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let a = u * u + lat1.cos() * lat2.cos() * v * v;
+
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+/* ---- neighbours ---- */
+
+// moves a hash one cell east (1) or west (-1)
+fn move_x(hash: GeoHash, direction: i8) -> GeoHash {
+    let x = hash.bits & 0xaaaaaaaaaaaaaaaa;
+    let y = hash.bits & 0x5555555555555555;
+    let zz = 0x5555555555555555u64 >> (64 - hash.step as u32 * 2);
+
+    let x = match direction {
+        1 => x.wrapping_add(zz + 1),
+        _ => (x | zz).wrapping_sub(zz + 1),
+    };
+
+    GeoHash {
+        bits: (x & (0xaaaaaaaaaaaaaaaa >> (64 - hash.step as u32 * 2))) | y,
+        step: hash.step,
+    }
+}
+
+// moves a hash one cell north (1) or south (-1)
+fn move_y(hash: GeoHash, direction: i8) -> GeoHash {
+    let x = hash.bits & 0xaaaaaaaaaaaaaaaa;
+    let y = hash.bits & 0x5555555555555555;
+    let zz = 0xaaaaaaaaaaaaaaaau64 >> (64 - hash.step as u32 * 2);
+
+    let y = match direction {
+        1 => y.wrapping_add(zz + 1),
+        _ => (y | zz).wrapping_sub(zz + 1),
+    };
+
+    GeoHash {
+        bits: x | (y & (0x5555555555555555 >> (64 - hash.step as u32 * 2))),
+        step: hash.step,
+    }
+}
+
+fn neighbour(hash: GeoHash, east: i8, north: i8) -> GeoHash {
+    let hash = if east != 0 { move_x(hash, east) } else { hash };
+
+    if north != 0 {
+        move_y(hash, north)
+    } else {
+        hash
+    }
+}
+
+// the step whose cells are about as wide as the searched radius
+fn estimate_step(mut radius: f64, latitude: f64) -> u8 {
+    if radius == 0.0 {
+        return STEP_MAX;
+    }
+
+    let mut step: i32 = 1;
+
+    while radius < MERCATOR_MAX {
+        radius *= 2.0;
+        step += 1;
+    }
+
+    // make sure the radius is covered in most cases
+    step -= 2;
+
+    // cells narrow towards the poles
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+
+    step.clamp(1, STEP_MAX as i32) as u8
+}
+
+impl Search {
+    /*
+     * the distance (in meters) from the center to a position within the
+     * searched shape, or None if the position is outside of it
+     */
+    pub fn distance_to(&self, longitude: f64, latitude: f64) -> Option<f64> {
+        match self.shape {
+            Shape::Radius(radius) => {
+                let distance = distance(self.longitude, self.latitude, longitude, latitude);
+                (distance <= radius).then_some(distance)
+            }
+            Shape::Box { width, height } => {
+                if lat_distance(latitude, self.latitude) > height / 2.0 {
+                    return None;
+                }
+
+                if distance(longitude, latitude, self.longitude, latitude) > width / 2.0 {
+                    return None;
+                }
+
+                Some(distance(self.longitude, self.latitude, longitude, latitude))
+            }
+        }
+    }
+
+    // (min longitude, min latitude, max longitude, max latitude)
+    fn bounding_box(&self) -> (f64, f64, f64, f64) {
+        let (width, height) = match self.shape {
+            Shape::Radius(radius) => (radius, radius),
+            Shape::Box { width, height } => (width / 2.0, height / 2.0),
+        };
+
+        let lat_delta = (height / EARTH_RADIUS_IN_METERS).to_degrees();
+        let long_delta =
+            |latitude: f64| (width / EARTH_RADIUS_IN_METERS / deg_rad(latitude).cos()).to_degrees();
+
+        // the box is widest on the side closest to the pole
+        let long_delta = match self.latitude < 0.0 {
+            true => long_delta(self.latitude - lat_delta),
+            false => long_delta(self.latitude + lat_delta),
+        };
+
+        (
+            self.longitude - long_delta,
+            self.latitude - lat_delta,
+            self.longitude + long_delta,
+            self.latitude + lat_delta,
+        )
+    }
+
+    /*
+     * the cells covering the searched shape: the cell of the center and its
+     * neighbours (north, south, east, west, north east, north west, south
+     * east, south west), leaving out the ones the shape does not reach
+     */
+    pub fn areas(&self) -> Vec<GeoHash> {
+        let (min_long, min_lat, max_long, max_lat) = self.bounding_box();
+
+        let radius = match self.shape {
+            Shape::Radius(radius) => radius,
+            Shape::Box { width, height } => (width / 2.0).hypot(height / 2.0),
+        };
+
+        let mut step = estimate_step(radius, self.latitude);
+        let cell = |step| encode(self.longitude, self.latitude, step).unwrap();
+
+        /*
+         * near the edge of its cell, the shape may reach further than the
+         * neighbours do. in that case bigger cells are needed
+         */
+        let hash = cell(step);
+        let north = decode(neighbour(hash, 0, 1));
+        let south = decode(neighbour(hash, 0, -1));
+        let east = decode(neighbour(hash, 1, 0));
+        let west = decode(neighbour(hash, -1, 0));
+
+        if step > 1
+            && (north.latitude.max < max_lat
+                || south.latitude.min > min_lat
+                || east.longitude.max < max_long
+                || west.longitude.min > min_long)
+        {
+            step -= 1;
+        }
+
+        let hash = cell(step);
+        let area = decode(hash);
+
+        /*
+         * a neighbour is useless when the center cell already goes past the
+         * shape on that side. the biggest cells are always kept
+         */
+        let (north, south, east, west) = match step >= 2 {
+            true => (
+                area.latitude.max <= max_lat,
+                area.latitude.min >= min_lat,
+                area.longitude.max <= max_long,
+                area.longitude.min >= min_long,
+            ),
+            false => (true, true, true, true),
+        };
+
+        let candidates = [
+            (true, 0, 0),
+            (north, 0, 1),
+            (south, 0, -1),
+            (east, 1, 0),
+            (west, -1, 0),
+            (north && east, 1, 1),
+            (north && west, -1, 1),
+            (south && east, 1, -1),
+            (south && west, -1, -1),
+        ];
+
+        let mut areas: Vec<GeoHash> = vec![];
+
+        for (needed, east, north) in candidates {
+            if !needed {
+                continue;
+            }
+
+            let cell = neighbour(hash, east, north);
+
+            // with huge radiuses neighbours may be the same cell
+            if areas.last() != Some(&cell) {
+                areas.push(cell);
+            }
+        }
+
+        areas
+    }
+}
+
+impl GeoHash {
+    // the range of scores [min, max) of the positions within the cell
+    pub fn score_range(&self) -> (f64, f64) {
+        let shift = 52 - self.step as u32 * 2;
+
+        (
+            (self.bits << shift) as f64,
+            ((self.bits + 1) << shift) as f64,
+        )
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use super::super::geohash::*;
+
+    const PALERMO: (f64, f64) = (13.361389, 38.115556);
+    const CATANIA: (f64, f64) = (15.087269, 37.502669);
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score(PALERMO.0, PALERMO.1), Some(3479099956230698.0));
+        assert_eq!(score(CATANIA.0, CATANIA.1), Some(3479447370796909.0));
+
+        assert_eq!(score(181.0, 0.0), None);
+        assert_eq!(score(0.0, 86.0), None);
+    }
+
+    #[test]
+    fn test_position() {
+        let (longitude, latitude) = position(3479099956230698.0);
+
+        assert_eq!(format!("{:.17}", longitude), "13.36138933897018433");
+        assert_eq!(format!("{:.17}", latitude), "38.11555639549629859");
+    }
+
+    #[test]
+    fn test_to_string() {
+        assert_eq!(to_string(3479099956230698.0), "sqc8b49rny0");
+        assert_eq!(to_string(3479447370796909.0), "sqdtr74hyu0");
+    }
+
+    #[test]
+    fn test_distance() {
+        let palermo = position(3479099956230698.0);
+        let catania = position(3479447370796909.0);
+
+        let meters = distance(palermo.0, palermo.1, catania.0, catania.1);
+        assert_eq!(format!("{:.4}", meters), "166274.1516");
+
+        assert_eq!(distance(10.0, 20.0, 10.0, 20.0), 0.0);
+    }
+
+    #[test]
+    fn test_search() {
+        let search = Search {
+            longitude: 15.0,
+            latitude: 37.0,
+            shape: Shape::Radius(200_000.0),
+        };
+
+        let catania = position(3479447370796909.0);
+        let distance = search.distance_to(catania.0, catania.1).unwrap();
+        assert_eq!(format!("{:.4}", distance / 1000.0), "56.4413");
+
+        let far = Search {
+            shape: Shape::Radius(50_000.0),
+            ..search
+        };
+        assert_eq!(far.distance_to(catania.0, catania.1), None);
+
+        // the cells searched hold both positions
+        let covered = |score: f64| {
+            search.areas().iter().any(|area| {
+                let (min, max) = area.score_range();
+                score >= min && score < max
+            })
+        };
+
+        assert!(covered(3479099956230698.0));
+        assert!(covered(3479447370796909.0));
+    }
+
+    #[test]
+    fn test_search_box() {
+        let search = Search {
+            longitude: 15.0,
+            latitude: 37.0,
+            shape: Shape::Box {
+                width: 400_000.0,
+                height: 400_000.0,
+            },
+        };
+
+        let palermo = position(3479099956230698.0);
+        assert!(search.distance_to(palermo.0, palermo.1).is_some());
+
+        let narrow = Search {
+            shape: Shape::Box {
+                width: 100_000.0,
+                height: 400_000.0,
+            },
+            ..search
+        };
+        assert_eq!(narrow.distance_to(palermo.0, palermo.1), None);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{hash_map, BTreeSet, HashMap};
+
+use super::scan::ScanIndex;
+
+/*
+ * value of a hash key. on top of the fields themselves, it keeps the unix
+ * time (in milliseconds) at which each field with a TTL expires, those same
+ * TTLs ordered by time (so that only the expired ones are ever visited), and
+ * the field names in scan order (HSCAN)
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Hash {
+    fields: HashMap<String, String>,
+    expires: HashMap<String, i64>,
+    deadlines: BTreeSet<(i64, String)>,
+    index: ScanIndex,
+}
+
+impl Hash {
+    pub fn new() -> Hash {
+        Hash::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&String> {
+        self.fields.get(field)
+    }
+
+    pub fn contains_key(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, String, String> {
+        self.fields.iter()
+    }
+
+    // sets a field, dropping the TTL it had (like HSET does)
+    pub fn insert(&mut self, field: String, value: String) -> Option<String> {
+        self.persist(&field);
+        self.update(field, value)
+    }
+
+    // sets a field, keeping the TTL it had (like HINCRBY does)
+    pub fn update(&mut self, field: String, value: String) -> Option<String> {
+        if !self.fields.contains_key(&field) {
+            self.index.insert(&field);
+        }
+
+        self.fields.insert(field, value)
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<String> {
+        self.persist(field);
+        self.index.remove(field);
+        self.fields.remove(field)
+    }
+
+    // the fields following `cursor`, see scan.rs
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&str, &String)>) {
+        let (cursor, fields) = self.index.scan(cursor, count);
+        let fields = fields.into_iter().map(|field| (field, &self.fields[field]));
+
+        (cursor, fields.collect())
+    }
+
+    pub fn expires_at(&self, field: &str) -> Option<i64> {
+        self.expires.get(field).copied()
+    }
+
+    pub fn set_expires_at(&mut self, field: &str, at_unix_time_ms: i64) {
+        if self.fields.contains_key(field) {
+            self.persist(field);
+            self.expires.insert(field.to_string(), at_unix_time_ms);
+            self.deadlines.insert((at_unix_time_ms, field.to_string()));
+        }
+    }
+
+    // removes the TTL of a field. returns `false` if it did not have one
+    pub fn persist(&mut self, field: &str) -> bool {
+        match self.expires.remove(field) {
+            Some(at) => self.deadlines.remove(&(at, field.to_string())),
+            None => false,
+        }
+    }
+
+    pub fn has_expires(&self) -> bool {
+        !self.expires.is_empty()
+    }
+
+    // deletes the fields whose TTL is over, returning how many were deleted
+    pub fn remove_expired(&mut self, now_ms: i64) -> usize {
+        let mut removed = 0;
+
+        while let Some((at, _)) = self.deadlines.first() {
+            if *at > now_ms {
+                break;
+            }
+
+            let (_, field) = self.deadlines.pop_first().unwrap();
+            self.remove(&field);
+            removed += 1;
+        }
+
+        removed
+    }
+}
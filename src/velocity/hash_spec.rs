@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use super::super::hash::*;
+
+    #[test]
+    fn test_insert_drops_ttl() {
+        let mut hash = Hash::new();
+        hash.insert("f".to_string(), "1".to_string());
+        hash.set_expires_at("f", 1000);
+
+        // Test that updating a value keeps the TTL
+        hash.update("f".to_string(), "2".to_string());
+        assert_eq!(hash.expires_at("f"), Some(1000));
+
+        // Test that overwriting a value drops the TTL
+        hash.insert("f".to_string(), "3".to_string());
+        assert_eq!(hash.expires_at("f"), None);
+        assert!(!hash.has_expires());
+    }
+
+    #[test]
+    fn test_set_expires_at_missing_field() {
+        let mut hash = Hash::new();
+        hash.set_expires_at("missing", 1000);
+
+        assert_eq!(hash.expires_at("missing"), None);
+        assert!(!hash.has_expires());
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let mut hash = Hash::new();
+        hash.insert("a".to_string(), "1".to_string());
+        hash.insert("b".to_string(), "2".to_string());
+        hash.insert("c".to_string(), "3".to_string());
+        hash.set_expires_at("a", 100);
+        hash.set_expires_at("b", 200);
+
+        assert_eq!(hash.remove_expired(150), 1);
+        assert!(!hash.contains_key("a"));
+        assert!(hash.has_expires());
+
+        assert_eq!(hash.remove_expired(200), 1);
+        assert!(!hash.has_expires());
+        assert_eq!(hash.len(), 1);
+        assert_eq!(hash.get("c"), Some(&"3".to_string()));
+    }
+
+    #[test]
+    fn test_remove_expired_after_new_ttl() {
+        let mut hash = Hash::new();
+        hash.insert("a".to_string(), "1".to_string());
+        hash.insert("b".to_string(), "2".to_string());
+        hash.set_expires_at("a", 100);
+        hash.set_expires_at("b", 100);
+
+        // only the latest TTL of a field counts
+        hash.set_expires_at("a", 300);
+        hash.persist("b");
+
+        assert_eq!(hash.remove_expired(200), 0);
+        assert_eq!(hash.len(), 2);
+
+        assert_eq!(hash.remove_expired(300), 1);
+        assert!(!hash.contains_key("a"));
+        assert!(!hash.has_expires());
+    }
+}
@@ -0,0 +1,413 @@
+/*
+ * hyperloglog, byte compatible with redis
+ * ---------------------------------------
+ * an HLL is stored as a plain string value laid out exactly like redis does,
+ * so it can be moved between both servers (GET/SET, DUMP/RESTORE) untouched:
+ *
+ * +------+---+-----+----------+---------------------------+
+ * | HYLL | E | N/U | Cardin.  | registers ...             |
+ * +------+---+-----+----------+---------------------------+
+ *
+ * E is the encoding (0 dense, 1 sparse), followed by 3 unused bytes and the
+ * last computed cardinality as a little endian u64. the most significant bit
+ * of its last byte set means the cached value is stale
+ *
+ * there are 16384 six-bit registers. the dense encoding packs them one after
+ * the other, least significant bits first (12288 bytes). the sparse encoding
+ * run-length encodes them with three opcodes:
+ *
+ * * ZERO  00xxxxxx           run of 1..64 zero registers
+ * * XZERO 01xxxxxx yyyyyyyy  run of 1..16384 zero registers
+ * * VAL   1vvvvvxx           run of 1..4 registers set to 1..32
+ *
+ * an HLL starts sparse and is promoted to dense once a register does not fit
+ * a VAL opcode, or the sparse form grows past `SPARSE_MAX_BYTES`. the value
+ * is decoded into one byte per register while it is worked on, and encoded
+ * back when stored
+ */
+
+const MAGIC: &[u8] = b"HYLL";
+const HEADER_SIZE: usize = 16;
+
+const P: u32 = 14; // bits of the hash used to pick the register
+const Q: u32 = 64 - P; // bits of the hash used to count the run of zeroes
+const REGISTERS: usize = 1 << P;
+const BITS: usize = 6; // bits per register
+const REGISTER_MAX: u8 = (1 << BITS) - 1;
+
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+const DENSE_SIZE: usize = HEADER_SIZE + (REGISTERS * BITS).div_ceil(8);
+
+// redis' default for hll-sparse-max-bytes
+const SPARSE_MAX_BYTES: usize = 3000;
+
+const SPARSE_VAL_MAX_VALUE: u8 = 32;
+const SPARSE_VAL_MAX_LEN: usize = 4;
+const SPARSE_ZERO_MAX_LEN: usize = 64;
+const SPARSE_XZERO_MAX_LEN: usize = 16384;
+
+const SEED: u64 = 0xadc83b19;
+
+#[derive(Debug, PartialEq)]
+pub enum HllErr {
+    // the string does not even look like an HLL
+    NotHll,
+    // the header is fine but the sparse registers do not add up
+    Corrupted,
+}
+
+#[derive(Debug, Clone)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+    dense: bool,
+    // the cardinality bytes of the header, kept as they are when stale
+    card: [u8; 8],
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog::new()
+    }
+}
+
+impl HyperLogLog {
+    // an empty, sparse HLL. its cached cardinality (0) is valid
+    pub fn new() -> HyperLogLog {
+        HyperLogLog {
+            registers: vec![0; REGISTERS],
+            dense: false,
+            card: [0; 8],
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<HyperLogLog, HllErr> {
+        if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(HllErr::NotHll);
+        }
+
+        let dense = match bytes[4] {
+            DENSE => true,
+            SPARSE => false,
+            _ => return Err(HllErr::NotHll),
+        };
+
+        if dense && bytes.len() != DENSE_SIZE {
+            return Err(HllErr::NotHll);
+        }
+
+        let card = bytes[8..HEADER_SIZE].try_into().unwrap();
+
+        let data = &bytes[HEADER_SIZE..];
+        let registers = match dense {
+            true => (0..REGISTERS).map(|index| dense_get(data, index)).collect(),
+            false => sparse_decode(data).ok_or(HllErr::Corrupted)?,
+        };
+
+        Ok(HyperLogLog {
+            registers,
+            dense,
+            card,
+        })
+    }
+
+    /*
+     * lays the HLL out the way redis does. it stays sparse as long as that
+     * is possible, once dense it never goes back
+     */
+    pub fn encode(&self) -> Vec<u8> {
+        let sparse = match self.dense {
+            true => None,
+            false => sparse_encode(&self.registers)
+                .filter(|data| HEADER_SIZE + data.len() <= SPARSE_MAX_BYTES),
+        };
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(if sparse.is_some() { SPARSE } else { DENSE });
+        bytes.extend_from_slice(&[0; 3]);
+
+        bytes.extend_from_slice(&self.card);
+
+        match sparse {
+            Some(data) => bytes.extend_from_slice(&data),
+            None => {
+                let mut data = vec![0; DENSE_SIZE - HEADER_SIZE];
+
+                for (index, &value) in self.registers.iter().enumerate() {
+                    dense_set(&mut data, index, value);
+                }
+
+                bytes.extend_from_slice(&data);
+            }
+        }
+
+        bytes
+    }
+
+    #[allow(dead_code)]
+    pub fn is_dense(&self) -> bool {
+        self.dense
+    }
+
+    // whether adding the element changed any register
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let (index, count) = pattern(element);
+
+        if self.registers[index] >= count {
+            return false;
+        }
+
+        self.registers[index] = count;
+        self.invalidate();
+
+        true
+    }
+
+    /*
+     * folds another HLL into this one, which then estimates the cardinality
+     * of their union. like PFMERGE, merging a dense HLL makes this one dense
+     */
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, &value) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(value);
+        }
+
+        self.dense |= other.dense;
+        self.invalidate();
+    }
+
+    // like redis, only flags the cached cardinality, the old value stays
+    fn invalidate(&mut self) {
+        self.card[7] |= 0x80;
+    }
+
+    // the estimated cardinality, cached until a register changes
+    pub fn count(&mut self) -> u64 {
+        let count = match self.card[7] & 0x80 {
+            0 => u64::from_le_bytes(self.card),
+            _ => self.estimate(),
+        };
+        self.card = count.to_le_bytes();
+
+        count
+    }
+
+    /*
+     * estimates the cardinality out of the histogram of the registers, see
+     * "New cardinality estimation algorithms for HyperLogLog sketches" by
+     * Otmar Ertl (arXiv:1702.01284)
+     */
+    fn estimate(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut histogram = [0u32; 64];
+
+        for &value in &self.registers {
+            histogram[value as usize] += 1;
+        }
+
+        let q = Q as usize;
+        let mut z = m * tau((m - histogram[q + 1] as f64) / m);
+
+        for j in (1..=q).rev() {
+            z += histogram[j] as f64;
+            z *= 0.5;
+        }
+
+        z += m * sigma(histogram[0] as f64 / m);
+
+        let alpha_inf = 0.5 / std::f64::consts::LN_2;
+        (alpha_inf * m * m / z).round() as u64
+    }
+}
+
+/*
+ * the register an element falls into, and the length of the run of zeroes
+ * (plus one) at the end of the rest of its hash
+ */
+fn pattern(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash64a(element, SEED);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+
+    // the extra bit keeps the count at Q + 1 at most
+    let hash = (hash >> P) | (1 << Q);
+
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+// MurmurHash2, 64 bit version, as found in redis
+fn murmur_hash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+
+    let mut chunks = key.chunks_exact(8);
+
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+
+    if !tail.is_empty() {
+        for (i, &byte) in tail.iter().enumerate() {
+            h ^= (byte as u64) << (8 * i);
+        }
+
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+
+    h
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let mut y = 1.0;
+    let mut z = x;
+
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+/* ---- dense encoding ---- */
+
+/*
+ * a register may straddle two bytes. the last one never does, which is why
+ * the byte after it is only touched when it exists
+ */
+fn dense_get(data: &[u8], index: usize) -> u8 {
+    let byte = index * BITS / 8;
+    let shift = index * BITS % 8;
+
+    let low = (data[byte] >> shift) as u16;
+    let high = data
+        .get(byte + 1)
+        .map_or(0, |&next| (next as u16) << (8 - shift));
+
+    (low | high) as u8 & REGISTER_MAX
+}
+
+fn dense_set(data: &mut [u8], index: usize, value: u8) {
+    let byte = index * BITS / 8;
+    let shift = index * BITS % 8;
+    let value = value as u16 & REGISTER_MAX as u16;
+    let mask = REGISTER_MAX as u16;
+
+    data[byte] &= !(mask << shift) as u8;
+    data[byte] |= (value << shift) as u8;
+
+    if let Some(next) = data.get_mut(byte + 1) {
+        *next &= !(mask >> (8 - shift)) as u8;
+        *next |= (value >> (8 - shift)) as u8;
+    }
+}
+
+/* ---- sparse encoding ---- */
+
+// None if the opcodes do not describe exactly `REGISTERS` registers
+fn sparse_decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut registers = Vec::with_capacity(REGISTERS);
+    let mut i = 0;
+
+    while i < data.len() {
+        let opcode = data[i];
+
+        let (value, run) = match opcode >> 6 {
+            0b00 => (0, (opcode & 0x3f) as usize + 1),
+            0b01 => {
+                let low = *data.get(i + 1)? as usize;
+                i += 1;
+                (0, (((opcode & 0x3f) as usize) << 8 | low) + 1)
+            }
+            _ => (((opcode >> 2) & 0x1f) + 1, (opcode & 0x03) as usize + 1),
+        };
+
+        if registers.len() + run > REGISTERS {
+            return None;
+        }
+
+        registers.resize(registers.len() + run, value);
+        i += 1;
+    }
+
+    (registers.len() == REGISTERS).then_some(registers)
+}
+
+// None if some register is too big for a VAL opcode
+fn sparse_encode(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut data = vec![];
+    let mut i = 0;
+
+    while i < registers.len() {
+        let value = registers[i];
+        let run = registers[i..].iter().take_while(|&&v| v == value).count();
+
+        i += run;
+
+        if value > SPARSE_VAL_MAX_VALUE {
+            return None;
+        }
+
+        let mut left = run;
+
+        while left > 0 {
+            if value != 0 {
+                let len = left.min(SPARSE_VAL_MAX_LEN);
+                data.push(0x80 | (value - 1) << 2 | (len - 1) as u8);
+                left -= len;
+            } else if left > SPARSE_ZERO_MAX_LEN {
+                let len = left.min(SPARSE_XZERO_MAX_LEN) - 1;
+                data.push(0x40 | (len >> 8) as u8);
+                data.push((len & 0xff) as u8);
+                left -= len + 1;
+            } else {
+                data.push((left - 1) as u8);
+                left = 0;
+            }
+        }
+    }
+
+    Some(data)
+}
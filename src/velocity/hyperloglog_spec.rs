@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use super::super::hyperloglog::*;
+
+    fn hll_of(elements: impl Iterator<Item = String>) -> HyperLogLog {
+        let mut hll = HyperLogLog::new();
+
+        for element in elements {
+            hll.add(element.as_bytes());
+        }
+
+        hll
+    }
+
+    // within the 0.81% standard error, with some room to spare
+    fn assert_close(count: u64, expected: u64) {
+        let error = (count as f64 - expected as f64).abs() / expected as f64;
+        assert!(error < 0.03, "estimated {} for {}", count, expected);
+    }
+
+    #[test]
+    fn test_new_layout() {
+        let bytes = HyperLogLog::new().encode();
+
+        // header, a valid cached cardinality of 0 and a single XZERO opcode
+        let mut expected = b"HYLL\x01\x00\x00\x00".to_vec();
+        expected.extend_from_slice(&[0; 8]);
+        expected.extend_from_slice(&[0x7f, 0xff]);
+
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_add_and_count() {
+        let mut hll = HyperLogLog::new();
+
+        assert!(hll.add(b"a"));
+        assert!(!hll.add(b"a"));
+        assert!(hll.add(b"b"));
+        assert!(hll.add(b"c"));
+        assert_eq!(hll.count(), 3);
+
+        let mut hll = hll_of((0..10_000).map(|n| n.to_string()));
+        assert_close(hll.count(), 10_000);
+    }
+
+    #[test]
+    fn test_cached_count() {
+        let mut hll = hll_of(["a", "b"].iter().map(|s| s.to_string()));
+
+        // the cache is stale until counted
+        let bytes = hll.encode();
+        assert_eq!(bytes[15] & 0x80, 0x80);
+
+        hll.count();
+
+        let bytes = hll.encode();
+        assert_eq!(&bytes[8..16], &2u64.to_le_bytes());
+        assert_eq!(HyperLogLog::decode(&bytes).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_stale_cache_keeps_count() {
+        let mut hll = hll_of(["a", "b"].iter().map(|s| s.to_string()));
+        hll.count();
+        hll.add(b"c");
+
+        // only the stale bit is set, the last count stays in place
+        let mut expected = 2u64.to_le_bytes();
+        expected[7] |= 0x80;
+
+        let bytes = hll.encode();
+        assert_eq!(&bytes[8..16], &expected);
+
+        // and it goes through a decode untouched
+        let mut hll = HyperLogLog::decode(&bytes).unwrap();
+        assert_eq!(&hll.encode()[8..16], &expected);
+        assert_eq!(hll.count(), 3);
+        assert_eq!(&hll.encode()[8..16], &3u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_promotion_to_dense() {
+        let small = hll_of((0..100).map(|n| n.to_string()));
+        let bytes = small.encode();
+
+        assert_eq!(bytes[4], 1);
+        assert!(bytes.len() < 3000);
+
+        let big = hll_of((0..5000).map(|n| n.to_string()));
+        let bytes = big.encode();
+
+        assert_eq!(bytes[4], 0);
+        assert_eq!(bytes.len(), 12304);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for n in [0, 10, 1000, 20_000] {
+            let mut hll = hll_of((0..n).map(|n| format!("element:{}", n)));
+            let bytes = hll.encode();
+            let mut decoded = HyperLogLog::decode(&bytes).unwrap();
+
+            assert_eq!(decoded.encode(), bytes);
+            assert_eq!(decoded.count(), hll.count());
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = hll_of((0..3000).map(|n| n.to_string()));
+        let b = hll_of((2000..6000).map(|n| n.to_string()));
+
+        assert!(!a.is_dense());
+        a.merge(&b);
+
+        assert_close(a.count(), 6000);
+
+        // merging a dense HLL makes the result dense
+        let mut c = HyperLogLog::new();
+        let dense = HyperLogLog::decode(&b.encode()).unwrap();
+
+        assert!(dense.is_dense());
+        c.merge(&dense);
+        assert!(c.is_dense());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(HyperLogLog::decode(b"hello").unwrap_err(), HllErr::NotHll);
+
+        let mut bytes = HyperLogLog::new().encode();
+        bytes[4] = 7;
+        assert_eq!(HyperLogLog::decode(&bytes).unwrap_err(), HllErr::NotHll);
+
+        // a dense HLL must have all of its registers
+        let mut bytes = hll_of((0..5000).map(|n| n.to_string())).encode();
+        bytes.pop();
+        assert_eq!(HyperLogLog::decode(&bytes).unwrap_err(), HllErr::NotHll);
+
+        // sparse opcodes covering fewer registers than there are
+        let mut bytes = HyperLogLog::new().encode();
+        bytes.truncate(16);
+        bytes.push(0x00);
+        assert_eq!(HyperLogLog::decode(&bytes).unwrap_err(), HllErr::Corrupted);
+    }
+}
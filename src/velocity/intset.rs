@@ -0,0 +1,144 @@
+/*
+ * compact, sorted set of integers (like redis' intset)
+ * ----------------------------------------------------
+ * the integers are stored back to back, in little endian, using the smallest
+ * width (2, 4 or 8 bytes) that fits every one of them. adding an integer that
+ * does not fit "upgrades" the whole set to a wider encoding
+ *
+ * lookups are binary searches, inserts and removals are O(n) memmoves, which
+ * is fine as long as the set is kept small
+ */
+
+#[derive(Debug, Clone)]
+pub struct IntSet {
+    width: usize,
+    contents: Vec<u8>,
+}
+
+fn width_of(value: i64) -> usize {
+    if value >= i16::MIN as i64 && value <= i16::MAX as i64 {
+        2
+    } else if value >= i32::MIN as i64 && value <= i32::MAX as i64 {
+        4
+    } else {
+        8
+    }
+}
+
+impl Default for IntSet {
+    fn default() -> Self {
+        IntSet::new()
+    }
+}
+
+impl IntSet {
+    pub fn new() -> IntSet {
+        IntSet {
+            width: 2,
+            contents: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len() / self.width
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    // size in bytes of every stored integer
+    #[allow(dead_code)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get(&self, index: usize) -> i64 {
+        let start = index * self.width;
+        let bytes = &self.contents[start..start + self.width];
+
+        match self.width {
+            2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+            4 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64,
+            _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+        }
+    }
+
+    fn encode(value: i64, width: usize) -> Vec<u8> {
+        match width {
+            2 => (value as i16).to_le_bytes().to_vec(),
+            4 => (value as i32).to_le_bytes().to_vec(),
+            _ => value.to_le_bytes().to_vec(),
+        }
+    }
+
+    // `Ok(index)` if found, `Err(index)` with the position to insert it at
+    fn search(&self, value: i64) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let middle = (low + high) / 2;
+            let current = self.get(middle);
+
+            if current == value {
+                return Ok(middle);
+            } else if current < value {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        Err(low)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.search(value).is_ok()
+    }
+
+    fn upgrade(&mut self, width: usize) {
+        let values: Vec<i64> = self.iter().collect();
+
+        self.width = width;
+        self.contents = Vec::with_capacity(values.len() * width);
+
+        for value in values {
+            self.contents.extend(IntSet::encode(value, width));
+        }
+    }
+
+    // returns `false` if the value was already there
+    pub fn insert(&mut self, value: i64) -> bool {
+        if width_of(value) > self.width {
+            self.upgrade(width_of(value));
+        }
+
+        let index = match self.search(value) {
+            Ok(_) => return false,
+            Err(index) => index,
+        };
+
+        let start = index * self.width;
+        let bytes = IntSet::encode(value, self.width);
+
+        self.contents.splice(start..start, bytes);
+        true
+    }
+
+    // returns `false` if the value was not there
+    pub fn remove(&mut self, value: i64) -> bool {
+        let index = match self.search(value) {
+            Ok(index) => index,
+            Err(_) => return false,
+        };
+
+        let start = index * self.width;
+        self.contents.drain(start..start + self.width);
+        true
+    }
+
+    // the integers, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.len()).map(|index| self.get(index))
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use super::super::intset::*;
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut set = IntSet::new();
+
+        for value in [5, -3, 10, 0, 5, -3] {
+            set.insert(value);
+        }
+
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter().collect::<Vec<i64>>(), vec![-3, 0, 5, 10]);
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let mut set = IntSet::new();
+        set.insert(1);
+        set.insert(-2);
+        assert_eq!(set.width(), 2);
+
+        // Test with a value that needs 4 bytes
+        set.insert(70_000);
+        assert_eq!(set.width(), 4);
+
+        // Test with a value that needs 8 bytes
+        set.insert(i64::MIN);
+        assert_eq!(set.width(), 8);
+
+        assert_eq!(
+            set.iter().collect::<Vec<i64>>(),
+            vec![i64::MIN, -2, 1, 70_000]
+        );
+    }
+
+    #[test]
+    fn test_contains_and_remove() {
+        let mut set = IntSet::new();
+        set.insert(i64::MAX);
+        set.insert(42);
+
+        assert!(set.contains(42));
+        assert!(set.contains(i64::MAX));
+        assert!(!set.contains(43));
+
+        assert!(set.remove(42));
+        assert!(!set.remove(42));
+        assert_eq!(set.len(), 1);
+
+        assert!(set.remove(i64::MAX));
+        assert!(set.is_empty());
+    }
+}
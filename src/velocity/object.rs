@@ -0,0 +1,292 @@
+use std::{cell::Cell, fmt};
+
+use super::{database::DataTypes, set::Set, settings::SETTINGS};
+
+/*
+ * what redis keeps in the header of every value besides its type: how the
+ * value is encoded, and when and how often it was accessed (what the LRU and
+ * LFU eviction policies go by). OBJECT reports all of it
+ */
+
+/*
+ * the encodings redis would use for a value. small collections are stored
+ * as a listpack (one compact array) and converted to a real data structure
+ * once they grow past the `*_max_listpack_*` settings. the conversion only
+ * goes one way, except for lists which turn back into a listpack when they
+ * shrink enough
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Int,
+    EmbStr,
+    Raw,
+    ListPack,
+    // a listpack hash with fields that have a TTL
+    ListPackEx,
+    QuickList,
+    IntSet,
+    HashTable,
+    SkipList,
+    Stream,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Encoding::Int => "int",
+            Encoding::EmbStr => "embstr",
+            Encoding::Raw => "raw",
+            Encoding::ListPack => "listpack",
+            Encoding::ListPackEx => "listpackex",
+            Encoding::QuickList => "quicklist",
+            Encoding::IntSet => "intset",
+            Encoding::HashTable => "hashtable",
+            Encoding::SkipList => "skiplist",
+            Encoding::Stream => "stream",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+// strings up to this size are allocated along with their header
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+/*
+ * a list node holds up to 8kb (list-max-listpack-size -2). every entry takes
+ * its own bytes plus about 2 bytes of bookkeeping
+ */
+const LIST_MAX_LISTPACK_BYTES: usize = 8192;
+const LISTPACK_ENTRY_OVERHEAD: usize = 2;
+
+// integers stored as strings in this range are shared by every key
+const SHARED_INTEGERS: i64 = 10000;
+
+fn as_integer(data: &[u8]) -> Option<i64> {
+    let text = std::str::from_utf8(data).ok()?;
+    let value = text.parse::<i64>().ok()?;
+
+    (value.to_string() == text).then_some(value)
+}
+
+/*
+ * whether `len` items, of the given sizes, fit in a listpack of at most
+ * `entries` items of `value` bytes
+ */
+fn fits_listpack(
+    len: usize,
+    mut sizes: impl Iterator<Item = usize>,
+    entries: usize,
+    value: usize,
+) -> bool {
+    len <= entries && sizes.all(|size| size <= value)
+}
+
+impl Encoding {
+    /*
+     * the encoding of `value`, which was `previous` before the last write.
+     * strings are encoded anew every time they are set
+     */
+    pub fn of(value: &DataTypes, previous: Option<Encoding>) -> Encoding {
+        match value {
+            DataTypes::String(data) => match as_integer(data) {
+                Some(_) => Encoding::Int,
+                None if data.len() <= EMBSTR_SIZE_LIMIT => Encoding::EmbStr,
+                None => Encoding::Raw,
+            },
+            DataTypes::List(list) => {
+                // a quicklist has to shrink to half the limit to go back
+                let limit = match previous {
+                    Some(Encoding::QuickList) => LIST_MAX_LISTPACK_BYTES / 2,
+                    _ => LIST_MAX_LISTPACK_BYTES,
+                };
+
+                // every entry takes at least the overhead, no need to look
+                if list.len() * LISTPACK_ENTRY_OVERHEAD > limit {
+                    return Encoding::QuickList;
+                }
+
+                let mut size = 0;
+
+                for item in list {
+                    size += item.len() + LISTPACK_ENTRY_OVERHEAD;
+
+                    if size > limit {
+                        return Encoding::QuickList;
+                    }
+                }
+
+                Encoding::ListPack
+            }
+            DataTypes::Hash(hash) => {
+                let compact = previous != Some(Encoding::HashTable)
+                    && fits_listpack(
+                        hash.len(),
+                        hash.iter()
+                            .flat_map(|(field, value)| [field.len(), value.len()]),
+                        SETTINGS.hash_max_listpack_entries,
+                        SETTINGS.hash_max_listpack_value,
+                    );
+
+                match compact {
+                    false => Encoding::HashTable,
+                    true if hash.has_expires() || previous == Some(Encoding::ListPackEx) => {
+                        Encoding::ListPackEx
+                    }
+                    true => Encoding::ListPack,
+                }
+            }
+            DataTypes::Set(Set::IntSet(_)) => Encoding::IntSet,
+            DataTypes::Set(set) => {
+                let compact = previous != Some(Encoding::HashTable)
+                    && fits_listpack(
+                        set.len(),
+                        set.iter().map(|member| member.len()),
+                        SETTINGS.set_max_listpack_entries,
+                        SETTINGS.set_max_listpack_value,
+                    );
+
+                match compact {
+                    true => Encoding::ListPack,
+                    false => Encoding::HashTable,
+                }
+            }
+            DataTypes::SortedSet(zset) => {
+                let compact = previous != Some(Encoding::SkipList)
+                    && fits_listpack(
+                        zset.len(),
+                        zset.iter().map(|(member, _)| member.len()),
+                        SETTINGS.zset_max_listpack_entries,
+                        SETTINGS.zset_max_listpack_value,
+                    );
+
+                match compact {
+                    true => Encoding::ListPack,
+                    false => Encoding::SkipList,
+                }
+            }
+            DataTypes::Stream(_) => Encoding::Stream,
+        }
+    }
+}
+
+/* ---- access tracking ---- */
+
+// the LRU clock has a resolution of a second and wraps around after 24 bits
+const LRU_CLOCK_MAX: u64 = (1 << 24) - 1;
+const LRU_CLOCK_RESOLUTION: u64 = 1000;
+
+/*
+ * the LFU counter grows logarithmically: the higher it is, the less likely an
+ * access is to increment it. it starts at LFU_INIT_VAL, so that new keys are
+ * not evicted right away, and loses a point per minute without accesses
+ */
+const LFU_INIT_VAL: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10.0;
+const LFU_DECAY_TIME: u64 = 1; // in minutes
+
+fn lru_clock(now_ms: u64) -> u64 {
+    (now_ms / LRU_CLOCK_RESOLUTION) & LRU_CLOCK_MAX
+}
+
+// minutes, wrapping around after 16 bits
+fn lfu_minutes(now_ms: u64) -> u64 {
+    (now_ms / 60_000) & 0xffff
+}
+
+fn lfu_log_incr(counter: u8, random: f64) -> u8 {
+    if counter == u8::MAX {
+        return counter;
+    }
+
+    let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+    let probability = 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+
+    match random < probability {
+        true => counter + 1,
+        false => counter,
+    }
+}
+
+/*
+ * the access times live in cells, so that looking a key up (which only
+ * borrows the database) can update them
+ */
+#[derive(Debug, Clone)]
+pub struct Metadata {
+    encoding: Encoding,
+    lru: Cell<u64>,
+    lfu_minutes: Cell<u64>,
+    lfu_counter: Cell<u8>,
+}
+
+impl Metadata {
+    pub fn new(encoding: Encoding, now_ms: u64) -> Metadata {
+        Metadata {
+            encoding,
+            lru: Cell::new(lru_clock(now_ms)),
+            lfu_minutes: Cell::new(lfu_minutes(now_ms)),
+            lfu_counter: Cell::new(LFU_INIT_VAL),
+        }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    // records an access. `random` is in [0, 1) and decides the LFU increment
+    pub fn touch(&self, now_ms: u64, random: f64) {
+        let counter = self.frequency(now_ms);
+
+        self.lru.set(lru_clock(now_ms));
+        self.lfu_minutes.set(lfu_minutes(now_ms));
+        self.lfu_counter.set(lfu_log_incr(counter, random));
+    }
+
+    // milliseconds since the last access, with the resolution of the clock
+    pub fn idle_time(&self, now_ms: u64) -> u64 {
+        let clock = lru_clock(now_ms);
+        let lru = self.lru.get();
+
+        let ticks = match clock >= lru {
+            true => clock - lru,
+            false => clock + (LRU_CLOCK_MAX - lru),
+        };
+
+        ticks * LRU_CLOCK_RESOLUTION
+    }
+
+    // the LFU counter, minus what it lost since the last access
+    pub fn frequency(&self, now_ms: u64) -> u8 {
+        let now = lfu_minutes(now_ms);
+        let then = self.lfu_minutes.get();
+
+        let elapsed = match now >= then {
+            true => now - then,
+            false => 0xffff - then + now,
+        };
+
+        let periods = elapsed / LFU_DECAY_TIME;
+        self.lfu_counter
+            .get()
+            .saturating_sub(periods.min(255) as u8)
+    }
+}
+
+/*
+ * how many references redis would report for the value. small integers are
+ * shared objects, which redis reports as having INT_MAX references
+ */
+pub fn refcount(value: &DataTypes) -> i64 {
+    match value {
+        DataTypes::String(data) => match as_integer(data) {
+            Some(value) if (0..SHARED_INTEGERS).contains(&value) => i32::MAX as i64,
+            _ => 1,
+        },
+        _ => 1,
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::super::{
+        database::DataTypes, hash::Hash, object::*, set::Set, sorted_set::SortedSet,
+    };
+
+    const MINUTE: u64 = 60_000;
+
+    fn string(value: &str) -> DataTypes {
+        DataTypes::String(value.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_string_encoding() {
+        assert_eq!(Encoding::of(&string("12345"), None), Encoding::Int);
+        assert_eq!(Encoding::of(&string("-7"), None), Encoding::Int);
+        assert_eq!(Encoding::of(&string("007"), None), Encoding::EmbStr);
+        assert_eq!(Encoding::of(&string("hello"), None), Encoding::EmbStr);
+        assert_eq!(
+            Encoding::of(&string(&"a".repeat(44)), None),
+            Encoding::EmbStr
+        );
+        assert_eq!(Encoding::of(&string(&"a".repeat(45)), None), Encoding::Raw);
+
+        // a string does not remember how it was encoded
+        let previous = Some(Encoding::Raw);
+        assert_eq!(Encoding::of(&string("hello"), previous), Encoding::EmbStr);
+    }
+
+    #[test]
+    fn test_set_encoding() {
+        let mut set = Set::new();
+        set.insert("1".to_string());
+        let value = DataTypes::Set(set.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::IntSet);
+
+        set.insert("a".to_string());
+        let value = DataTypes::Set(set.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::ListPack);
+
+        set.insert("b".repeat(65));
+        let value = DataTypes::Set(set.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::HashTable);
+
+        // no way back, even once small again
+        set.remove(&"b".repeat(65));
+        let value = DataTypes::Set(set);
+        let previous = Some(Encoding::HashTable);
+        assert_eq!(Encoding::of(&value, previous), Encoding::HashTable);
+    }
+
+    #[test]
+    fn test_hash_encoding() {
+        let mut hash = Hash::new();
+        hash.insert("field".to_string(), "value".to_string());
+        let value = DataTypes::Hash(hash.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::ListPack);
+
+        for i in 0..128 {
+            hash.insert(format!("f{}", i), "v".to_string());
+        }
+        let value = DataTypes::Hash(hash);
+        assert_eq!(Encoding::of(&value, None), Encoding::HashTable);
+    }
+
+    #[test]
+    fn test_zset_encoding() {
+        let mut zset = SortedSet::new();
+        zset.insert("a".to_string(), 1.0);
+        let value = DataTypes::SortedSet(zset.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::ListPack);
+
+        zset.insert("b".repeat(65), 2.0);
+        let value = DataTypes::SortedSet(zset);
+        assert_eq!(Encoding::of(&value, None), Encoding::SkipList);
+    }
+
+    #[test]
+    fn test_list_encoding() {
+        let mut list: VecDeque<Vec<u8>> = (0..10).map(|i| i.to_string().into_bytes()).collect();
+        let value = DataTypes::List(list.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::ListPack);
+
+        list.push_back(b"x".repeat(8192));
+        let value = DataTypes::List(list.clone());
+        assert_eq!(Encoding::of(&value, None), Encoding::QuickList);
+
+        // a quicklist turns back into a listpack only at half the size
+        list.pop_back();
+        list.push_back(b"x".repeat(5000));
+        let value = DataTypes::List(list.clone());
+        let previous = Some(Encoding::QuickList);
+        assert_eq!(Encoding::of(&value, previous), Encoding::QuickList);
+
+        list.pop_back();
+        let value = DataTypes::List(list);
+        assert_eq!(Encoding::of(&value, previous), Encoding::ListPack);
+    }
+
+    #[test]
+    fn test_frequency() {
+        let start = 1_700_000_000_000;
+        let meta = Metadata::new(Encoding::EmbStr, start);
+        assert_eq!(meta.frequency(start), 5);
+
+        // the first increments always happen
+        meta.touch(start, 0.0);
+        assert_eq!(meta.frequency(start), 6);
+
+        // and then get less likely
+        meta.touch(start, 0.5);
+        assert_eq!(meta.frequency(start), 6);
+
+        assert_eq!(meta.frequency(start + MINUTE), 5);
+        assert_eq!(meta.frequency(start + 10 * MINUTE), 0);
+    }
+
+    #[test]
+    fn test_idle_time() {
+        let start = 1_700_000_000_000;
+        let meta = Metadata::new(Encoding::EmbStr, start);
+        assert_eq!(meta.idle_time(start), 0);
+        assert_eq!(meta.idle_time(start + 12_345), 12_000);
+
+        meta.touch(start + 20_000, 0.9);
+        assert_eq!(meta.idle_time(start + 23_000), 3000);
+    }
+
+    #[test]
+    fn test_refcount() {
+        assert_eq!(refcount(&string("42")), i32::MAX as i64);
+        assert_eq!(refcount(&string("10000")), 1);
+        assert_eq!(refcount(&string("hello")), 1);
+    }
+}
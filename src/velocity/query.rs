@@ -1,14 +1,20 @@
-use super::command::Command;
+use super::{client::Client, command::Command};
 
 #[derive(Debug)]
 pub struct Query {
     pub command_str: String,
     pub command_enum: Command,
     pub args: Vec<String>,
+    /*
+     * every argument after the command name (`command_str` followed by
+     * `args`) exactly as the client sent it. the strings above are lossy, so
+     * values that may hold arbitrary bytes (SET, PFADD) are read from here
+     */
+    pub raw_args: Vec<Vec<u8>>,
 }
 
 impl Query {
-    pub fn new(query: &str) -> Query {
+    pub fn new(query: &[u8]) -> Query {
         /*
          * Query Example
          * -------------
@@ -23,75 +29,79 @@ impl Query {
          * NX
          */
 
-        let mut query = query.split("\r\n");
-        let data_type = query.next(); // *len
-
-        match data_type {
-            Some(data) => {
-                if !data.starts_with("*") {
-                    return Query {
-                        command_str: "query is not an array".to_string(),
-                        command_enum: Command::Unknown,
-                        args: vec![],
-                    };
-                }
-            }
-
-            None => {
-                return Query {
-                    command_str: "query is not an array".to_string(),
-                    command_enum: Command::Unknown,
-                    args: vec![],
-                };
-            }
-        }
-
-        // remove argument length from RESP2 query
-        let _ = query.next().unwrap(); // $len
-        let command_str = query.next();
-
-        let command = match command_str {
-            Some(command_str) => Command::from_str(command_str),
-            None => {
+        let parts = match parse_array(query) {
+            Some(parts) if !parts.is_empty() => parts,
+            _ => {
                 return Query {
                     command_str: "query is not an array".to_string(),
                     command_enum: Command::Unknown,
                     args: vec![],
+                    raw_args: vec![],
                 };
             }
         };
 
-        // remove argument length from RESP2 query
-        let _ = query.next(); // $len
+        let command = Command::from_str(&String::from_utf8_lossy(&parts[0]));
+        let raw_args = parts[1..].to_vec();
 
-        let value = match query.next() {
-            Some(value) => value,
-            None => "",
-        };
+        let mut strings = raw_args
+            .iter()
+            .map(|arg| String::from_utf8_lossy(arg).into_owned());
 
-        // the command arguments are the rest of the query
-        let mut args: Vec<String> = vec![];
+        let command_str = strings.next().unwrap_or_default();
 
-        for (_, arg) in query.enumerate() {
-            // we do not need length of the query
-            if arg.starts_with("$") || arg.is_empty() {
-                continue;
-            }
-
-            args.push(arg.to_string());
-        }
+        // the command arguments are the rest of the query
+        let args: Vec<String> = strings.collect();
 
-        let query = Query {
-            command_str: value.to_string(),
+        Query {
+            command_str,
             command_enum: command,
             args,
-        };
-
-        query
+            raw_args,
+        }
     }
 
-    pub fn create_response(&self) -> Vec<u8> {
+    pub fn create_response(&self, client: &Client) -> Vec<u8> {
         let command = &self.command_enum;
-        command.create_response(self)
+        command.create_response(self, client)
     }
 }
+
+/*
+ * splits a RESP array of bulk strings into its elements. every element is
+ * taken by the length announced right before it, so it may be empty, start
+ * with "$" (XREAD ... STREAMS key $) or even contain "\r\n"
+ */
+fn parse_array(query: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let (header, mut rest) = split_line(query)?;
+    let count = parse_len(header.strip_prefix(b"*")?)?;
+
+    let mut parts = vec![];
+
+    for _ in 0..count {
+        let Some((len, tail)) = split_line(rest) else {
+            break;
+        };
+
+        let Some(len) = len.strip_prefix(b"$").and_then(parse_len) else {
+            break;
+        };
+
+        // a truncated read keeps whatever made it through
+        let part = &tail[..len.min(tail.len())];
+        parts.push(part.to_vec());
+
+        rest = tail.get(len + 2..).unwrap_or_default();
+    }
+
+    Some(parts)
+}
+
+fn split_line(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let end = data.windows(2).position(|window| window == b"\r\n")?;
+    Some((&data[..end], &data[end + 2..]))
+}
+
+fn parse_len(digits: &[u8]) -> Option<usize> {
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
@@ -0,0 +1,75 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+};
+
+/*
+ * cursor based iteration (HSCAN, SSCAN, ZSCAN)
+ * --------------------------------------------
+ * every element is ordered by a fixed hash of its name, and the cursor is the
+ * next hash value that has not been visited yet. an element keeps the same
+ * hash for as long as it exists, so:
+ *
+ * * an element present during the whole iteration is returned exactly once
+ * * elements added or removed during the iteration may or may not be returned
+ *
+ * which are the guarantees redis gives for SCAN, without the duplicates that
+ * come from rehashing
+ *
+ * a cursor of `0` starts a new iteration, and is returned once it is over
+ */
+
+fn hash_of(name: &str) -> u64 {
+    // `DefaultHasher::new()` always uses the same keys, unlike `RandomState`
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish()
+}
+
+/*
+ * the names of a collection ordered by their hash. it is kept up to date
+ * along with the collection, so a call resumes right at the cursor and only
+ * costs what it returns
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ScanIndex {
+    names: BTreeSet<(u64, String)>,
+}
+
+impl ScanIndex {
+    pub fn new() -> ScanIndex {
+        ScanIndex::default()
+    }
+
+    pub fn insert(&mut self, name: &str) {
+        self.names.insert((hash_of(name), name.to_string()));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.names.remove(&(hash_of(name), name.to_string()));
+    }
+
+    /*
+     * returns the next cursor and (at least) `count` names following `cursor`.
+     * more names may be returned when several of them share the same hash, as
+     * they can not be told apart by the next cursor
+     */
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&str>) {
+        let mut names = self.names.range((cursor, String::new())..).peekable();
+        let mut found = vec![];
+
+        while let Some((hash, name)) = names.next() {
+            found.push(name.as_str());
+
+            match names.peek() {
+                Some((next, _)) if found.len() >= count && next != hash => {
+                    return (*next, found);
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        (0, found)
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use super::super::scan::*;
+    use std::collections::HashSet;
+
+    fn index_of(names: &[String]) -> ScanIndex {
+        let mut index = ScanIndex::new();
+
+        for name in names {
+            index.insert(name);
+        }
+
+        index
+    }
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("field:{}", i)).collect()
+    }
+
+    #[test]
+    fn test_scan_everything_at_once() {
+        let index = index_of(&names(10));
+        let (cursor, found) = index.scan(0, 100);
+
+        assert_eq!(cursor, 0);
+        assert_eq!(found.len(), 10);
+    }
+
+    #[test]
+    fn test_scan_returns_every_element_once() {
+        let index = index_of(&names(1000));
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut cursor = 0;
+        let mut calls = 0;
+
+        loop {
+            let (next, found) = index.scan(cursor, 10);
+
+            // a call returns what was asked for, not more
+            assert!(found.len() <= 11);
+
+            for name in found {
+                // no duplicates
+                assert!(seen.insert(name.to_string()));
+            }
+
+            calls += 1;
+            cursor = next;
+
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        assert_eq!(seen.len(), 1000);
+        assert!(calls >= 100);
+    }
+
+    #[test]
+    fn test_scan_while_modifying() {
+        let mut items = names(500);
+        let mut index = index_of(&items);
+        let stable: HashSet<String> = items.iter().step_by(2).cloned().collect();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut cursor = 0;
+        let mut round = 0;
+
+        loop {
+            let (next, found) = index.scan(cursor, 7);
+            seen.extend(found.into_iter().map(|name| name.to_string()));
+
+            // remove some of the odd elements and add new ones in between calls
+            round += 1;
+
+            for name in items.iter() {
+                if !stable.contains(name) && name.ends_with(&format!("{}", round % 10)) {
+                    index.remove(name);
+                }
+            }
+
+            items.retain(|n| stable.contains(n) || !n.ends_with(&format!("{}", round % 10)));
+            items.push(format!("new:{}", round));
+            index.insert(&format!("new:{}", round));
+
+            cursor = next;
+
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        assert!(stable.is_subset(&seen));
+    }
+
+    #[test]
+    fn test_scan_removed() {
+        let mut index = index_of(&names(3));
+        index.remove("field:1");
+        index.remove("field:7");
+
+        let (cursor, found) = index.scan(0, 10);
+        assert_eq!(cursor, 0);
+        assert_eq!(found.len(), 2);
+        assert!(!found.contains(&"field:1"));
+    }
+}
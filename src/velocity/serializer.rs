@@ -16,7 +16,28 @@ pub fn bulk_str(message: &str) -> Vec<u8> {
     response.to_owned()
 }
 
-pub fn str_arr(list: &Vec<String>) -> Vec<u8> {
+// a bulk string holding arbitrary bytes, which are sent untouched
+pub fn bulk_bytes(data: &[u8]) -> Vec<u8> {
+    let mut response = format!("${}\r\n", data.len()).into_bytes();
+
+    response.extend_from_slice(data);
+    response.extend_from_slice(b"\r\n");
+
+    response
+}
+
+// an array of bulk strings holding arbitrary bytes
+pub fn bytes_arr(list: &[Vec<u8>]) -> Vec<u8> {
+    let mut response = format!("*{}\r\n", list.len()).into_bytes();
+
+    for item in list {
+        response.extend_from_slice(&bulk_bytes(item));
+    }
+
+    response
+}
+
+pub fn str_arr(list: &[String]) -> Vec<u8> {
     let mut values = vec![];
 
     for item in list {
@@ -58,3 +79,27 @@ pub fn nil() -> Vec<u8> {
 
     response.to_owned()
 }
+
+pub fn null_arr() -> Vec<u8> {
+    let value = resp::Value::NullArray;
+    let encoded = resp::encode(&value);
+    let response: String = String::from_utf8_lossy(&encoded).into_owned();
+    let response = response.as_bytes();
+
+    response.to_owned()
+}
+
+/*
+ * builds an array out of already serialized items. this is used for replies
+ * that mix types (e.g. `[key, value]` pairs, nested arrays, nils in between)
+ * and thus can not be expressed through `str_arr`
+ */
+pub fn arr(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut response = format!("*{}\r\n", items.len()).into_bytes();
+
+    for item in items {
+        response.extend_from_slice(item);
+    }
+
+    response
+}
@@ -32,24 +32,37 @@ mod tests {
         assert_eq!(bulk_str("\\r\\n"), b"$4\r\n\\r\\n\r\n");
     }
 
+    #[test]
+    fn test_bulk_bytes() {
+        assert_eq!(bulk_bytes(b""), b"$0\r\n\r\n");
+
+        // bytes that are not valid UTF-8 go through untouched
+        assert_eq!(bulk_bytes(b"\xff\r\n\x00"), b"$4\r\n\xff\r\n\x00\r\n");
+    }
+
+    #[test]
+    fn test_bytes_arr() {
+        assert_eq!(bytes_arr(&[]), b"*0\r\n");
+        assert_eq!(
+            bytes_arr(&[b"a".to_vec(), b"\xff\x00".to_vec()]),
+            b"*2\r\n$1\r\na\r\n$2\r\n\xff\x00\r\n"
+        );
+    }
+
     #[test]
     fn test_str_arr() {
         // Test with an empty array
-        assert_eq!(str_arr(&vec![]), b"*0\r\n");
+        assert_eq!(str_arr(&[]), b"*0\r\n");
 
         // Test with an array of strings
         assert_eq!(
-            str_arr(&vec![
-                "One".to_string(),
-                "Two".to_string(),
-                "Three".to_string()
-            ]),
+            str_arr(&["One".to_string(), "Two".to_string(), "Three".to_string()]),
             b"*3\r\n$3\r\nOne\r\n$3\r\nTwo\r\n$5\r\nThree\r\n"
         );
 
         // Test with an array of empty strings
         assert_eq!(
-            str_arr(&vec!["".to_string(), "".to_string(), "".to_string()]),
+            str_arr(&["".to_string(), "".to_string(), "".to_string()]),
             b"*3\r\n$0\r\n\r\n$0\r\n\r\n$0\r\n\r\n"
         );
     }
@@ -89,4 +102,28 @@ mod tests {
         // Test with nil
         assert_eq!(nil(), b"$-1\r\n");
     }
+
+    #[test]
+    fn test_null_arr() {
+        // Test with null array
+        assert_eq!(null_arr(), b"*-1\r\n");
+    }
+
+    #[test]
+    fn test_arr() {
+        // Test with an empty array
+        assert_eq!(arr(&[]), b"*0\r\n");
+
+        // Test with mixed item types
+        assert_eq!(
+            arr(&[bulk_str("key"), int(3), nil()]),
+            b"*3\r\n$3\r\nkey\r\n:3\r\n$-1\r\n"
+        );
+
+        // Test with a nested array
+        assert_eq!(
+            arr(&[arr(&[int(1)]), null_arr()]),
+            b"*2\r\n*1\r\n:1\r\n*-1\r\n"
+        );
+    }
 }
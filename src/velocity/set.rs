@@ -0,0 +1,169 @@
+use indexmap::IndexSet;
+use rand::Rng;
+use std::borrow::Cow;
+
+use super::{intset::IntSet, scan::ScanIndex, settings::SETTINGS};
+
+/*
+ * value of a set key. small sets made only of integers are kept as an
+ * `IntSet`, and converted to a `HashSet` the first time a member that is not
+ * an integer is added, or when they grow past `set_max_intset_entries`. a set
+ * never goes back to being an `IntSet`
+ *
+ * the hash set is an `IndexSet`, so that a random member can be picked in
+ * O(1) (SPOP, SRANDMEMBER), along with its members in scan order (SSCAN)
+ */
+#[derive(Debug, Clone)]
+pub enum Set {
+    IntSet(IntSet),
+    HashSet(IndexSet<String>, ScanIndex),
+}
+
+/*
+ * only members that are written exactly the way redis would print the integer
+ * ("12", not "012" or "+12") can go in an intset, so that they can be turned
+ * back into the very same string
+ */
+fn as_integer(member: &str) -> Option<i64> {
+    let value = member.parse::<i64>().ok()?;
+
+    if value.to_string() == member {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+impl Default for Set {
+    fn default() -> Self {
+        Set::new()
+    }
+}
+
+impl Set {
+    pub fn new() -> Set {
+        Set::IntSet(IntSet::new())
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Set::IntSet(set) => set.len(),
+            Set::HashSet(set, _) => set.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Set::IntSet(set) => set.is_empty(),
+            Set::HashSet(set, _) => set.is_empty(),
+        }
+    }
+
+    pub fn contains(&self, member: &str) -> bool {
+        match self {
+            Set::IntSet(set) => match as_integer(member) {
+                Some(value) => set.contains(value),
+                None => false,
+            },
+            Set::HashSet(set, _) => set.contains(member),
+        }
+    }
+
+    fn convert_to_hash_set(&mut self) {
+        if let Set::IntSet(set) = self {
+            let members: IndexSet<String> = set.iter().map(|value| value.to_string()).collect();
+            let mut index = ScanIndex::new();
+
+            for member in members.iter() {
+                index.insert(member);
+            }
+
+            *self = Set::HashSet(members, index);
+        }
+    }
+
+    // returns `false` if `member` was already in the set
+    pub fn insert(&mut self, member: String) -> bool {
+        if let Set::IntSet(set) = self {
+            match as_integer(&member) {
+                Some(value) if set.contains(value) => return false,
+                Some(value) if set.len() < SETTINGS.set_max_intset_entries => {
+                    return set.insert(value);
+                }
+                _ => self.convert_to_hash_set(),
+            }
+        }
+
+        match self {
+            Set::HashSet(set, index) => {
+                if set.contains(&member) {
+                    return false;
+                }
+
+                index.insert(&member);
+                set.insert(member)
+            }
+            Set::IntSet(_) => unreachable!(),
+        }
+    }
+
+    // returns `false` if `member` was not in the set
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self {
+            Set::IntSet(set) => match as_integer(member) {
+                Some(value) => set.remove(value),
+                None => false,
+            },
+            Set::HashSet(set, index) => {
+                index.remove(member);
+                set.swap_remove(member)
+            }
+        }
+    }
+
+    // `index` must be lower than `len()`
+    pub fn member_at(&self, index: usize) -> Cow<'_, str> {
+        match self {
+            Set::IntSet(set) => Cow::Owned(set.get(index).to_string()),
+            Set::HashSet(set, _) => Cow::Borrowed(set.get_index(index).unwrap()),
+        }
+    }
+
+    pub fn random_member(&self, rng: &mut impl Rng) -> Option<Cow<'_, str>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.member_at(rng.gen_range(0..self.len())))
+    }
+
+    pub fn pop_random(&mut self, rng: &mut impl Rng) -> Option<String> {
+        let member = self.random_member(rng)?.into_owned();
+        self.remove(&member);
+
+        Some(member)
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match self {
+            Set::IntSet(set) => Box::new(set.iter().map(|value| Cow::Owned(value.to_string()))),
+            Set::HashSet(set, _) => {
+                Box::new(set.iter().map(|member| Cow::Borrowed(member.as_str())))
+            }
+        }
+    }
+
+    /*
+     * the members following `cursor`, see scan.rs. an intset is small enough
+     * to be returned at once, like redis does
+     */
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<Cow<'_, str>>) {
+        match self {
+            Set::IntSet(_) => (0, self.iter().collect()),
+            Set::HashSet(_, index) => {
+                let (cursor, members) = index.scan(cursor, count);
+                (cursor, members.into_iter().map(Cow::Borrowed).collect())
+            }
+        }
+    }
+}
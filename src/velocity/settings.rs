@@ -0,0 +1,45 @@
+use config::{Config, File};
+use once_cell::sync::Lazy;
+
+/*
+ * server settings, read once from `src/config.toml`. every setting has a
+ * default, so the file (or any entry in it) may be missing
+ */
+#[derive(Debug)]
+pub struct Settings {
+    // sets of integers up to this size are stored as an intset
+    pub set_max_intset_entries: usize,
+
+    /*
+     * collections with at most this many entries, none of them longer than
+     * the `value` limit (in bytes), are reported as listpack encoded
+     */
+    pub hash_max_listpack_entries: usize,
+    pub hash_max_listpack_value: usize,
+    pub set_max_listpack_entries: usize,
+    pub set_max_listpack_value: usize,
+    pub zset_max_listpack_entries: usize,
+    pub zset_max_listpack_value: usize,
+}
+
+pub static SETTINGS: Lazy<Settings> = Lazy::new(|| {
+    let config = Config::builder()
+        .add_source(File::with_name("src/config").required(false))
+        .build()
+        .unwrap_or_default();
+
+    let get_usize = |key: &str, default: usize| match config.get_int(key) {
+        Ok(value) if value >= 0 => value as usize,
+        _ => default,
+    };
+
+    Settings {
+        set_max_intset_entries: get_usize("set_max_intset_entries", 512),
+        hash_max_listpack_entries: get_usize("hash_max_listpack_entries", 128),
+        hash_max_listpack_value: get_usize("hash_max_listpack_value", 64),
+        set_max_listpack_entries: get_usize("set_max_listpack_entries", 128),
+        set_max_listpack_value: get_usize("set_max_listpack_value", 64),
+        zset_max_listpack_entries: get_usize("zset_max_listpack_entries", 128),
+        zset_max_listpack_value: get_usize("zset_max_listpack_value", 64),
+    }
+});
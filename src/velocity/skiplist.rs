@@ -0,0 +1,369 @@
+use rand::Rng;
+
+/*
+ * skiplist ordered by (score, member), as used by redis for sorted sets
+ * --------------------------------------------------------------------
+ * every level of a node stores, besides the next node on that level, its
+ * "span": how many nodes the link jumps over. summing the spans along the
+ * search path gives the rank of a node, so both "rank of member" and "member
+ * at rank" are O(log n)
+ *
+ * nodes live in a `Vec` and point at each other by index. the node at index
+ * `HEAD` is the header, which holds no member. freed slots are reused
+ */
+
+const MAX_LEVEL: usize = 32;
+const HEAD: usize = 0;
+
+// probability for a node to be promoted to the next level
+const P: f64 = 0.25;
+
+// handle to a node, only valid until the skiplist is modified
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    forward: Option<NodeId>,
+    span: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    member: String,
+    score: f64,
+    backward: Option<NodeId>,
+    levels: Vec<Level>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<NodeId>,
+    tail: Option<NodeId>,
+    len: usize,
+    level: usize,
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        SkipList::new()
+    }
+}
+
+fn random_level() -> usize {
+    let mut rng = rand::thread_rng();
+    let mut level = 1;
+
+    while level < MAX_LEVEL && rng.gen::<f64>() < P {
+        level += 1;
+    }
+
+    level
+}
+
+impl SkipList {
+    pub fn new() -> SkipList {
+        let head = Node {
+            member: String::new(),
+            score: 0.0,
+            backward: None,
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0,
+                };
+                MAX_LEVEL
+            ],
+        };
+
+        SkipList {
+            nodes: vec![head],
+            free: vec![],
+            tail: None,
+            len: 0,
+            level: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn member(&self, node: NodeId) -> &str {
+        &self.nodes[node].member
+    }
+
+    pub fn score(&self, node: NodeId) -> f64 {
+        self.nodes[node].score
+    }
+
+    pub fn first(&self) -> Option<NodeId> {
+        self.nodes[HEAD].levels[0].forward
+    }
+
+    pub fn last(&self) -> Option<NodeId> {
+        self.tail
+    }
+
+    pub fn next(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node].levels[0].forward
+    }
+
+    pub fn prev(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node].backward
+    }
+
+    fn forward(&self, node: NodeId, level: usize) -> Option<NodeId> {
+        self.nodes[node].levels[level].forward
+    }
+
+    fn span(&self, node: NodeId, level: usize) -> usize {
+        self.nodes[node].levels[level].span
+    }
+
+    // is `node` ordered before (score, member)?
+    fn is_before(&self, node: NodeId, score: f64, member: &str) -> bool {
+        let node = &self.nodes[node];
+        node.score < score || (node.score == score && node.member.as_str() < member)
+    }
+
+    fn is_at(&self, node: NodeId, score: f64, member: &str) -> bool {
+        let node = &self.nodes[node];
+        node.score == score && node.member == member
+    }
+
+    fn alloc(&mut self, member: String, score: f64, level: usize) -> NodeId {
+        let node = Node {
+            member,
+            score,
+            backward: None,
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0,
+                };
+                level
+            ],
+        };
+
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /*
+     * the last node of every level that is before (score, member), along
+     * with its rank
+     */
+    fn find_predecessors(
+        &self,
+        score: f64,
+        member: &str,
+    ) -> ([NodeId; MAX_LEVEL], [usize; MAX_LEVEL]) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut node = HEAD;
+
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+
+            while let Some(next) = self.forward(node, i) {
+                if !self.is_before(next, score, member) {
+                    break;
+                }
+
+                rank[i] += self.span(node, i);
+                node = next;
+            }
+
+            update[i] = node;
+        }
+
+        (update, rank)
+    }
+
+    // the caller must make sure `member` is not already in the list
+    pub fn insert(&mut self, score: f64, member: String) -> NodeId {
+        let (mut update, mut rank) = self.find_predecessors(score, &member);
+        let level = random_level();
+
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+
+            self.level = level;
+        }
+
+        let new = self.alloc(member, score, level);
+
+        for i in 0..level {
+            let previous = update[i];
+            let jumped = rank[0] - rank[i];
+
+            self.nodes[new].levels[i].forward = self.forward(previous, i);
+            self.nodes[new].levels[i].span = self.span(previous, i) - jumped;
+            self.nodes[previous].levels[i].forward = Some(new);
+            self.nodes[previous].levels[i].span = jumped + 1;
+        }
+
+        // the new node is jumped over by the levels it is not part of
+        for (i, previous) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[*previous].levels[i].span += 1;
+        }
+
+        self.nodes[new].backward = if update[0] == HEAD {
+            None
+        } else {
+            Some(update[0])
+        };
+
+        match self.forward(new, 0) {
+            Some(next) => self.nodes[next].backward = Some(new),
+            None => self.tail = Some(new),
+        }
+
+        self.len += 1;
+        new
+    }
+
+    // returns `false` when (score, member) is not in the list
+    pub fn remove(&mut self, score: f64, member: &str) -> bool {
+        let (update, _) = self.find_predecessors(score, member);
+
+        let node = match self.forward(update[0], 0) {
+            Some(node) if self.is_at(node, score, member) => node,
+            _ => return false,
+        };
+
+        for (i, previous) in update.iter().enumerate().take(self.level) {
+            if self.forward(*previous, i) == Some(node) {
+                self.nodes[*previous].levels[i].span =
+                    self.span(*previous, i) + self.span(node, i) - 1;
+                self.nodes[*previous].levels[i].forward = self.forward(node, i);
+            } else {
+                self.nodes[*previous].levels[i].span -= 1;
+            }
+        }
+
+        match self.forward(node, 0) {
+            Some(next) => self.nodes[next].backward = self.nodes[node].backward,
+            None => self.tail = self.nodes[node].backward,
+        }
+
+        while self.level > 1 && self.forward(HEAD, self.level - 1).is_none() {
+            self.level -= 1;
+        }
+
+        self.nodes[node].member = String::new();
+        self.nodes[node].levels = vec![];
+        self.free.push(node);
+        self.len -= 1;
+
+        true
+    }
+
+    // 0 based rank of (score, member), if it is in the list
+    pub fn rank(&self, score: f64, member: &str) -> Option<usize> {
+        let mut rank = 0;
+        let mut node = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(node, i) {
+                if self.is_before(next, score, member) || self.is_at(next, score, member) {
+                    rank += self.span(node, i);
+                    node = next;
+                } else {
+                    break;
+                }
+            }
+
+            if node != HEAD && self.is_at(node, score, member) {
+                return Some(rank - 1);
+            }
+        }
+
+        None
+    }
+
+    // node at the given 0 based rank
+    pub fn by_rank(&self, rank: usize) -> Option<NodeId> {
+        let target = rank + 1;
+        let mut traversed = 0;
+        let mut node = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(node, i) {
+                if traversed + self.span(node, i) > target {
+                    break;
+                }
+
+                traversed += self.span(node, i);
+                node = next;
+            }
+
+            if traversed == target {
+                return Some(node);
+            }
+        }
+
+        None
+    }
+
+    /*
+     * first node for which `is_after_start` is true. `is_after_start` must be
+     * false for a prefix of the list and true for the rest of it
+     */
+    pub fn first_where(&self, is_after_start: impl Fn(f64, &str) -> bool) -> Option<NodeId> {
+        let mut node = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(node, i) {
+                if is_after_start(self.score(next), self.member(next)) {
+                    break;
+                }
+
+                node = next;
+            }
+        }
+
+        self.forward(node, 0)
+    }
+
+    /*
+     * last node for which `is_before_end` is true. `is_before_end` must be
+     * true for a prefix of the list and false for the rest of it
+     */
+    pub fn last_where(&self, is_before_end: impl Fn(f64, &str) -> bool) -> Option<NodeId> {
+        let mut node = HEAD;
+
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.forward(node, i) {
+                if !is_before_end(self.score(next), self.member(next)) {
+                    break;
+                }
+
+                node = next;
+            }
+        }
+
+        if node == HEAD {
+            None
+        } else {
+            Some(node)
+        }
+    }
+}
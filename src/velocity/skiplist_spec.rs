@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    use super::super::skiplist::*;
+
+    fn members(list: &SkipList) -> Vec<(String, f64)> {
+        std::iter::successors(list.first(), |node| list.next(*node))
+            .map(|node| (list.member(node).to_string(), list.score(node)))
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut list = SkipList::new();
+
+        list.insert(2.0, "b".to_string());
+        list.insert(1.0, "z".to_string());
+        list.insert(2.0, "a".to_string());
+        list.insert(-1.5, "c".to_string());
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(
+            members(&list),
+            vec![
+                ("c".to_string(), -1.5),
+                ("z".to_string(), 1.0),
+                ("a".to_string(), 2.0),
+                ("b".to_string(), 2.0),
+            ]
+        );
+        assert_eq!(list.member(list.last().unwrap()), "b");
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut list = SkipList::new();
+
+        for i in 0..10 {
+            list.insert(i as f64, i.to_string());
+        }
+
+        assert!(list.remove(3.0, "3"));
+        assert!(!list.remove(3.0, "3"));
+        assert!(!list.remove(4.0, "5"));
+        assert!(list.remove(9.0, "9"));
+
+        assert_eq!(list.len(), 8);
+        assert_eq!(list.member(list.last().unwrap()), "8");
+        assert_eq!(list.member(list.prev(list.last().unwrap()).unwrap()), "7");
+    }
+
+    #[test]
+    fn test_rank_matches_position() {
+        let mut list = SkipList::new();
+
+        // enough members to get several levels, inserted out of order
+        for i in 0..1000 {
+            let value = (i * 7919) % 1000;
+            list.insert(value as f64, format!("m{}", value));
+        }
+
+        for i in (0..1000).step_by(3) {
+            list.remove(i as f64, &format!("m{}", i));
+        }
+
+        let expected: Vec<i32> = (0..1000).filter(|i| i % 3 != 0).collect();
+        assert_eq!(list.len(), expected.len());
+
+        for (rank, value) in expected.iter().enumerate() {
+            let member = format!("m{}", value);
+
+            assert_eq!(list.rank(*value as f64, &member), Some(rank));
+            assert_eq!(list.member(list.by_rank(rank).unwrap()), member);
+        }
+
+        assert_eq!(list.rank(3.0, "m3"), None);
+        assert!(list.by_rank(expected.len()).is_none());
+    }
+
+    #[test]
+    fn test_first_and_last_where() {
+        let mut list = SkipList::new();
+
+        for i in 0..100 {
+            list.insert(i as f64, i.to_string());
+        }
+
+        let first = list.first_where(|score, _| score >= 42.5).unwrap();
+        assert_eq!(list.member(first), "43");
+
+        let last = list.last_where(|score, _| score <= 42.5).unwrap();
+        assert_eq!(list.member(last), "42");
+
+        assert!(list.first_where(|score, _| score > 99.0).is_none());
+        assert!(list.last_where(|score, _| score < 0.0).is_none());
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use super::{
+    scan::ScanIndex,
+    skiplist::{NodeId, SkipList},
+};
+
+/*
+ * value of a sorted set key. the scores are kept twice: in a dictionary, to
+ * look up the score of a member in O(1), and in a skiplist ordered by
+ * (score, member), for everything that depends on the order (ranks, ranges,
+ * popping the lowest/highest members). the members are also kept in scan
+ * order (ZSCAN)
+ */
+#[derive(Debug, Clone, Default)]
+pub struct SortedSet {
+    scores: HashMap<String, f64>,
+    list: SkipList,
+    index: ScanIndex,
+}
+
+/*
+ * a contiguous range of (score, member) pairs: every pair is either before
+ * the range, in it, or after it
+ */
+pub trait Range {
+    fn is_above_min(&self, score: f64, member: &str) -> bool;
+    fn is_below_max(&self, score: f64, member: &str) -> bool;
+}
+
+// one end of a score range. `exclusive` is set for the "(1.5" syntax
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreBound {
+    pub value: f64,
+    pub exclusive: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreRange {
+    pub min: ScoreBound,
+    pub max: ScoreBound,
+}
+
+impl Range for ScoreRange {
+    fn is_above_min(&self, score: f64, _: &str) -> bool {
+        if self.min.exclusive {
+            score > self.min.value
+        } else {
+            score >= self.min.value
+        }
+    }
+
+    fn is_below_max(&self, score: f64, _: &str) -> bool {
+        if self.max.exclusive {
+            score < self.max.value
+        } else {
+            score <= self.max.value
+        }
+    }
+}
+
+/*
+ * one end of a lexicographical range: "-", "+", "[member" (inclusive) or
+ * "(member" (exclusive). lex ranges only make sense when every member has the
+ * same score, as the members are then ordered by their bytes
+ */
+#[derive(Debug, Clone)]
+pub enum LexBound {
+    Lowest,
+    Highest,
+    Inclusive(String),
+    Exclusive(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct LexRange {
+    pub min: LexBound,
+    pub max: LexBound,
+}
+
+impl Range for LexRange {
+    fn is_above_min(&self, _: f64, member: &str) -> bool {
+        match &self.min {
+            LexBound::Lowest => true,
+            LexBound::Highest => false,
+            LexBound::Inclusive(min) => member >= min.as_str(),
+            LexBound::Exclusive(min) => member > min.as_str(),
+        }
+    }
+
+    fn is_below_max(&self, _: f64, member: &str) -> bool {
+        match &self.max {
+            LexBound::Lowest => false,
+            LexBound::Highest => true,
+            LexBound::Inclusive(max) => member <= max.as_str(),
+            LexBound::Exclusive(max) => member < max.as_str(),
+        }
+    }
+}
+
+impl SortedSet {
+    pub fn new() -> SortedSet {
+        SortedSet::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn score(&self, member: &str) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    // adds `member`, or updates its score. returns `false` if it was already there
+    pub fn insert(&mut self, member: String, score: f64) -> bool {
+        match self.scores.get_mut(&member) {
+            Some(current) if *current == score => false,
+            Some(current) => {
+                self.list.remove(*current, &member);
+                *current = score;
+                self.list.insert(score, member);
+                false
+            }
+            None => {
+                self.index.insert(&member);
+                self.scores.insert(member.clone(), score);
+                self.list.insert(score, member);
+                true
+            }
+        }
+    }
+
+    // returns `false` if `member` was not in the set
+    pub fn remove(&mut self, member: &str) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.index.remove(member);
+                self.list.remove(score, member)
+            }
+            None => false,
+        }
+    }
+
+    // 0 based position of `member`, from the lowest score or from the highest
+    pub fn rank(&self, member: &str, reverse: bool) -> Option<usize> {
+        let rank = self.list.rank(self.score(member)?, member)?;
+
+        if reverse {
+            Some(self.len() - 1 - rank)
+        } else {
+            Some(rank)
+        }
+    }
+
+    fn node_rank(&self, node: NodeId) -> usize {
+        let (score, member) = (self.list.score(node), self.list.member(node));
+        self.list.rank(score, member).unwrap()
+    }
+
+    fn pair(&self, node: NodeId) -> (String, f64) {
+        (self.list.member(node).to_string(), self.list.score(node))
+    }
+
+    // first and last nodes within `range`
+    fn range_ends(&self, range: &impl Range) -> Option<(NodeId, NodeId)> {
+        let first = self
+            .list
+            .first_where(|score, member| range.is_above_min(score, member))?;
+        let last = self
+            .list
+            .last_where(|score, member| range.is_below_max(score, member))?;
+
+        let (first_score, first_member) = (self.list.score(first), self.list.member(first));
+        let (last_score, last_member) = (self.list.score(last), self.list.member(last));
+
+        // min is above max
+        if !range.is_below_max(first_score, first_member)
+            || !range.is_above_min(last_score, last_member)
+        {
+            return None;
+        }
+
+        Some((first, last))
+    }
+
+    pub fn count_in_range(&self, range: &impl Range) -> usize {
+        match self.range_ends(range) {
+            Some((first, last)) => self.node_rank(last) - self.node_rank(first) + 1,
+            None => 0,
+        }
+    }
+
+    /*
+     * members from `start` to `stop` (both included), counting from the
+     * lowest score, or from the highest one when `reverse` is set. both must
+     * be lower than `len()`
+     */
+    pub fn range_by_rank(&self, start: usize, stop: usize, reverse: bool) -> Vec<(String, f64)> {
+        let first = if reverse {
+            self.len() - 1 - start
+        } else {
+            start
+        };
+
+        std::iter::successors(self.list.by_rank(first), |node| self.step(*node, reverse))
+            .take(stop + 1 - start)
+            .map(|node| self.pair(node))
+            .collect()
+    }
+
+    /*
+     * members within `range`, in ascending order (or descending, with
+     * `reverse`), skipping the first `offset` ones and returning at most
+     * `count` of them
+     */
+    pub fn range(
+        &self,
+        range: &impl Range,
+        reverse: bool,
+        offset: usize,
+        count: Option<usize>,
+    ) -> Vec<(String, f64)> {
+        let (first, last) = match self.range_ends(range) {
+            Some(ends) => ends,
+            None => return vec![],
+        };
+
+        let (mut node, end) = if reverse {
+            (last, first)
+        } else {
+            (first, last)
+        };
+        let mut skipped = 0;
+        let mut members = vec![];
+
+        loop {
+            if skipped < offset {
+                skipped += 1;
+            } else if count.is_some_and(|count| members.len() >= count) {
+                break;
+            } else {
+                members.push(self.pair(node));
+            }
+
+            if node == end {
+                break;
+            }
+
+            node = self.step(node, reverse).unwrap();
+        }
+
+        members
+    }
+
+    fn step(&self, node: NodeId, reverse: bool) -> Option<NodeId> {
+        if reverse {
+            self.list.prev(node)
+        } else {
+            self.list.next(node)
+        }
+    }
+
+    fn pop_node(&mut self, node: Option<NodeId>) -> Option<(String, f64)> {
+        let node = node?;
+        let member = self.list.member(node).to_string();
+        let score = self.list.score(node);
+
+        self.remove(&member);
+        Some((member, score))
+    }
+
+    // removes the member with the lowest score
+    pub fn pop_min(&mut self) -> Option<(String, f64)> {
+        self.pop_node(self.list.first())
+    }
+
+    // removes the member with the highest score
+    pub fn pop_max(&mut self) -> Option<(String, f64)> {
+        self.pop_node(self.list.last())
+    }
+
+    // member at the given 0 based rank, in O(log n)
+    pub fn member_at(&self, rank: usize) -> Option<(&str, f64)> {
+        let node = self.list.by_rank(rank)?;
+        Some((self.list.member(node), self.list.score(node)))
+    }
+
+    // the members following `cursor`, see scan.rs
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&str, f64)>) {
+        let (cursor, members) = self.index.scan(cursor, count);
+        let members = members
+            .into_iter()
+            .map(|member| (member, self.scores[member]));
+
+        (cursor, members.collect())
+    }
+
+    // (member, score) pairs, by ascending score
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> + '_ {
+        std::iter::successors(self.list.first(), |node| self.list.next(*node))
+            .map(|node| (self.list.member(node), self.list.score(node)))
+    }
+}
@@ -0,0 +1,848 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/*
+ * value of a stream key
+ * ---------------------
+ * entries are appended, in ID order, to "blocks": byte buffers holding up to
+ * `BLOCK_MAX_ENTRIES` entries (or about `BLOCK_MAX_BYTES`) back to back. the
+ * blocks are indexed by the ID of their first entry, the way redis indexes its
+ * listpacks with a radix tree
+ *
+ * inside a block every entry is laid out as:
+ *
+ *     flags | ms - block ms | seq | field count | (len | field | len | value)*
+ *
+ * all the numbers being varints, so that most IDs take 2 bytes
+ *
+ * deleting an entry only flags it. a block is dropped once every entry in it
+ * has been deleted
+ *
+ * unlike other collections, a stream stays around when it has no entries
+ * left, as it still holds its last ID
+ */
+
+const BLOCK_MAX_ENTRIES: usize = 100;
+const BLOCK_MAX_BYTES: usize = 4096;
+
+// how many entries "~" trimming removes at most when no LIMIT is given
+pub const APPROX_TRIM_LIMIT: usize = 100 * BLOCK_MAX_ENTRIES;
+
+const DELETED: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> StreamId {
+        StreamId { ms, seq }
+    }
+
+    // the smallest ID greater than this one
+    pub fn next(&self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    // the greatest ID smaller than this one
+    pub fn prev(&self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+pub type Fields = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub id: StreamId,
+    pub fields: Fields,
+}
+
+// the ID XADD was given
+#[derive(Debug, Clone, Copy)]
+pub enum IdSpec {
+    Auto,         // *
+    AutoSeq(u64), // ms-*
+    Explicit(StreamId),
+}
+
+#[derive(Debug)]
+pub enum IdErr {
+    TooSmall,
+    Exhausted,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TrimStrategy {
+    MaxLen(usize),
+    MinId(StreamId),
+}
+
+/*
+ * `approx` (the "~" modifier) only drops whole blocks, which is much cheaper.
+ * in that case at most `limit` entries are removed, 0 meaning no limit
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Trim {
+    pub strategy: TrimStrategy,
+    pub approx: bool,
+    pub limit: usize,
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+
+        buffer.push(byte | 0x80);
+    }
+}
+
+fn read_varint(buffer: &[u8], position: &mut usize) -> u64 {
+    let mut value = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = buffer[*position];
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return value;
+        }
+
+        shift += 7;
+    }
+}
+
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    write_varint(buffer, value.len() as u64);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn read_str(buffer: &[u8], position: &mut usize) -> String {
+    let len = read_varint(buffer, position) as usize;
+    let value = String::from_utf8_lossy(&buffer[*position..*position + len]).into_owned();
+    *position += len;
+
+    value
+}
+
+// where an entry sits in its block
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    offset: usize,
+    fields_at: usize,
+    id: StreamId,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    master: StreamId,
+    last: StreamId,
+    data: Vec<u8>,
+    entries: usize, // deleted ones included
+    live: usize,
+}
+
+impl Block {
+    fn new(master: StreamId) -> Block {
+        Block {
+            master,
+            last: master,
+            data: vec![],
+            entries: 0,
+            live: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries >= BLOCK_MAX_ENTRIES || self.data.len() >= BLOCK_MAX_BYTES
+    }
+
+    fn push(&mut self, id: StreamId, fields: &Fields) {
+        self.data.push(0);
+        write_varint(&mut self.data, id.ms - self.master.ms);
+        write_varint(&mut self.data, id.seq);
+        write_varint(&mut self.data, fields.len() as u64);
+
+        for (field, value) in fields {
+            write_str(&mut self.data, field);
+            write_str(&mut self.data, value);
+        }
+
+        self.last = id;
+        self.entries += 1;
+        self.live += 1;
+    }
+
+    // every entry of the block, deleted ones included, without their fields
+    fn slots(&self) -> Vec<Slot> {
+        let mut slots = Vec::with_capacity(self.entries);
+        let mut position = 0;
+
+        while position < self.data.len() {
+            let offset = position;
+            let deleted = self.data[position] & DELETED != 0;
+            position += 1;
+
+            let ms = self.master.ms + read_varint(&self.data, &mut position);
+            let seq = read_varint(&self.data, &mut position);
+            let fields_at = position;
+
+            // skip the fields and values
+            for _ in 0..read_varint(&self.data, &mut position) * 2 {
+                let len = read_varint(&self.data, &mut position) as usize;
+                position += len;
+            }
+
+            slots.push(Slot {
+                offset,
+                fields_at,
+                id: StreamId::new(ms, seq),
+                deleted,
+            });
+        }
+
+        slots
+    }
+
+    fn fields(&self, slot: &Slot) -> Fields {
+        let mut position = slot.fields_at;
+        let count = read_varint(&self.data, &mut position);
+
+        (0..count)
+            .map(|_| {
+                let field = read_str(&self.data, &mut position);
+                let value = read_str(&self.data, &mut position);
+                (field, value)
+            })
+            .collect()
+    }
+
+    fn delete(&mut self, slot: &Slot) {
+        self.data[slot.offset] |= DELETED;
+        self.live -= 1;
+    }
+}
+
+/*
+ * consumer groups
+ * ---------------
+ * a group remembers the last ID it handed out, and every entry it delivered
+ * that was not acknowledged yet: its pending entries list (PEL). a pending
+ * entry belongs to one consumer, which keeps the IDs of its own pending
+ * entries as well, so that both the PEL of the group and the one of a
+ * consumer can be walked in ID order
+ */
+
+#[derive(Debug, Clone)]
+pub struct PendingEntry {
+    pub consumer: String,
+    pub delivery_time: u64, // unix time in milliseconds
+    pub delivery_count: u64,
+}
+
+/*
+ * `seen_time` is the last time the consumer showed up, `active_time` the last
+ * time it was handed entries to process (never, for `None`)
+ */
+#[derive(Debug, Clone)]
+pub struct Consumer {
+    seen_time: u64,
+    active_time: Option<u64>,
+    pending: BTreeSet<StreamId>,
+}
+
+impl Consumer {
+    fn new(now: u64) -> Consumer {
+        Consumer {
+            seen_time: now,
+            active_time: None,
+            pending: BTreeSet::new(),
+        }
+    }
+
+    pub fn seen_time(&self) -> u64 {
+        self.seen_time
+    }
+
+    pub fn active_time(&self) -> Option<u64> {
+        self.active_time
+    }
+
+    pub fn pending(&self) -> &BTreeSet<StreamId> {
+        &self.pending
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConsumerGroup {
+    last_id: StreamId,
+    entries_read: Option<u64>, // `None` when it can not be known
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeMap<String, Consumer>,
+}
+
+impl ConsumerGroup {
+    fn new(last_id: StreamId, entries_read: Option<u64>) -> ConsumerGroup {
+        ConsumerGroup {
+            last_id,
+            entries_read,
+            pending: BTreeMap::new(),
+            consumers: BTreeMap::new(),
+        }
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn entries_read(&self) -> Option<u64> {
+        self.entries_read
+    }
+
+    // XGROUP SETID
+    pub fn set_last_id(&mut self, id: StreamId, entries_read: Option<u64>) {
+        self.last_id = id;
+        self.entries_read = entries_read;
+    }
+
+    pub fn pending(&self) -> &BTreeMap<StreamId, PendingEntry> {
+        &self.pending
+    }
+
+    pub fn consumers(&self) -> &BTreeMap<String, Consumer> {
+        &self.consumers
+    }
+
+    // returns `false` if the consumer already existed
+    pub fn create_consumer(&mut self, name: &str, now: u64) -> bool {
+        if self.consumers.contains_key(name) {
+            return false;
+        }
+
+        self.consumers.insert(name.to_string(), Consumer::new(now));
+        true
+    }
+
+    // records that the consumer showed up, creating it if needed
+    pub fn see_consumer(&mut self, name: &str, now: u64) -> &mut Consumer {
+        let consumer = self
+            .consumers
+            .entry(name.to_string())
+            .or_insert_with(|| Consumer::new(now));
+
+        consumer.seen_time = now;
+        consumer
+    }
+
+    /*
+     * deletes the consumer along with its pending entries. returns how many
+     * entries it had pending, `None` if it did not exist
+     */
+    pub fn delete_consumer(&mut self, name: &str) -> Option<usize> {
+        let consumer = self.consumers.remove(name)?;
+
+        for id in &consumer.pending {
+            self.pending.remove(id);
+        }
+
+        Some(consumer.pending.len())
+    }
+
+    /*
+     * makes `id` pending for `consumer` (created if needed), taking it over
+     * from its previous owner if it was already pending
+     */
+    pub fn claim(
+        &mut self,
+        id: StreamId,
+        consumer: &str,
+        delivery_time: u64,
+        delivery_count: u64,
+        now: u64,
+    ) {
+        if let Some(previous) = self.pending.get(&id) {
+            if let Some(owner) = self.consumers.get_mut(&previous.consumer) {
+                owner.pending.remove(&id);
+            }
+        }
+
+        let owner = self.see_consumer(consumer, now);
+        owner.active_time = Some(now);
+        owner.pending.insert(id);
+
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.to_string(),
+                delivery_time,
+                delivery_count,
+            },
+        );
+    }
+
+    // removes `id` from the PEL. returns `false` if it was not pending
+    pub fn ack(&mut self, id: StreamId) -> bool {
+        let entry = match self.pending.remove(&id) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+            consumer.pending.remove(&id);
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stream {
+    blocks: BTreeMap<StreamId, Block>,
+    len: usize,
+    last_id: StreamId,
+    max_deleted_id: StreamId,
+    entries_added: u64,
+    groups: BTreeMap<String, ConsumerGroup>,
+}
+
+impl Stream {
+    pub fn new() -> Stream {
+        Stream::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    pub fn max_deleted_id(&self) -> StreamId {
+        self.max_deleted_id
+    }
+
+    // how many entries were ever added, deleted ones included
+    pub fn entries_added(&self) -> u64 {
+        self.entries_added
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // XSETID
+    pub fn set_last_id(&mut self, id: StreamId) {
+        self.last_id = id;
+    }
+
+    pub fn set_max_deleted_id(&mut self, id: StreamId) {
+        self.max_deleted_id = id;
+    }
+
+    pub fn set_entries_added(&mut self, entries_added: u64) {
+        self.entries_added = entries_added;
+    }
+
+    // the ID of the next entry, following the rules of XADD
+    pub fn next_id(&self, spec: IdSpec, now_ms: u64) -> Result<StreamId, IdErr> {
+        let last = self.last_id;
+
+        match spec {
+            IdSpec::Auto if now_ms > last.ms => Ok(StreamId::new(now_ms, 0)),
+            IdSpec::Auto => last.next().ok_or(IdErr::Exhausted),
+            IdSpec::AutoSeq(ms) if ms > last.ms => Ok(StreamId::new(ms, 0)),
+            IdSpec::AutoSeq(ms) if ms == last.ms => match last.seq.checked_add(1) {
+                Some(seq) => Ok(StreamId::new(ms, seq)),
+                None => Err(IdErr::TooSmall),
+            },
+            IdSpec::AutoSeq(_) => Err(IdErr::TooSmall),
+            IdSpec::Explicit(id) if id > last => Ok(id),
+            IdSpec::Explicit(_) => Err(IdErr::TooSmall),
+        }
+    }
+
+    // `id` must be greater than `last_id()`
+    pub fn add(&mut self, id: StreamId, fields: &Fields) {
+        let needs_block = match self.blocks.last_key_value() {
+            Some((_, block)) => block.is_full(),
+            None => true,
+        };
+
+        if needs_block {
+            self.blocks.insert(id, Block::new(id));
+        }
+
+        self.blocks.last_entry().unwrap().get_mut().push(id, fields);
+        self.len += 1;
+        self.last_id = id;
+        self.entries_added += 1;
+    }
+
+    // the block that would hold `id`
+    fn block_of(&self, id: StreamId) -> Option<StreamId> {
+        self.blocks
+            .range(..=id)
+            .next_back()
+            .map(|(master, _)| *master)
+    }
+
+    /*
+     * entries from `start` to `end` (both included), in ascending order or
+     * descending with `reverse`, returning at most `count` of them
+     */
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        reverse: bool,
+    ) -> Vec<Entry> {
+        let mut entries = vec![];
+
+        if start > end {
+            return entries;
+        }
+
+        let count = count.unwrap_or(usize::MAX);
+        let first = self.block_of(start).unwrap_or(start);
+        let blocks = self.blocks.range(first..=end).map(|(_, block)| block);
+
+        let blocks: Box<dyn Iterator<Item = &Block>> = if reverse {
+            Box::new(blocks.rev())
+        } else {
+            Box::new(blocks)
+        };
+
+        for block in blocks {
+            let mut slots = block.slots();
+
+            if reverse {
+                slots.reverse();
+            }
+
+            for slot in slots {
+                if entries.len() >= count {
+                    return entries;
+                }
+
+                if slot.deleted || slot.id < start || slot.id > end {
+                    continue;
+                }
+
+                entries.push(Entry {
+                    id: slot.id,
+                    fields: block.fields(&slot),
+                });
+            }
+        }
+
+        entries
+    }
+
+    pub fn last_entry(&self) -> Option<Entry> {
+        self.range(StreamId::MIN, StreamId::MAX, Some(1), true)
+            .pop()
+    }
+
+    // returns `false` if there is no entry with this ID
+    pub fn remove(&mut self, id: StreamId) -> bool {
+        let master = match self.block_of(id) {
+            Some(master) => master,
+            None => return false,
+        };
+
+        let block = self.blocks.get_mut(&master).unwrap();
+        let slot = block
+            .slots()
+            .into_iter()
+            .find(|slot| slot.id == id && !slot.deleted);
+
+        let slot = match slot {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        block.delete(&slot);
+
+        if block.live == 0 {
+            self.blocks.remove(&master);
+        }
+
+        self.len -= 1;
+
+        if id > self.max_deleted_id {
+            self.max_deleted_id = id;
+        }
+
+        true
+    }
+
+    // returns how many entries were removed
+    pub fn trim(&mut self, trim: &Trim) -> usize {
+        let mut removed = 0;
+
+        while let Some((&master, block)) = self.blocks.first_key_value() {
+            let whole_block = match trim.strategy {
+                TrimStrategy::MaxLen(max) if self.len <= max => break,
+                TrimStrategy::MaxLen(max) => self.len - block.live >= max,
+                TrimStrategy::MinId(min) => block.last < min,
+            };
+
+            if whole_block {
+                if trim.limit != 0 && removed + block.live > trim.limit {
+                    break;
+                }
+
+                removed += block.live;
+                self.len -= block.live;
+                self.blocks.remove(&master);
+                continue;
+            }
+
+            if trim.approx {
+                break;
+            }
+
+            // only some of the entries of the oldest block have to go
+            let block = self.blocks.get_mut(&master).unwrap();
+
+            for slot in block.slots().iter().filter(|slot| !slot.deleted) {
+                let done = match trim.strategy {
+                    TrimStrategy::MaxLen(max) => self.len <= max,
+                    TrimStrategy::MinId(min) => slot.id >= min,
+                };
+
+                if done {
+                    break;
+                }
+
+                block.delete(slot);
+                self.len -= 1;
+                removed += 1;
+            }
+
+            if block.live == 0 {
+                self.blocks.remove(&master);
+            }
+
+            break;
+        }
+
+        removed
+    }
+
+    // the entry with the given ID, if it was not deleted
+    pub fn entry(&self, id: StreamId) -> Option<Entry> {
+        self.range(id, id, Some(1), false).pop()
+    }
+
+    pub fn first_entry(&self) -> Option<Entry> {
+        self.range(StreamId::MIN, StreamId::MAX, Some(1), false)
+            .pop()
+    }
+
+    // whether an entry at or after `start` was deleted
+    fn has_tombstones_from(&self, start: StreamId) -> bool {
+        !self.is_empty() && self.max_deleted_id != StreamId::MIN && start <= self.max_deleted_id
+    }
+
+    /*
+     * how many entries were added up to, and including, `id`. this is only
+     * known for sure when no entry was deleted from the middle of the stream
+     */
+    fn entries_added_until(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+
+        if self.is_empty() && id <= self.last_id {
+            return Some(self.entries_added);
+        }
+
+        match id.cmp(&self.last_id) {
+            Ordering::Equal => return Some(self.entries_added),
+            Ordering::Greater => return None,
+            Ordering::Less => {}
+        }
+
+        let first = self.first_entry()?.id;
+
+        if self.max_deleted_id != StreamId::MIN && self.max_deleted_id >= first {
+            return None;
+        }
+
+        match id.cmp(&first) {
+            Ordering::Less => Some(self.entries_added - self.len as u64),
+            Ordering::Equal => Some(self.entries_added - self.len as u64 + 1),
+            Ordering::Greater => None,
+        }
+    }
+
+    /*
+     * how many entries the group has yet to read. like `entries_added_until`,
+     * this is not always known
+     */
+    pub fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+
+        let entries_read = match group.entries_read {
+            Some(read) if !self.has_tombstones_from(group.last_id) => Some(read),
+            _ => self.entries_added_until(group.last_id),
+        };
+
+        entries_read.map(|read| self.entries_added.saturating_sub(read))
+    }
+
+    // consumer groups, by name
+    pub fn groups(&self) -> impl Iterator<Item = (&str, &ConsumerGroup)> {
+        self.groups
+            .iter()
+            .map(|(name, group)| (name.as_str(), group))
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    // returns `false` if there already is a group with this name
+    pub fn create_group(
+        &mut self,
+        name: &str,
+        last_id: StreamId,
+        entries_read: Option<u64>,
+    ) -> bool {
+        if self.groups.contains_key(name) {
+            return false;
+        }
+
+        let group = ConsumerGroup::new(last_id, entries_read);
+        self.groups.insert(name.to_string(), group);
+
+        true
+    }
+
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /*
+     * hands the entries after the last ID of `group` to `consumer`, making
+     * them pending unless `noack` is set. the group must exist
+     */
+    pub fn read_group(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        count: Option<usize>,
+        noack: bool,
+        now: u64,
+    ) -> Vec<Entry> {
+        let entries = match self.groups[group].last_id.next() {
+            Some(start) => self.range(start, StreamId::MAX, count, false),
+            None => vec![],
+        };
+
+        for entry in &entries {
+            let tombstones = self.has_tombstones_from(entry.id);
+            let added_until = self.entries_added_until(entry.id);
+            let group = self.groups.get_mut(group).unwrap();
+
+            // counting one more read is only right when nothing was skipped
+            group.entries_read = match group.entries_read {
+                Some(read) if !tombstones => Some(read + 1),
+                _ => added_until,
+            };
+            group.last_id = entry.id;
+
+            if !noack {
+                group.claim(entry.id, consumer, now, 1, now);
+            }
+        }
+
+        entries
+    }
+
+    /*
+     * delivers again the entries pending for `consumer` after `after`.
+     * entries that were deleted since come with no fields
+     */
+    pub fn read_pending(
+        &mut self,
+        group: &str,
+        consumer: &str,
+        after: StreamId,
+        count: Option<usize>,
+        now: u64,
+    ) -> Vec<(StreamId, Option<Fields>)> {
+        let ids: Vec<StreamId> = match self.groups[group].consumers.get(consumer) {
+            Some(consumer) => match after.next() {
+                Some(start) => consumer
+                    .pending
+                    .range(start..)
+                    .take(count.unwrap_or(usize::MAX))
+                    .copied()
+                    .collect(),
+                None => vec![],
+            },
+            None => vec![],
+        };
+
+        let entries = ids
+            .iter()
+            .map(|id| (*id, self.entry(*id).map(|entry| entry.fields)))
+            .collect();
+
+        let group = self.groups.get_mut(group).unwrap();
+
+        for id in &ids {
+            let pending = group.pending.get_mut(id).unwrap();
+            pending.delivery_time = now;
+            pending.delivery_count += 1;
+        }
+
+        entries
+    }
+}
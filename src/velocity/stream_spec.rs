@@ -0,0 +1,366 @@
+#[cfg(test)]
+mod tests {
+    use super::super::stream::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Fields {
+        pairs
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.to_string()))
+            .collect()
+    }
+
+    // a stream with the entries 1-0 .. n-0
+    fn stream_of(n: u64) -> Stream {
+        let mut stream = Stream::new();
+
+        for ms in 1..=n {
+            stream.add(StreamId::new(ms, 0), &fields(&[("n", &ms.to_string())]));
+        }
+
+        stream
+    }
+
+    fn ids(entries: &[Entry]) -> Vec<u64> {
+        entries.iter().map(|entry| entry.id.ms).collect()
+    }
+
+    fn all(stream: &Stream) -> Vec<u64> {
+        ids(&stream.range(StreamId::MIN, StreamId::MAX, None, false))
+    }
+
+    #[test]
+    fn test_add_and_range() {
+        let mut stream = Stream::new();
+
+        stream.add(StreamId::new(1, 0), &fields(&[("a", "1"), ("b", "")]));
+        stream.add(StreamId::new(1, 1), &fields(&[("c", "3")]));
+
+        assert_eq!(stream.len(), 2);
+        assert_eq!(stream.last_id(), StreamId::new(1, 1));
+        assert_eq!(
+            stream.range(StreamId::MIN, StreamId::MAX, None, false),
+            vec![
+                Entry {
+                    id: StreamId::new(1, 0),
+                    fields: fields(&[("a", "1"), ("b", "")]),
+                },
+                Entry {
+                    id: StreamId::new(1, 1),
+                    fields: fields(&[("c", "3")]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_across_blocks() {
+        let stream = stream_of(350);
+
+        assert_eq!(all(&stream), (1..=350).collect::<Vec<_>>());
+
+        let start = StreamId::new(95, 0);
+        let end = StreamId::new(205, 0);
+
+        assert_eq!(
+            ids(&stream.range(start, end, None, false)),
+            (95..=205).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            ids(&stream.range(start, end, Some(3), true)),
+            vec![205, 204, 203]
+        );
+        assert_eq!(
+            ids(&stream.range(end, start, None, false)),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut stream = stream_of(150);
+
+        assert!(stream.remove(StreamId::new(10, 0)));
+        assert!(!stream.remove(StreamId::new(10, 0)));
+        assert!(!stream.remove(StreamId::new(500, 0)));
+
+        assert_eq!(stream.len(), 149);
+        assert!(!all(&stream).contains(&10));
+
+        for ms in 1..=100 {
+            stream.remove(StreamId::new(ms, 0));
+        }
+
+        assert_eq!(stream.len(), 50);
+        assert_eq!(all(&stream), (101..=150).collect::<Vec<_>>());
+        assert_eq!(stream.last_entry().unwrap().id, StreamId::new(150, 0));
+
+        // the last ID is kept even once every entry is gone
+        for ms in 101..=150 {
+            stream.remove(StreamId::new(ms, 0));
+        }
+
+        assert!(stream.is_empty());
+        assert_eq!(stream.last_id(), StreamId::new(150, 0));
+    }
+
+    #[test]
+    fn test_trim_maxlen() {
+        let mut stream = stream_of(250);
+
+        let exact = Trim {
+            strategy: TrimStrategy::MaxLen(120),
+            approx: false,
+            limit: 0,
+        };
+
+        assert_eq!(stream.trim(&exact), 130);
+        assert_eq!(all(&stream), (131..=250).collect::<Vec<_>>());
+
+        // only whole blocks go, so a few more entries than asked remain
+        let mut stream = stream_of(250);
+
+        let approx = Trim {
+            strategy: TrimStrategy::MaxLen(120),
+            approx: true,
+            limit: APPROX_TRIM_LIMIT,
+        };
+
+        assert_eq!(stream.trim(&approx), 100);
+        assert_eq!(stream.len(), 150);
+
+        let limited = Trim {
+            strategy: TrimStrategy::MaxLen(0),
+            approx: true,
+            limit: 10,
+        };
+
+        assert_eq!(stream.trim(&limited), 0);
+        assert_eq!(stream.len(), 150);
+    }
+
+    #[test]
+    fn test_trim_minid() {
+        let mut stream = stream_of(250);
+
+        let trim = Trim {
+            strategy: TrimStrategy::MinId(StreamId::new(180, 0)),
+            approx: false,
+            limit: 0,
+        };
+
+        assert_eq!(stream.trim(&trim), 179);
+        assert_eq!(all(&stream), (180..=250).collect::<Vec<_>>());
+        assert_eq!(stream.trim(&trim), 0);
+    }
+
+    #[test]
+    fn test_next_id() {
+        let mut stream = Stream::new();
+
+        assert_eq!(
+            stream.next_id(IdSpec::Auto, 5).unwrap(),
+            StreamId::new(5, 0)
+        );
+        assert_eq!(
+            stream.next_id(IdSpec::AutoSeq(0), 5).unwrap(),
+            StreamId::new(0, 1)
+        );
+
+        stream.add(StreamId::new(10, 3), &fields(&[("a", "1")]));
+
+        // the clock going backwards does not go below the last ID
+        assert_eq!(
+            stream.next_id(IdSpec::Auto, 5).unwrap(),
+            StreamId::new(10, 4)
+        );
+        assert_eq!(
+            stream.next_id(IdSpec::AutoSeq(10), 5).unwrap(),
+            StreamId::new(10, 4)
+        );
+        assert_eq!(
+            stream.next_id(IdSpec::AutoSeq(11), 5).unwrap(),
+            StreamId::new(11, 0)
+        );
+        assert!(matches!(
+            stream.next_id(IdSpec::AutoSeq(9), 5),
+            Err(IdErr::TooSmall)
+        ));
+        assert!(matches!(
+            stream.next_id(IdSpec::Explicit(StreamId::new(10, 3)), 5),
+            Err(IdErr::TooSmall)
+        ));
+
+        stream.set_last_id(StreamId::MAX);
+
+        assert!(matches!(
+            stream.next_id(IdSpec::Auto, 5),
+            Err(IdErr::Exhausted)
+        ));
+    }
+
+    #[test]
+    fn test_id_neighbours() {
+        assert_eq!(StreamId::new(1, 5).next(), Some(StreamId::new(1, 6)));
+        assert_eq!(StreamId::new(1, u64::MAX).next(), Some(StreamId::new(2, 0)));
+        assert_eq!(StreamId::MAX.next(), None);
+
+        assert_eq!(StreamId::new(2, 0).prev(), Some(StreamId::new(1, u64::MAX)));
+        assert_eq!(StreamId::MIN.prev(), None);
+
+        assert_eq!(StreamId::new(12, 3).to_string(), "12-3");
+    }
+
+    #[test]
+    fn test_read_group() {
+        let mut stream = stream_of(5);
+
+        assert!(stream.create_group("g", StreamId::MIN, Some(0)));
+        assert!(!stream.create_group("g", StreamId::MIN, None));
+
+        let read = stream.read_group("g", "alice", Some(2), false, 100);
+        assert_eq!(ids(&read), vec![1, 2]);
+
+        let read = stream.read_group("g", "bob", None, false, 200);
+        assert_eq!(ids(&read), vec![3, 4, 5]);
+        assert!(stream.read_group("g", "bob", None, false, 300).is_empty());
+
+        let group = stream.group("g").unwrap();
+        assert_eq!(group.last_id(), StreamId::new(5, 0));
+        assert_eq!(group.entries_read(), Some(5));
+        assert_eq!(group.pending().len(), 5);
+        assert_eq!(group.consumers()["alice"].pending().len(), 2);
+
+        let entry = &group.pending()[&StreamId::new(3, 0)];
+        assert_eq!(entry.consumer, "bob");
+        assert_eq!(entry.delivery_time, 200);
+        assert_eq!(entry.delivery_count, 1);
+
+        // NOACK reads do not make the entries pending
+        stream.add(StreamId::new(6, 0), &fields(&[("n", "6")]));
+        stream.read_group("g", "carol", None, true, 400);
+
+        assert_eq!(stream.group("g").unwrap().pending().len(), 5);
+        assert!(stream.destroy_group("g"));
+        assert!(stream.group("g").is_none());
+    }
+
+    #[test]
+    fn test_read_pending() {
+        let mut stream = stream_of(3);
+
+        stream.create_group("g", StreamId::MIN, None);
+        stream.read_group("g", "alice", None, false, 100);
+        stream.remove(StreamId::new(2, 0));
+
+        let history = stream.read_pending("g", "alice", StreamId::new(1, 0), None, 500);
+
+        assert_eq!(
+            history,
+            vec![
+                (StreamId::new(2, 0), None),
+                (StreamId::new(3, 0), Some(fields(&[("n", "3")]))),
+            ]
+        );
+
+        let group = stream.group("g").unwrap();
+        let entry = &group.pending()[&StreamId::new(3, 0)];
+        assert_eq!(entry.delivery_time, 500);
+        assert_eq!(entry.delivery_count, 2);
+
+        assert!(stream
+            .read_pending("g", "nobody", StreamId::MIN, None, 500)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_claim_and_ack() {
+        let mut stream = stream_of(3);
+
+        stream.create_group("g", StreamId::MIN, None);
+        stream.read_group("g", "alice", None, false, 100);
+
+        let group = stream.group_mut("g").unwrap();
+        group.claim(StreamId::new(1, 0), "bob", 300, 4, 350);
+
+        assert_eq!(group.pending()[&StreamId::new(1, 0)].consumer, "bob");
+        assert_eq!(group.consumers()["alice"].pending().len(), 2);
+        assert_eq!(group.consumers()["bob"].pending().len(), 1);
+
+        assert!(group.ack(StreamId::new(2, 0)));
+        assert!(!group.ack(StreamId::new(2, 0)));
+        assert_eq!(group.consumers()["alice"].pending().len(), 1);
+
+        assert!(!group.create_consumer("bob", 400));
+        assert!(group.create_consumer("carol", 400));
+
+        assert_eq!(group.delete_consumer("alice"), Some(1));
+        assert_eq!(group.delete_consumer("alice"), None);
+        assert_eq!(
+            group.pending().keys().copied().collect::<Vec<_>>(),
+            vec![StreamId::new(1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_entries_read_with_deletions() {
+        let mut stream = stream_of(5);
+
+        stream.create_group("g", StreamId::MIN, None);
+
+        // the count can be worked out even if it was unknown
+        stream.read_group("g", "alice", Some(1), true, 0);
+        assert_eq!(stream.group("g").unwrap().entries_read(), Some(1));
+
+        // a gap ahead makes the count unknown
+        stream.remove(StreamId::new(3, 0));
+        stream.read_group("g", "alice", Some(1), true, 0);
+        assert_eq!(stream.group("g").unwrap().entries_read(), None);
+
+        // reading up to the last entry makes it known again
+        stream.read_group("g", "alice", None, true, 0);
+        assert_eq!(stream.group("g").unwrap().entries_read(), Some(5));
+    }
+
+    #[test]
+    fn test_lag() {
+        let mut stream = Stream::new();
+
+        stream.create_group("g", StreamId::MIN, None);
+        assert_eq!(stream.lag(stream.group("g").unwrap()), Some(0));
+
+        for ms in 1..=4 {
+            stream.add(StreamId::new(ms, 0), &fields(&[("n", "x")]));
+        }
+
+        stream.read_group("g", "alice", Some(1), false, 0);
+        assert_eq!(stream.lag(stream.group("g").unwrap()), Some(3));
+
+        // a deleted entry the group has yet to read makes the lag unknown
+        stream.remove(StreamId::new(3, 0));
+        assert_eq!(stream.lag(stream.group("g").unwrap()), None);
+
+        stream.read_group("g", "alice", None, false, 0);
+        assert_eq!(stream.lag(stream.group("g").unwrap()), Some(0));
+    }
+
+    #[test]
+    fn test_consumer_times() {
+        let mut stream = stream_of(2);
+
+        stream.create_group("g", StreamId::MIN, None);
+
+        let group = stream.group_mut("g").unwrap();
+        group.create_consumer("alice", 100);
+        group.see_consumer("alice", 200);
+
+        let alice = &group.consumers()["alice"];
+        assert_eq!(alice.seen_time(), 200);
+        assert_eq!(alice.active_time(), None);
+
+        stream.read_group("g", "alice", None, false, 300);
+
+        let alice = &stream.group("g").unwrap().consumers()["alice"];
+        assert_eq!(alice.seen_time(), 300);
+        assert_eq!(alice.active_time(), Some(300));
+    }
+}